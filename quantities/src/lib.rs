@@ -0,0 +1,366 @@
+//! Newtypes for physical quantities, in the spirit of the temperature types
+//! in u03's `tempr`. Each quantity is stored in its SI base unit; values go
+//! in and come out in any of its units, and only dimensionally sound
+//! arithmetic compiles: `Length / Speed` is a `Duration`, `Length + Speed`
+//! doesn't exist.
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// A value with its unit symbol, for display. Width and precision apply to
+/// the number: `{:8.1}` of a kilometre gives `     1.0 km`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InUnit {
+    value: f64,
+    symbol: &'static str,
+}
+
+impl fmt::Display for InUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match f.precision() {
+            Some(p) => format!("{:.*}", p, self.value),
+            None => self.value.to_string(),
+        };
+        let width = f.width().unwrap_or(0);
+        write!(f, "{:>width$} {}", value, self.symbol)
+    }
+}
+
+macro_rules! units {
+    ($(#[$doc:meta])* $unit:ident {
+        $($(#[$variant_doc:meta])* $name:ident => $factor:expr, $symbol:literal;)*
+    }) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $unit {
+            $($(#[$variant_doc])* $name),*
+        }
+
+        impl $unit {
+            /// How many of the SI base unit one of these is.
+            pub const fn factor(self) -> f64 {
+                match self {
+                    $($unit::$name => $factor),*
+                }
+            }
+
+            pub const fn symbol(self) -> &'static str {
+                match self {
+                    $($unit::$name => $symbol),*
+                }
+            }
+        }
+    };
+}
+
+units!(LengthUnit {
+    Meter => 1.0, "m";
+    Kilometer => 1e3, "km";
+    Mile => 1_609.344, "mi";
+    AstronomicalUnit => 149_597_870_700.0, "au";
+    /// Julian year at the speed of light.
+    LightYear => 9_460_730_472_580_800.0, "ly";
+});
+
+units!(DurationUnit {
+    Second => 1.0, "s";
+    Minute => 60.0, "min";
+    Hour => 3_600.0, "h";
+    Day => 86_400.0, "d";
+    /// A Julian year of 365.25 days.
+    Year => 31_557_600.0, "yr";
+});
+
+units!(SpeedUnit {
+    MeterPerSecond => 1.0, "m/s";
+    KilometerPerHour => 1e3 / 3_600.0, "km/h";
+    KilometerPerSecond => 1e3, "km/s";
+    MilePerHour => 0.447_04, "mph";
+    Knot => 1_852.0 / 3_600.0, "kn";
+    SpeedOfLight => 299_792_458.0, "c";
+});
+
+macro_rules! quantity {
+    ($(#[$doc:meta])* $name:ident, $unit:ident, $base:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+        pub struct $name(f64);
+
+        impl $name {
+            pub const ZERO: $name = $name(0.0);
+
+            pub const fn new(value: f64, unit: $unit) -> Self {
+                Self(value * unit.factor())
+            }
+
+            pub const fn value_in(self, unit: $unit) -> f64 {
+                self.0 / unit.factor()
+            }
+
+            /// For display in `unit` rather than the SI base unit.
+            pub const fn in_unit(self, unit: $unit) -> InUnit {
+                InUnit {
+                    value: self.value_in(unit),
+                    symbol: unit.symbol(),
+                }
+            }
+
+            pub fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.in_unit($unit::$base), f)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.0 -= rhs.0;
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: f64) -> Self {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl Mul<$name> for f64 {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> $name {
+                $name(self * rhs.0)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = Self;
+
+            fn div(self, rhs: f64) -> Self {
+                Self(self.0 / rhs)
+            }
+        }
+
+        /// The ratio of two quantities of the same kind has no unit.
+        impl Div for $name {
+            type Output = f64;
+
+            fn div(self, rhs: Self) -> f64 {
+                self.0 / rhs.0
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                Self(iter.map(|q| q.0).sum())
+            }
+        }
+    };
+}
+
+quantity!(Length, LengthUnit, Meter);
+quantity!(
+    /// A span of time, which unlike `std::time::Duration` may be negative.
+    Duration,
+    DurationUnit,
+    Second
+);
+quantity!(Speed, SpeedUnit, MeterPerSecond);
+
+macro_rules! impl_product {
+    ($a:ident * $b:ident = $out:ident) => {
+        impl Mul<$b> for $a {
+            type Output = $out;
+
+            fn mul(self, rhs: $b) -> $out {
+                $out(self.0 * rhs.0)
+            }
+        }
+
+        impl Mul<$a> for $b {
+            type Output = $out;
+
+            fn mul(self, rhs: $a) -> $out {
+                $out(self.0 * rhs.0)
+            }
+        }
+
+        impl Div<$a> for $out {
+            type Output = $b;
+
+            fn div(self, rhs: $a) -> $b {
+                $b(self.0 / rhs.0)
+            }
+        }
+
+        impl Div<$b> for $out {
+            type Output = $a;
+
+            fn div(self, rhs: $b) -> $a {
+                $a(self.0 / rhs.0)
+            }
+        }
+    };
+}
+
+impl_product!(Speed * Duration = Length);
+
+impl From<std::time::Duration> for Duration {
+    fn from(d: std::time::Duration) -> Self {
+        Duration(d.as_secs_f64())
+    }
+}
+
+impl Duration {
+    /// `None` if negative or too long for a `std::time::Duration`.
+    pub fn to_std(self) -> Option<std::time::Duration> {
+        std::time::Duration::try_from_secs_f64(self.0).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-12 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn values_convert_between_units() {
+        let marathon = Length::new(42.195, LengthUnit::Kilometer);
+        assert_eq!(marathon.value_in(LengthUnit::Meter), 42_195.0);
+        assert!(close(
+            marathon.value_in(LengthUnit::Mile),
+            26.218_757_456_454_3
+        ));
+        assert!(close(
+            Length::new(1.0, LengthUnit::LightYear).value_in(LengthUnit::AstronomicalUnit),
+            63_241.077_084_266_28
+        ));
+
+        let year = Duration::new(1.0, DurationUnit::Year);
+        assert_eq!(year.value_in(DurationUnit::Day), 365.25);
+        assert_eq!(
+            Duration::new(90.0, DurationUnit::Minute).value_in(DurationUnit::Hour),
+            1.5
+        );
+
+        assert!(close(
+            Speed::new(100.0, SpeedUnit::KilometerPerHour).value_in(SpeedUnit::MeterPerSecond),
+            27.777_777_777_777_78
+        ));
+        assert!(close(
+            Speed::new(1.0, SpeedUnit::Knot).value_in(SpeedUnit::KilometerPerHour),
+            1.852
+        ));
+        assert!(close(
+            Speed::new(60.0, SpeedUnit::MilePerHour).value_in(SpeedUnit::KilometerPerHour),
+            96.560_64
+        ));
+    }
+
+    #[test]
+    fn in_unit_formats_the_number_then_the_symbol() {
+        let km = Length::new(1.0, LengthUnit::Kilometer);
+        assert_eq!(km.in_unit(LengthUnit::Kilometer).to_string(), "1 km");
+        assert_eq!(
+            format!("{:8.1}", km.in_unit(LengthUnit::Kilometer)),
+            "     1.0 km"
+        );
+        assert_eq!(format!("{:.2}", km.in_unit(LengthUnit::Mile)), "0.62 mi");
+        assert_eq!(km.to_string(), "1000 m");
+        assert_eq!(
+            format!("{:.0}", Speed::new(36.0, SpeedUnit::KilometerPerHour)),
+            "10 m/s"
+        );
+        assert_eq!(
+            Duration::new(-2.0, DurationUnit::Minute).to_string(),
+            "-120 s"
+        );
+    }
+
+    #[test]
+    fn speed_times_duration_is_length_and_back() {
+        let speed = Speed::new(90.0, SpeedUnit::KilometerPerHour);
+        let time = Duration::new(20.0, DurationUnit::Minute);
+        let distance = speed * time;
+        assert!(close(distance.value_in(LengthUnit::Kilometer), 30.0));
+        assert_eq!(time * speed, distance);
+        assert!(close(
+            (distance / speed).value_in(DurationUnit::Minute),
+            20.0
+        ));
+        assert!(close(
+            (distance / time).value_in(SpeedUnit::KilometerPerHour),
+            90.0
+        ));
+
+        let light =
+            Speed::new(1.0, SpeedUnit::SpeedOfLight) * Duration::new(1.0, DurationUnit::Year);
+        assert_eq!(light, Length::new(1.0, LengthUnit::LightYear));
+    }
+
+    #[test]
+    fn same_kind_arithmetic() {
+        let legs = [3.0, 4.5, 2.5].map(|km| Length::new(km, LengthUnit::Kilometer));
+        let total: Length = legs.iter().copied().sum();
+        assert_eq!(total, Length::new(10.0, LengthUnit::Kilometer));
+        assert_eq!(total / legs[0] * 3.0, 10.0);
+        assert_eq!(
+            -(legs[1] - legs[0]).abs(),
+            Length::new(-1.5, LengthUnit::Kilometer)
+        );
+        assert_eq!(2.0 * legs[2], legs[2] + legs[2]);
+        assert!(legs[0] < legs[1]);
+
+        let mut left = total;
+        left -= legs[0];
+        left += Length::ZERO;
+        assert_eq!(left, Length::new(7.0, LengthUnit::Kilometer));
+    }
+
+    #[test]
+    fn std_durations_convert_both_ways() {
+        let d = Duration::from(std::time::Duration::from_millis(1500));
+        assert_eq!(d.value_in(DurationUnit::Second), 1.5);
+        assert_eq!(d.to_std(), Some(std::time::Duration::from_millis(1500)));
+        assert_eq!((-d).to_std(), None);
+    }
+}
@@ -1,3 +1,4 @@
+use quantities::{DurationUnit, Length, LengthUnit, Speed, SpeedUnit};
 use rand::Rng;
 
 fn main() {
@@ -25,14 +26,13 @@ fn mars_tickets(rng: &mut impl Rng) {
     const ROUND_TRIP: &str = "Round-trip";
     println!("Spaceline         Days Trip type   Price");
     println!("========================================");
-    const DIST: f64 = 62_100_000.0; // km
-    const SECS_PER_DAY: f64 = 24.0 * 60.0 * 60.0; // s
+    const DIST: Length = Length::new(62_100_000.0, LengthUnit::Kilometer);
     for _ in 0..10 {
         let spaceline = LINES[rng.gen_range(0..LINES.len())];
         let trip_type = if rng.gen() { ROUND_TRIP } else { ONE_WAY };
-        let speed = rng.gen_range(16..=30) as f64; // km/s
-        let price = 36.0 + (speed - 16.0); // USD
-        let one_way_days = (DIST / speed / SECS_PER_DAY).ceil() as i32;
+        let speed = Speed::new(rng.gen_range(16..=30) as f64, SpeedUnit::KilometerPerSecond);
+        let price = 36.0 + (speed.value_in(SpeedUnit::KilometerPerSecond) - 16.0); // USD
+        let one_way_days = (DIST / speed).value_in(DurationUnit::Day).ceil() as i32;
         let days = match trip_type {
             ONE_WAY => one_way_days,
             ROUND_TRIP => 2 * one_way_days,
@@ -1,5 +1,6 @@
 use chrono::{TimeZone, Utc};
-use num::BigInt;
+use num::{BigInt, ToPrimitive};
+use quantities::{Length, LengthUnit};
 use rand::prelude::SliceRandom;
 
 pub fn run() {
@@ -83,13 +84,20 @@ fn chars_exp() {
 }
 
 fn canis_distance() {
+    println!("{}", canis_report());
+}
+
+fn canis_report() -> String {
     let canis_distance = BigInt::parse_bytes(b"236000000000000000", 10).unwrap();
-    let light_year = BigInt::from(365) * 24 * 60 * 60 * 300_000; // km
-    let canis_ly = &canis_distance / light_year;
-    println!(
-        "Canis Major is {} km ({} light years) away from us.",
-        canis_distance, canis_ly
+    let canis = Length::new(
+        canis_distance.to_f64().unwrap_or(f64::INFINITY),
+        LengthUnit::Kilometer,
     );
+    format!(
+        "Canis Major is {} km ({:.0} light years) away from us.",
+        canis_distance,
+        canis.value_in(LengthUnit::LightYear)
+    )
 }
 
 fn integral_piggy_bank() {
@@ -121,3 +129,18 @@ fn floating_piggy_bank() {
     }
     println!("Finally! Piggy is at ${:5.2}.", piggy);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canis_distance_survives_the_exact_light_year() {
+        // a Julian light year rather than 365 days at 300 000 km/s moves the
+        // distance from 24945.04 to 24945.22 light years, which rounds the same
+        assert_eq!(
+            canis_report(),
+            "Canis Major is 236000000000000000 km (24945 light years) away from us."
+        );
+    }
+}
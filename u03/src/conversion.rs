@@ -0,0 +1,297 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ranges::{StepRange, StepRangeError};
+use crate::tables::{Style, TableWriter};
+use crate::tempr::{Celsius, Fahrenheit, Kelvin, Temperature, TemperatureError};
+
+/// Tables longer than this are almost certainly a typo in the step.
+pub const MAX_ROWS: usize = 100_000;
+
+/// A temperature scale picked at run time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    Kelvin,
+    Celsius,
+    Fahrenheit,
+}
+
+impl Scale {
+    pub const ALL: [Scale; 3] = [Scale::Kelvin, Scale::Celsius, Scale::Fahrenheit];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Scale::Kelvin => Kelvin::SYMBOL,
+            Scale::Celsius => Celsius::SYMBOL,
+            Scale::Fahrenheit => Fahrenheit::SYMBOL,
+        }
+    }
+
+    /// `t` on this scale, as long as it's a real temperature.
+    pub fn check(self, t: f64) -> Result<f64, TemperatureError> {
+        match self {
+            Scale::Kelvin => Kelvin::check(t).map(|t| t.as_f64()),
+            Scale::Celsius => Celsius::check(t).map(|t| t.as_f64()),
+            Scale::Fahrenheit => Fahrenheit::check(t).map(|t| t.as_f64()),
+        }
+    }
+
+    /// `t` on this scale, expressed on `to`.
+    pub fn convert(self, t: f64, to: Scale) -> f64 {
+        let kelvin = match self {
+            Scale::Kelvin => Kelvin::new_unchecked(t).to_kelvin(),
+            Scale::Celsius => Celsius::new_unchecked(t).to_kelvin(),
+            Scale::Fahrenheit => Fahrenheit::new_unchecked(t).to_kelvin(),
+        };
+        match to {
+            Scale::Kelvin => kelvin.as_f64(),
+            Scale::Celsius => Celsius::from_kelvin(kelvin).as_f64(),
+            Scale::Fahrenheit => Fahrenheit::from_kelvin(kelvin).as_f64(),
+        }
+    }
+
+    /// The other scales, in the order the default tables list them.
+    pub fn others(self) -> Vec<Scale> {
+        match self {
+            Scale::Kelvin => vec![Scale::Celsius, Scale::Fahrenheit],
+            Scale::Celsius => vec![Scale::Kelvin, Scale::Fahrenheit],
+            Scale::Fahrenheit => vec![Scale::Kelvin, Scale::Celsius],
+        }
+    }
+
+    /// From -40° (where Celsius and Fahrenheit meet) up to boiling water,
+    /// the way the old fixed tables covered it: `(begin, end, step)`.
+    pub fn default_range(self) -> (f64, f64, f64) {
+        match self {
+            Scale::Kelvin => (233.15, 373.15, 5.0),
+            Scale::Celsius => (-40.0, 100.0, 5.0),
+            Scale::Fahrenheit => (-40.0, 212.0, 12.0),
+        }
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scale::Kelvin => "kelvin",
+            Scale::Celsius => "celsius",
+            Scale::Fahrenheit => "fahrenheit",
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown temperature scale `{0}` (expected c, f or k)")]
+pub struct UnknownScale(String);
+
+impl FromStr for Scale {
+    type Err = UnknownScale;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().trim_start_matches('°') {
+            "k" | "kelvin" => Ok(Scale::Kelvin),
+            "c" | "celsius" => Ok(Scale::Celsius),
+            "f" | "fahrenheit" => Ok(Scale::Fahrenheit),
+            _ => Err(UnknownScale(s.to_string())),
+        }
+    }
+}
+
+/// How the rows of a table are spaced out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spacing {
+    /// Every `step` degrees, ending on `end` if a whole number of steps
+    /// lands there.
+    Step(f64),
+    /// This many rows, evenly spaced, from `begin` to `end`.
+    Count(usize),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConversionTableError {
+    #[error(transparent)]
+    Temperature(#[from] TemperatureError),
+    #[error(transparent)]
+    Range(#[from] StepRangeError),
+    #[error("a table needs at least one row")]
+    NoRows,
+    #[error(
+        "a table of {0} rows is too long (the most is {MAX_ROWS}); use a bigger step or fewer rows"
+    )]
+    TooManyRows(usize),
+    #[error("a table needs at least one scale to convert to")]
+    NoTargets,
+}
+
+/// Temperatures on one scale alongside the same temperatures on others.
+#[derive(Debug, Clone)]
+pub struct ConversionTable {
+    from: Scale,
+    to: Vec<Scale>,
+    rows: StepRange<f64>,
+    precision: usize,
+}
+
+impl ConversionTable {
+    /// Checks that both ends are real temperatures on `from` and that the
+    /// spacing gets from one to the other in a sensible number of rows.
+    pub fn new(
+        from: Scale,
+        begin: f64,
+        end: f64,
+        spacing: Spacing,
+    ) -> Result<Self, ConversionTableError> {
+        let (begin, end) = (from.check(begin)?, from.check(end)?);
+        let rows = match spacing {
+            Spacing::Step(step) => StepRange::inclusive(begin, end, step)?,
+            Spacing::Count(0) => return Err(ConversionTableError::NoRows),
+            Spacing::Count(count) => StepRange::linspace(begin, end, count)?,
+        };
+        if rows.len() > MAX_ROWS {
+            return Err(ConversionTableError::TooManyRows(rows.len()));
+        }
+        Ok(Self {
+            from,
+            to: from.others(),
+            rows,
+            precision: 1,
+        })
+    }
+
+    /// The default table for `from`, as the binary prints with no options.
+    pub fn standard(from: Scale) -> Self {
+        let (begin, end, step) = from.default_range();
+        Self::new(from, begin, end, Spacing::Step(step)).expect("the default ranges are all valid")
+    }
+
+    pub fn to(mut self, to: &[Scale]) -> Result<Self, ConversionTableError> {
+        if to.is_empty() {
+            return Err(ConversionTableError::NoTargets);
+        }
+        self.to = to.to_vec();
+        Ok(self)
+    }
+
+    /// Digits after the decimal point.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn table(&self, style: Style) -> TableWriter {
+        let headers: Vec<&str> = std::iter::once(self.from)
+            .chain(self.to.iter().copied())
+            .map(Scale::symbol)
+            .collect();
+        let mut table = TableWriter::new(&headers).style(style);
+        let p = self.precision;
+        table.extend_rows(self.rows.clone().map(|t| {
+            std::iter::once(format!("{:.*}", p, t))
+                .chain(
+                    self.to
+                        .iter()
+                        .map(|&to| format!("{:.*}", p, self.from.convert(t, to))),
+                )
+                .collect::<Vec<_>>()
+        }));
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_ranges_start_where_celsius_meets_fahrenheit() {
+        for scale in Scale::ALL {
+            let (begin, end, _) = scale.default_range();
+            assert!((scale.convert(begin, Scale::Celsius) + 40.0).abs() < 1e-9);
+            assert!((scale.convert(end, Scale::Celsius) - 100.0).abs() < 1e-9);
+            assert!(ConversionTable::new(scale, begin, end, Spacing::Count(3)).is_ok());
+        }
+    }
+
+    #[test]
+    fn a_table_needs_rows() {
+        assert!(matches!(
+            ConversionTable::new(Scale::Celsius, 0.0, 100.0, Spacing::Count(0)),
+            Err(ConversionTableError::NoRows)
+        ));
+        assert!(ConversionTable::new(Scale::Celsius, 0.0, 100.0, Spacing::Count(1)).is_ok());
+    }
+
+    #[test]
+    fn too_many_rows_is_an_error_for_either_spacing() {
+        let err =
+            ConversionTable::new(Scale::Celsius, 0.0, 1000.0, Spacing::Step(0.001)).unwrap_err();
+        assert!(matches!(err, ConversionTableError::TooManyRows(1_000_001)));
+        assert_eq!(
+            err.to_string(),
+            "a table of 1000001 rows is too long (the most is 100000); \
+             use a bigger step or fewer rows"
+        );
+        assert!(matches!(
+            ConversionTable::new(Scale::Kelvin, 0.0, 1.0, Spacing::Count(MAX_ROWS + 1)),
+            Err(ConversionTableError::TooManyRows(n)) if n == MAX_ROWS + 1
+        ));
+        assert!(ConversionTable::new(Scale::Kelvin, 0.0, 1.0, Spacing::Count(MAX_ROWS)).is_ok());
+    }
+
+    #[test]
+    fn both_ends_must_be_real_temperatures() {
+        assert!(matches!(
+            ConversionTable::new(Scale::Fahrenheit, -500.0, 0.0, Spacing::Step(10.0)),
+            Err(ConversionTableError::Temperature(
+                TemperatureError::BelowAbsoluteZero(..)
+            ))
+        ));
+        assert!(matches!(
+            ConversionTable::new(Scale::Kelvin, 10.0, -1.0, Spacing::Step(-1.0)),
+            Err(ConversionTableError::Temperature(
+                TemperatureError::BelowAbsoluteZero(..)
+            ))
+        ));
+        assert!(matches!(
+            ConversionTable::new(Scale::Celsius, 0.0, f64::INFINITY, Spacing::Count(5)),
+            Err(ConversionTableError::Temperature(
+                TemperatureError::NotFinite(..)
+            ))
+        ));
+    }
+
+    #[test]
+    fn bad_steps_are_range_errors() {
+        let table = |step| ConversionTable::new(Scale::Celsius, 0.0, 100.0, Spacing::Step(step));
+        assert!(matches!(
+            table(0.0),
+            Err(ConversionTableError::Range(StepRangeError::ZeroStep))
+        ));
+        assert!(matches!(
+            table(f64::NAN),
+            Err(ConversionTableError::Range(StepRangeError::NotFinite))
+        ));
+        assert!(matches!(
+            table(-5.0),
+            Err(ConversionTableError::Range(
+                StepRangeError::WrongDirection { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn targets_can_be_chosen_but_not_emptied() {
+        let table = ConversionTable::standard(Scale::Celsius);
+        assert!(matches!(
+            table.clone().to(&[]),
+            Err(ConversionTableError::NoTargets)
+        ));
+        let csv = table
+            .to(&[Scale::Fahrenheit])
+            .unwrap()
+            .table(Style::Csv)
+            .to_string();
+        assert!(csv.starts_with("C,°F\r\n-40.0,-40.0\r\n"));
+        assert!(csv.ends_with("100.0,212.0\r\n"));
+    }
+}
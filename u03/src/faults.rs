@@ -0,0 +1,479 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::ranges::Linear;
+use crate::sensors::{Reading, Sample, Sensor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpikeMethod {
+    /// Distance from the window mean in standard deviations.
+    ZScore,
+    /// Iglewicz and Hoaglin's modified z-score: distance from the window
+    /// median in median absolute deviations, scaled by 0.6745.
+    MedianAbsoluteDeviation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FaultKind {
+    /// The exact same value `repeats` times in a row.
+    StuckAt {
+        repeats: usize,
+    },
+    OutOfRange,
+    Spike {
+        method: SpikeMethod,
+        score: f64,
+    },
+    /// Changed faster than allowed since the previous reading.
+    RateOfChange {
+        per_second: f64,
+    },
+    /// Stayed within the flatline tolerance ever since `since`.
+    Flatline {
+        since: Duration,
+    },
+}
+
+/// A detection, along with the reading that triggered it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fault<T> {
+    pub kind: FaultKind,
+    pub reading: Reading<T>,
+}
+
+/// A reading together with whatever was found wrong with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotated<T> {
+    pub value: T,
+    pub faults: Vec<FaultKind>,
+}
+
+impl<T> Annotated<T> {
+    pub fn is_faulty(&self) -> bool {
+        !self.faults.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SpikeCheck {
+    method: SpikeMethod,
+    window: usize,
+    threshold: f64,
+}
+
+/// Runs the configured checks over a stream of readings.
+///
+/// Readings flagged as out of range or as spikes are kept out of the
+/// baseline the spike checks compare against, so one bad reading doesn't
+/// mask the next. A run of spikes as long as the longest window is taken
+/// as a shift in level instead, and becomes the new baseline. The spike
+/// checks only start once their window is full.
+#[derive(Debug, Clone, Default)]
+pub struct FaultDetector {
+    stuck_after: Option<usize>,
+    range: Option<(f64, f64)>,
+    spikes: Vec<SpikeCheck>,
+    max_rate: Option<f64>,
+    flatline: Option<(f64, Duration)>,
+
+    repeats: usize,
+    last: Option<(Duration, f64)>,
+    baseline: VecDeque<f64>,
+    // spikes in a row, held back in case they turn out to be a new level
+    shifted: Vec<f64>,
+    flat: Option<(Duration, f64, f64)>,
+}
+
+impl FaultDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flags a value repeated exactly `repeats` or more times in a row.
+    pub fn stuck_at(mut self, repeats: usize) -> Self {
+        self.stuck_after = Some(repeats.max(2));
+        self
+    }
+
+    pub fn range<T: Linear>(mut self, min: T, max: T) -> Self {
+        self.range = Some((min.to_f64(), max.to_f64()));
+        self
+    }
+
+    /// Flags readings more than `threshold` standard deviations from the
+    /// mean of the last `window` good readings.
+    pub fn z_score(self, window: usize, threshold: f64) -> Self {
+        self.spike(SpikeMethod::ZScore, window, threshold)
+    }
+
+    /// Flags readings whose modified z-score against the last `window`
+    /// good readings exceeds `threshold` (3.5 is the usual choice).
+    pub fn mad(self, window: usize, threshold: f64) -> Self {
+        self.spike(SpikeMethod::MedianAbsoluteDeviation, window, threshold)
+    }
+
+    pub fn max_rate<T: Linear>(mut self, per_second: T::Step) -> Self {
+        self.max_rate = Some(T::step_to_f64(per_second).abs());
+        self
+    }
+
+    /// Flags readings once the value has stayed within `tolerance` of
+    /// itself for at least `duration`.
+    pub fn flatline<T: Linear>(mut self, tolerance: T::Step, duration: Duration) -> Self {
+        self.flatline = Some((T::step_to_f64(tolerance).abs(), duration));
+        self
+    }
+
+    fn spike(mut self, method: SpikeMethod, window: usize, threshold: f64) -> Self {
+        self.spikes.push(SpikeCheck {
+            method,
+            window: window.max(2),
+            threshold,
+        });
+        self
+    }
+
+    /// Every fault found in `reading`, in the order the checks above are
+    /// listed.
+    pub fn check<T: Linear>(&mut self, reading: &Reading<T>) -> Vec<FaultKind> {
+        let (at, v) = (reading.at(), reading.value().to_f64());
+        let mut faults = Vec::new();
+
+        match self.last {
+            Some((_, last)) if last.to_bits() == v.to_bits() => self.repeats += 1,
+            _ => self.repeats = 1,
+        }
+        if let Some(after) = self.stuck_after {
+            if self.repeats >= after {
+                faults.push(FaultKind::StuckAt {
+                    repeats: self.repeats,
+                });
+            }
+        }
+
+        let mut outlier = false;
+        if let Some((min, max)) = self.range {
+            if !(min..=max).contains(&v) {
+                faults.push(FaultKind::OutOfRange);
+                outlier = true;
+            }
+        }
+
+        let mut spiked = false;
+        for check in &self.spikes {
+            if self.baseline.len() < check.window {
+                continue;
+            }
+            let window: Vec<f64> = self
+                .baseline
+                .iter()
+                .skip(self.baseline.len() - check.window)
+                .copied()
+                .collect();
+            let score = spike_score(check.method, &window, v);
+            if score > check.threshold {
+                faults.push(FaultKind::Spike {
+                    method: check.method,
+                    score,
+                });
+                spiked = true;
+            }
+        }
+        let longest = self.spikes.iter().map(|c| c.window).max().unwrap_or(0);
+        if spiked && !outlier {
+            self.shifted.push(v);
+            if self.shifted.len() >= longest {
+                self.baseline = self.shifted.drain(..).collect();
+            }
+        } else if !outlier {
+            self.shifted.clear();
+            self.baseline.push_back(v);
+            while self.baseline.len() > longest {
+                self.baseline.pop_front();
+            }
+        }
+
+        if let (Some(max_rate), Some((last_at, last))) = (self.max_rate, self.last) {
+            let dt = at.saturating_sub(last_at).as_secs_f64();
+            let per_second = if dt > 0.0 {
+                (v - last) / dt
+            } else if v == last {
+                0.0
+            } else {
+                (v - last).signum() * f64::INFINITY
+            };
+            if per_second.abs() > max_rate {
+                faults.push(FaultKind::RateOfChange { per_second });
+            }
+        }
+        self.last = Some((at, v));
+
+        if let Some((tolerance, duration)) = self.flatline {
+            let flat = match self.flat {
+                Some((since, lo, hi)) if hi.max(v) - lo.min(v) <= tolerance => {
+                    (since, lo.min(v), hi.max(v))
+                }
+                _ => (at, v, v),
+            };
+            self.flat = Some(flat);
+            if at.saturating_sub(flat.0) >= duration {
+                faults.push(FaultKind::Flatline { since: flat.0 });
+            }
+        }
+
+        faults
+    }
+}
+
+/// How far `v` is from the window.
+///
+/// When more than half the window holds one value its median absolute
+/// deviation is zero, so the mean absolute deviation scaled by 1.2533 (its
+/// ratio to the standard deviation for normal data) stands in. A window with
+/// no spread at all is given one of a few ulps of its centre, so every
+/// score stays finite.
+fn spike_score(method: SpikeMethod, window: &[f64], v: f64) -> f64 {
+    let n = window.len() as f64;
+    let (centre, distance, spread) = match method {
+        SpikeMethod::ZScore => {
+            let mean = window.iter().sum::<f64>() / n;
+            let var = window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            (mean, (v - mean).abs(), var.sqrt())
+        }
+        SpikeMethod::MedianAbsoluteDeviation => {
+            let med = median(window.to_vec());
+            let mad = median(window.iter().map(|x| (x - med).abs()).collect());
+            if mad != 0.0 {
+                (med, 0.6745 * (v - med).abs(), mad)
+            } else {
+                let mean_ad = window.iter().map(|x| (x - med).abs()).sum::<f64>() / n;
+                (med, (v - med).abs(), 1.2533 * mean_ad)
+            }
+        }
+    };
+    distance / spread.max(4.0 * f64::EPSILON * centre.abs().max(1.0))
+}
+
+fn median(mut xs: Vec<f64>) -> f64 {
+    xs.sort_by(f64::total_cmp);
+    let mid = xs.len() / 2;
+    if xs.len().is_multiple_of(2) {
+        (xs[mid - 1] + xs[mid]) / 2.0
+    } else {
+        xs[mid]
+    }
+}
+
+/// Holds back faulty readings, reporting them as `Sample::Missing`. See
+/// `Sensor::drop_faults`.
+pub struct DropFaults<S: Sensor> {
+    sensor: S,
+    detector: FaultDetector,
+    faults: Vec<Fault<S::Value>>,
+}
+
+impl<S: Sensor> DropFaults<S> {
+    pub(crate) fn new(sensor: S, detector: FaultDetector) -> Self {
+        Self {
+            sensor,
+            detector,
+            faults: Vec::new(),
+        }
+    }
+
+    /// The faults found since the last call, oldest first.
+    pub fn drain_faults(&mut self) -> Vec<Fault<S::Value>> {
+        std::mem::take(&mut self.faults)
+    }
+}
+
+impl<S> Sensor for DropFaults<S>
+where
+    S: Sensor,
+    S::Value: Linear,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        match self.sensor.sample(now) {
+            Sample::Reading(reading) => {
+                let faults = self.detector.check(&reading);
+                if faults.is_empty() {
+                    return Sample::Reading(reading);
+                }
+                self.faults
+                    .extend(faults.into_iter().map(|kind| Fault { kind, reading }));
+                Sample::Missing
+            }
+            sample => sample,
+        }
+    }
+}
+
+/// Passes every reading on along with the faults found in it. See
+/// `Sensor::annotate_faults`.
+pub struct AnnotateFaults<S> {
+    sensor: S,
+    detector: FaultDetector,
+}
+
+impl<S: Sensor> AnnotateFaults<S> {
+    pub(crate) fn new(sensor: S, detector: FaultDetector) -> Self {
+        Self { sensor, detector }
+    }
+}
+
+impl<S> Sensor for AnnotateFaults<S>
+where
+    S: Sensor,
+    S::Value: Linear,
+{
+    type Value = Annotated<S::Value>;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        let sample = self.sensor.sample(now);
+        if let Sample::Reading(reading) = sample {
+            let faults = self.detector.check(&reading);
+            sample.map(|value| Annotated { value, faults })
+        } else {
+            sample.map(|value| Annotated {
+                value,
+                faults: Vec::new(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spikes(detector: &mut FaultDetector, values: &[f64]) -> Vec<Option<f64>> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| {
+                let reading = Reading::new(Duration::from_secs(i as u64), v);
+                detector
+                    .check(&reading)
+                    .into_iter()
+                    .find_map(|kind| match kind {
+                        FaultKind::Spike { score, .. } => Some(score),
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zero_spread_scores_stay_finite() {
+        for method in [SpikeMethod::ZScore, SpikeMethod::MedianAbsoluteDeviation] {
+            assert_eq!(spike_score(method, &[5.0; 4], 5.0), 0.0);
+            let near = spike_score(method, &[5.0; 4], 5.001);
+            assert!(near.is_finite() && near > 1e9, "{method:?} {near}");
+            assert!(spike_score(method, &[5.0; 4], -5.0) > near);
+            // rounding error alone isn't a spike
+            assert!(spike_score(method, &[0.1; 4], 0.1 + f64::EPSILON) < 1.0);
+        }
+    }
+
+    #[test]
+    fn zero_mad_falls_back_to_mean_absolute_deviation() {
+        // half the window is on the median, so there's no spread about it
+        let window = [20.0, 20.0, 20.0, 21.0, 25.0];
+        let score = |v| spike_score(SpikeMethod::MedianAbsoluteDeviation, &window, v);
+        assert_eq!(score(20.0), 0.0);
+        // mean absolute deviation about the median is 6 / 5
+        assert!((score(21.0) - 1.0 / (1.2533 * 1.2)).abs() < 1e-12);
+        assert!(score(21.0) < 3.5);
+        assert!(score(30.0) > 3.5);
+    }
+
+    #[test]
+    fn a_flat_baseline_still_catches_spikes() {
+        let mut detector = FaultDetector::new().z_score(4, 3.0);
+        let scores = spikes(&mut detector, &[10.0, 10.0, 10.0, 10.0, 10.0, 80.0, 10.0]);
+        assert_eq!(scores[..5], [None; 5]);
+        assert!(scores[5].is_some_and(|z| z.is_finite() && z > 1e9));
+        assert_eq!(scores[6], None);
+
+        let mut detector = FaultDetector::new().mad(4, 3.5);
+        let scores = spikes(&mut detector, &[10.0, 10.0, 10.0, 10.0, 9.5, 10.0]);
+        assert!(scores[4].is_some());
+        assert_eq!(scores.iter().filter(|s| s.is_some()).count(), 1);
+    }
+
+    #[test]
+    fn a_level_shift_after_a_flat_run_becomes_the_baseline() {
+        let mut values = vec![10.0; 4];
+        values.extend([10.5; 10]);
+        for mut detector in [
+            FaultDetector::new().z_score(4, 3.0),
+            FaultDetector::new().mad(4, 3.5),
+        ] {
+            let flagged: Vec<bool> = spikes(&mut detector, &values)
+                .iter()
+                .map(Option::is_some)
+                .collect();
+            // flagged until a window's worth of the new level has been seen
+            assert_eq!(
+                flagged[..8],
+                [false, false, false, false, true, true, true, true]
+            );
+            assert!(flagged[8..].iter().all(|&f| !f));
+        }
+    }
+
+    #[test]
+    fn scattered_spikes_do_not_shift_the_baseline() {
+        let mut detector = FaultDetector::new().z_score(4, 3.0);
+        let values = [
+            10.0, 10.2, 9.9, 10.1, 50.0, 10.0, 50.0, 10.1, 50.0, 9.9, 50.0, 50.0,
+        ];
+        let flagged: Vec<bool> = spikes(&mut detector, &values)
+            .iter()
+            .map(Option::is_some)
+            .collect();
+        assert_eq!(
+            flagged,
+            [false, false, false, false, true, false, true, false, true, false, true, true]
+        );
+    }
+
+    #[test]
+    fn annotations_carry_each_readings_faults() {
+        let mut values = [20.0, 20.0, 20.0, 400.0].into_iter();
+        let mut sensor = (move || values.next().unwrap())
+            .annotate_faults(FaultDetector::new().stuck_at(3).range(0.0, 100.0));
+        let faults: Vec<Vec<FaultKind>> = (0..4)
+            .map(|i| {
+                sensor
+                    .sample(Duration::from_secs(i))
+                    .reading()
+                    .unwrap()
+                    .into_value()
+                    .faults
+            })
+            .collect();
+        assert_eq!(
+            faults,
+            [
+                vec![],
+                vec![],
+                vec![FaultKind::StuckAt { repeats: 3 }],
+                vec![FaultKind::OutOfRange],
+            ]
+        );
+    }
+
+    #[test]
+    fn scores_match_their_definitions() {
+        let window = [1.0, 2.0, 3.0, 4.0, 5.0];
+        // mean 3, sample standard deviation sqrt(2.5)
+        let z = spike_score(SpikeMethod::ZScore, &window, 8.0);
+        assert!((z - 5.0 / 2.5f64.sqrt()).abs() < 1e-12);
+        // median 3, median absolute deviation 1
+        let m = spike_score(SpikeMethod::MedianAbsoluteDeviation, &window, 8.0);
+        assert!((m - 0.6745 * 5.0).abs() < 1e-12);
+    }
+}
@@ -1,17 +1,53 @@
 use rand::Rng;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use u03::tempr::Kelvin;
+use u03::faults::FaultDetector;
+use u03::ranges::StepRange;
+use u03::recording::{self, Format, Recorder, Replay, Timing};
+use u03::sampler::{Sampler, SystemClock};
+use u03::sensors::{Sample, Sensor};
+use u03::stats::Stats;
+use u03::tempr::{Celsius, Delta, Kelvin, Temperature, Valid};
 
 pub fn run() {
-    let k = Kelvin::new(255.0);
+    let k = Kelvin::new_unchecked(255.0);
     println!("{} is {} and also {}", k, k.to_celsius(), k.to_fahrenheit());
+    checked_exploration();
     hofn_exploration();
+    sensor_exploration();
+    sampler_exploration();
+    recording_exploration();
+    faults_exploration();
+    stats_exploration();
+}
+
+pub fn checked_exploration() {
+    for t in [-5.0, f64::NAN, f64::INFINITY, 42.0] {
+        match Kelvin::new(t) {
+            Ok(k) => println!("ok: {}", k),
+            Err(err) => println!("rejected: {}", err),
+        }
+    }
+
+    let mut readings: BTreeMap<Valid<Celsius>, &str> = BTreeMap::new();
+    for (t, place) in [(21.5, "kitchen"), (-3.0, "porch"), (18.25, "attic")] {
+        match Valid::try_from(t) {
+            Ok(t) => {
+                readings.insert(t, place);
+            }
+            Err(err) => println!("rejected: {}", err),
+        }
+    }
+    for (t, place) in &readings {
+        println!("{:>10}: {}", place, t);
+    }
 }
 
 pub fn hofn_exploration() {
-    let always_42_sensor = || Kelvin::new(42.0);
+    let always_42_sensor = || Kelvin::new_unchecked(42.0);
 
     let mut sensor: fn() -> Kelvin = random_sensor;
     println!("-> {}", sensor());
@@ -19,25 +55,180 @@ pub fn hofn_exploration() {
     println!("-> {}", sensor());
     sensor = always_42_sensor;
     println!("-> {}", sensor());
-    sensor = || Kelvin::new(371.0);
+    sensor = || Kelvin::new_unchecked(371.0);
     println!("-> {}", sensor());
 
     measure_n_fptr(2, random_sensor);
     measure_n(2, random_sensor);
     measure_n_fptr(2, always_42_sensor);
     measure_n(2, always_42_sensor);
-    measure_n_fptr(2, || Kelvin::new(371.0));
-    measure_n(2, || Kelvin::new(371.0));
-    measure_n(2, calibrate(always_42_sensor, Kelvin::new(3.0)));
+    measure_n_fptr(2, || Kelvin::new_unchecked(371.0));
+    measure_n(2, || Kelvin::new_unchecked(371.0));
+    measure_n(2, calibrate(always_42_sensor, Kelvin::new_unchecked(3.0)));
 
-    let mut counting_sensor = mk_counting_sensor(Kelvin::new(3.0));
-    measure_n(2, calibrate(&mut counting_sensor, Kelvin::new(3.0)));
+    let mut counting_sensor = mk_counting_sensor(Kelvin::new_unchecked(3.0));
+    measure_n(
+        2,
+        calibrate(&mut counting_sensor, Kelvin::new_unchecked(3.0)),
+    );
     measure_n(2, &mut counting_sensor);
 }
 
-fn mk_counting_sensor(mut start: Kelvin) -> impl FnMut() -> Kelvin {
+pub fn sensor_exploration() {
+    // the same bare functions and closures as above, now with filters
+    let mut smoothed = random_sensor.smooth(0.25).convert::<Celsius>();
+    let mut stable = mk_counting_sensor(Kelvin::new_unchecked(270.0))
+        .calibrate(Delta::new(3.0), 1.0)
+        .median(3)
+        .clamp(Kelvin::new_unchecked(0.0), Kelvin::new_unchecked(275.0));
+    let mut flaky = (|| Kelvin::new_unchecked(42.0))
+        .noise(Delta::new(0.5), rand::thread_rng())
+        .dropout(0.3, rand::thread_rng());
+    let start = Instant::now();
+    for i in 0..4 {
+        let now = start.elapsed();
+        println!(
+            "{:02}: {:?} {:?} {:?}",
+            i,
+            smoothed.sample(now),
+            stable.sample(now),
+            flaky.sample(now)
+        );
+        thread::sleep(Duration::from_millis(125));
+    }
+}
+
+pub fn sampler_exploration() {
+    // unlike measure_n, every sensor runs on its own thread at its own rate
+    let mut sampler = Sampler::new(Arc::new(SystemClock::new()));
+    let events = sampler.subscribe();
+    let fast = sampler.add("random", random_sensor, Duration::from_millis(50), 16);
+    sampler.add(
+        "counting",
+        mk_counting_sensor(Kelvin::new_unchecked(3.0)),
+        Duration::from_millis(125),
+        16,
+    );
+    sampler.add(
+        "sluggish",
+        || {
+            thread::sleep(Duration::from_millis(300));
+            Kelvin::new_unchecked(42.0)
+        },
+        Duration::from_millis(100),
+        16,
+    );
+    thread::sleep(Duration::from_millis(500));
+    println!("latest fast reading: {:?}", sampler.latest(fast));
+    for stats in sampler.shutdown() {
+        println!("{:?}", stats);
+    }
+    for event in events {
+        println!("{:?}", event);
+    }
+}
+
+pub fn recording_exploration() {
+    let recorder = Recorder::new(Vec::new(), Format::Csv).unwrap();
+    let mut recorded = random_sensor.record("random", recorder);
+    for i in 0..4 {
+        recorded.sample(Duration::from_millis(125 * i));
+    }
+    let csv = recorded.finish().unwrap();
+    print!("{}", String::from_utf8_lossy(&csv));
+
+    // the same readings through the same pipeline give the same results
+    for _ in 0..2 {
+        let records = recording::load(&csv[..], Format::Csv).unwrap();
+        let mut replay = Replay::<Kelvin>::new(records, Some("random"), Timing::AsFastAsPossible)
+            .unwrap()
+            .calibrate(Delta::new(3.0), 1.0)
+            .moving_average(2);
+        loop {
+            match replay.sample(Duration::ZERO) {
+                Sample::Reading(reading) => print!("{} ", reading.value()),
+                Sample::Missing => continue,
+                Sample::Ended => break,
+            }
+        }
+        println!();
+    }
+}
+
+pub fn faults_exploration() {
+    let detector = || {
+        FaultDetector::new()
+            .stuck_at(3)
+            .range(Kelvin::new_unchecked(150.0), Kelvin::new_unchecked(300.0))
+            .mad(8, 3.5)
+            .max_rate::<Kelvin>(Delta::new(400.0))
+    };
+
+    let mut stuck = always_0_sensor.drop_faults(detector());
+    for i in 0..4 {
+        print!(
+            "{:?} ",
+            stuck.sample(Duration::from_millis(125 * i)).reading()
+        );
+    }
+    println!();
+    for fault in stuck.drain_faults() {
+        println!("{:?}", fault);
+    }
+
+    let mut spiky = random_sensor.annotate_faults(detector());
+    for i in 0..12 {
+        if let Some(reading) = spiky.sample(Duration::from_millis(125 * i)).reading() {
+            println!("{} {:?}", reading.value().value, reading.value().faults);
+        }
+    }
+}
+
+pub fn stats_exploration() {
+    let table: Stats<Celsius> = StepRange::inclusive(
+        Celsius::new_unchecked(-40.0),
+        Celsius::new_unchecked(100.0),
+        Delta::new(5.0),
+    )
+    .unwrap()
+    .collect();
+    println!(
+        "{:?} {:?} {:?}",
+        table.mean(),
+        table.std_dev(),
+        table.median()
+    );
+
+    // one accumulator per thread, merged at the end
+    let mut stats = Stats::<Kelvin>::new();
+    let parts: Vec<Stats<Kelvin>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                s.spawn(|| {
+                    (0..1000)
+                        .map(|i| random_sensor.sample(Duration::from_millis(i)))
+                        .filter_map(Sample::reading)
+                        .collect()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    for part in &parts {
+        stats.merge(part);
+    }
+    println!(
+        "{} readings, {:?}..{:?}, mean {:?}, p90 {:?}",
+        stats.count(),
+        stats.min(),
+        stats.max(),
+        stats.mean(),
+        stats.quantile(0.9)
+    );
+}
+
+pub fn mk_counting_sensor(mut start: Kelvin) -> impl FnMut() -> Kelvin {
     move || {
-        dbg!(start);
         start += 1.0;
         start
     }
@@ -47,12 +238,12 @@ fn calibrate(mut sensor: impl FnMut() -> Kelvin, offset: Kelvin) -> impl FnMut()
     move || sensor() + offset
 }
 
-fn random_sensor() -> Kelvin {
-    Kelvin::new(rand::thread_rng().gen_range(150.0..300.0))
+pub fn random_sensor() -> Kelvin {
+    Kelvin::new_unchecked(rand::thread_rng().gen_range(150.0..300.0))
 }
 
-fn always_0_sensor() -> Kelvin {
-    Kelvin::new(0.0)
+pub fn always_0_sensor() -> Kelvin {
+    Kelvin::new_unchecked(0.0)
 }
 
 fn measure_n(n: usize, mut sensor: impl FnMut() -> Kelvin) {
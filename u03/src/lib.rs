@@ -1,2 +1,10 @@
+pub mod conversion;
+pub mod faults;
+pub mod ranges;
+pub mod recording;
+pub mod sampler;
+pub mod sensors;
+pub mod stats;
 pub mod tables;
 pub mod tempr;
+pub mod weather;
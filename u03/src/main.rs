@@ -1,52 +1,97 @@
-use std::iter;
-use u03::tables;
-use u03::tempr::{Celsius, Fahrenheit};
+use clap::Parser;
+use std::process;
+use u03::conversion::{ConversionTable, Scale, Spacing};
+use u03::tables::Style;
 
 pub mod fnexplore;
 
+/// Prints temperature conversion tables.
+///
+/// With no scale, range or spacing options, prints the standard Celsius
+/// and Fahrenheit tables.
+#[derive(Parser)]
+struct Args {
+    /// Table style: plain, markdown, csv, html or box
+    #[arg(long, default_value_t)]
+    style: Style,
+
+    /// Scale to convert from: c, f or k [default: c]
+    #[arg(short, long)]
+    from: Option<Scale>,
+
+    /// Scales to convert to, comma separated [default: the other two]
+    #[arg(short, long, value_delimiter = ',')]
+    to: Vec<Scale>,
+
+    /// First and last temperature, as FROM..TO [default: depends on --from]
+    #[arg(short, long, value_parser = parse_range, allow_hyphen_values = true)]
+    range: Option<(f64, f64)>,
+
+    /// Degrees between rows [default: depends on --from]
+    #[arg(short, long, allow_negative_numbers = true, conflicts_with = "count")]
+    step: Option<f64>,
+
+    /// Number of evenly spaced rows, instead of a step
+    #[arg(short = 'n', long)]
+    count: Option<usize>,
+
+    /// Digits after the decimal point
+    #[arg(short, long, default_value_t = 1)]
+    precision: usize,
+}
+
+fn parse_range(s: &str) -> Result<(f64, f64), String> {
+    let (begin, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("`{}` isn't a range like -40..100", s))?;
+    let parse = |t: &str| {
+        t.trim()
+            .parse::<f64>()
+            .map_err(|e| format!("`{}` in `{}`: {}", t, s, e))
+    };
+    Ok((parse(begin)?, parse(end)?))
+}
+
 fn main() {
-    let mut temps =
-        stepped_range_inclusive(Celsius::new(-40.0), Celsius::new(100.0), Celsius::new(5.0));
-    tables::print_table(7, &["C", "K", "°F"], || {
-        temps.next().map(|t| {
-            vec![
-                format!("{:5.1}", t.as_f64()),
-                format!("{:5.1}", t.to_kelvin().as_f64()),
-                format!("{:5.1}", t.to_fahrenheit().as_f64()),
-            ]
-        })
-    });
+    let args = Args::parse();
+    let style = args.style;
+
+    let customised = args.from.is_some()
+        || !args.to.is_empty()
+        || args.range.is_some()
+        || args.step.is_some()
+        || args.count.is_some();
+    if !customised {
+        for from in [Scale::Celsius, Scale::Fahrenheit] {
+            print!("{}", ConversionTable::standard(from).table(style));
+        }
+        return;
+    }
 
-    let mut temps = stepped_range_inclusive(
-        Fahrenheit::new(-40.0),
-        Fahrenheit::new(212.0),
-        Fahrenheit::new(12.0),
-    );
-    tables::print_table(7, &["°F", "K", "C"], || {
-        temps.next().map(|t| {
-            vec![
-                format!("{:5.1}", t.as_f64()),
-                format!("{:5.1}", t.to_kelvin().as_f64()),
-                format!("{:5.1}", t.to_celsius().as_f64()),
-            ]
-        })
+    let from = args.from.unwrap_or(Scale::Celsius);
+    let (default_begin, default_end, default_step) = from.default_range();
+    let (begin, end) = args.range.unwrap_or((default_begin, default_end));
+    let spacing = match (args.step, args.count) {
+        (_, Some(count)) => Spacing::Count(count),
+        (Some(step), None) => Spacing::Step(step),
+        // count down by default when the range runs backwards
+        (None, None) => Spacing::Step(default_step.copysign(end - begin)),
+    };
+
+    let table = ConversionTable::new(from, begin, end, spacing).and_then(|table| {
+        if args.to.is_empty() {
+            Ok(table)
+        } else {
+            table.to(&args.to)
+        }
     });
+    match table {
+        Ok(table) => print!("{}", table.precision(args.precision).table(style)),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    }
 
     // fnexplore::run();
 }
-
-fn stepped_range_inclusive<T>(begin: T, end: T, step: T) -> impl Iterator<Item = T>
-where
-    T: std::ops::Add<Output = T> + PartialOrd<T> + Copy,
-{
-    iter::successors(
-        Some(begin),
-        move |&t| {
-            if t < end {
-                Some(t + step)
-            } else {
-                None
-            }
-        },
-    )
-}
@@ -0,0 +1,378 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+use crate::tempr::{Celsius, Delta, Fahrenheit, Kelvin, Temperature};
+
+/// Values that sit on a number line, stepped by `Step`.
+pub trait Linear: Copy {
+    type Step: Copy;
+
+    fn to_f64(self) -> f64;
+    fn from_f64(v: f64) -> Self;
+    fn step_to_f64(step: Self::Step) -> f64;
+    fn step_from_f64(v: f64) -> Self::Step;
+}
+
+macro_rules! impl_linear_for_floats {
+    ($($typ:ty),*) => {
+        $(impl Linear for $typ {
+            type Step = $typ;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_f64(v: f64) -> Self {
+                v as $typ
+            }
+
+            fn step_to_f64(step: Self::Step) -> f64 {
+                step as f64
+            }
+
+            fn step_from_f64(v: f64) -> Self::Step {
+                v as $typ
+            }
+        })*
+    };
+}
+
+impl_linear_for_floats!(f32, f64);
+
+macro_rules! impl_linear_for_temperatures {
+    ($($typ:ty),*) => {
+        $(impl Linear for $typ {
+            type Step = Delta<$typ>;
+
+            fn to_f64(self) -> f64 {
+                self.as_f64()
+            }
+
+            fn from_f64(v: f64) -> Self {
+                <$typ as Temperature>::new_unchecked(v)
+            }
+
+            fn step_to_f64(step: Self::Step) -> f64 {
+                step.degrees()
+            }
+
+            fn step_from_f64(v: f64) -> Self::Step {
+                Delta::new(v)
+            }
+        })*
+    };
+}
+
+impl_linear_for_temperatures!(Kelvin, Celsius, Fahrenheit);
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum StepRangeError {
+    #[error("range bounds and step must be finite")]
+    NotFinite,
+    #[error("step must not be zero")]
+    ZeroStep,
+    #[error("a step of {step:?} never gets from {begin:?} to {end:?}")]
+    WrongDirection { begin: f64, end: f64, step: f64 },
+    #[error("range from {begin:?} to {end:?} by {step:?} has too many steps")]
+    TooManySteps { begin: f64, end: f64, step: f64 },
+}
+
+// how close (relative to the step count) a float quotient has to be to a
+// whole number of steps for `end` to count as landed on
+const SNAP: f64 = 1e-9;
+
+/// An arithmetic progression whose `i`th value is `begin + i * step`, so
+/// rounding error doesn't build up the way repeated addition does.
+#[derive(Debug, Clone)]
+pub struct StepRange<T> {
+    begin: f64,
+    step: f64,
+    // when set, the last value is exactly this rather than begin + i * step
+    last: Option<f64>,
+    len: usize,
+    front: usize,
+    back: usize,
+    kind: PhantomData<T>,
+}
+
+impl<T: Linear> StepRange<T> {
+    /// From `begin` up to and including `end` when a whole number of steps
+    /// lands on it. `step` may be negative to count down.
+    pub fn inclusive(begin: T, end: T, step: T::Step) -> Result<Self, StepRangeError> {
+        let (begin, end, step) = (begin.to_f64(), end.to_f64(), T::step_to_f64(step));
+        let steps = Self::steps(begin, end, step)?;
+        let whole = steps.round();
+        let (len, last) = if (steps - whole).abs() <= SNAP * whole.max(1.0) {
+            (whole + 1.0, Some(end))
+        } else {
+            (steps.floor() + 1.0, None)
+        };
+        Self::with_len(begin, end, step, len, last)
+    }
+
+    /// From `begin` up to but excluding `end`.
+    pub fn exclusive(begin: T, end: T, step: T::Step) -> Result<Self, StepRangeError> {
+        let (begin, end, step) = (begin.to_f64(), end.to_f64(), T::step_to_f64(step));
+        let steps = Self::steps(begin, end, step)?;
+        let whole = steps.round();
+        let len = if (steps - whole).abs() <= SNAP * whole.max(1.0) {
+            whole
+        } else {
+            steps.ceil()
+        };
+        Self::with_len(begin, end, step, len, None)
+    }
+
+    /// `count` evenly spaced values from `begin` to `end`, both included.
+    pub fn linspace(begin: T, end: T, count: usize) -> Result<Self, StepRangeError> {
+        let (begin, end) = (begin.to_f64(), end.to_f64());
+        if !begin.is_finite() || !end.is_finite() {
+            return Err(StepRangeError::NotFinite);
+        }
+        let step = if count > 1 {
+            (end - begin) / (count - 1) as f64
+        } else {
+            0.0
+        };
+        Ok(Self {
+            begin,
+            step,
+            last: (count > 1).then_some(end),
+            len: count,
+            front: 0,
+            back: count,
+            kind: PhantomData,
+        })
+    }
+
+    /// The `i`th value of the whole range, regardless of how far it's been
+    /// iterated.
+    pub fn get(&self, i: usize) -> Option<T> {
+        (i < self.len).then(|| self.value(i))
+    }
+
+    fn steps(begin: f64, end: f64, step: f64) -> Result<f64, StepRangeError> {
+        if !begin.is_finite() || !end.is_finite() || !step.is_finite() {
+            return Err(StepRangeError::NotFinite);
+        }
+        if step == 0.0 {
+            return Err(StepRangeError::ZeroStep);
+        }
+        let steps = (end - begin) / step;
+        if steps < 0.0 {
+            return Err(StepRangeError::WrongDirection { begin, end, step });
+        }
+        Ok(steps)
+    }
+
+    fn with_len(
+        begin: f64,
+        end: f64,
+        step: f64,
+        len: f64,
+        last: Option<f64>,
+    ) -> Result<Self, StepRangeError> {
+        if len >= usize::MAX as f64 {
+            return Err(StepRangeError::TooManySteps { begin, end, step });
+        }
+        let len = len as usize;
+        Ok(Self {
+            begin,
+            step,
+            last,
+            len,
+            front: 0,
+            back: len,
+            kind: PhantomData,
+        })
+    }
+
+    fn value(&self, i: usize) -> T {
+        match self.last {
+            Some(last) if i + 1 == self.len => T::from_f64(last),
+            _ => T::from_f64(self.begin + i as f64 * self.step),
+        }
+    }
+}
+
+impl<T: Linear> Iterator for StepRange<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.front += 1;
+            Some(self.value(self.front - 1))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.back - self.front;
+        (n, Some(n))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+}
+
+impl<T: Linear> DoubleEndedIterator for StepRange<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            self.back -= 1;
+            Some(self.value(self.back))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Linear> ExactSizeIterator for StepRange<T> {}
+
+impl<T: Linear> FusedIterator for StepRange<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(range: StepRange<f64>) -> Vec<f64> {
+        let len = range.len();
+        let values: Vec<f64> = range.collect();
+        assert_eq!(values.len(), len);
+        values
+    }
+
+    #[test]
+    fn inclusive_snaps_onto_a_nearly_whole_end() {
+        // 0.3 / 0.1 is 2.9999999999999996, which would drop the end point
+        let values = collect(StepRange::inclusive(0.0, 0.3, 0.1).unwrap());
+        assert_eq!(values.len(), 4);
+        assert_eq!(values.last(), Some(&0.3));
+
+        let values = collect(StepRange::inclusive(-1.0, 1.0, 0.1).unwrap());
+        assert_eq!(values.len(), 21);
+        assert_eq!((values[0], values[20]), (-1.0, 1.0));
+        // each value comes from begin + i * step, not a running sum
+        assert_eq!(values[7], -1.0 + 7.0 * 0.1);
+    }
+
+    #[test]
+    fn inclusive_never_overshoots_the_end() {
+        let values = collect(StepRange::inclusive(0.0, 1.0, 0.3).unwrap());
+        assert_eq!(values.len(), 4);
+        assert!(values.iter().all(|&v| v <= 1.0));
+        assert!((values[3] - 0.9).abs() < 1e-12);
+
+        let values = collect(StepRange::inclusive(1.0, 1.0, 0.5).unwrap());
+        assert_eq!(values, [1.0]);
+    }
+
+    #[test]
+    fn negative_steps_count_down() {
+        let values = collect(StepRange::inclusive(10.0, 0.0, -2.5).unwrap());
+        assert_eq!(values, [10.0, 7.5, 5.0, 2.5, 0.0]);
+
+        let values = collect(StepRange::exclusive(1.0, -1.0, -0.5).unwrap());
+        assert_eq!(values, [1.0, 0.5, 0.0, -0.5]);
+    }
+
+    #[test]
+    fn exclusive_stops_short_of_the_end() {
+        assert_eq!(
+            collect(StepRange::exclusive(0.0, 1.0, 0.25).unwrap()),
+            [0.0, 0.25, 0.5, 0.75]
+        );
+        assert_eq!(
+            collect(StepRange::exclusive(0.0, 0.3, 0.1).unwrap()).len(),
+            3
+        );
+        assert_eq!(
+            collect(StepRange::exclusive(0.0, 1.0, 0.3).unwrap()).len(),
+            4
+        );
+        assert!(collect(StepRange::exclusive(2.0, 2.0, 1.0).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn linspace_hits_both_ends() {
+        let values = collect(StepRange::linspace(0.1, 0.7, 7).unwrap());
+        assert_eq!((values[0], values[6]), (0.1, 0.7));
+        assert!((values[3] - 0.4).abs() < 1e-12);
+
+        assert_eq!(collect(StepRange::linspace(5.0, 9.0, 1).unwrap()), [5.0]);
+        assert!(collect(StepRange::linspace(5.0, 9.0, 0).unwrap()).is_empty());
+        assert_eq!(
+            StepRange::linspace(0.0, f64::INFINITY, 3).unwrap_err(),
+            StepRangeError::NotFinite
+        );
+    }
+
+    #[test]
+    fn bad_steps_are_errors() {
+        assert_eq!(
+            StepRange::inclusive(0.0, 1.0, 0.0).unwrap_err(),
+            StepRangeError::ZeroStep
+        );
+        assert_eq!(
+            StepRange::exclusive(0.0, 1.0, -0.0).unwrap_err(),
+            StepRangeError::ZeroStep
+        );
+        assert_eq!(
+            StepRange::inclusive(0.0, 1.0, f64::NAN).unwrap_err(),
+            StepRangeError::NotFinite
+        );
+        assert_eq!(
+            StepRange::inclusive(f64::NAN, 1.0, 0.1).unwrap_err(),
+            StepRangeError::NotFinite
+        );
+        assert_eq!(
+            StepRange::inclusive(0.0, 1.0, -0.1).unwrap_err(),
+            StepRangeError::WrongDirection {
+                begin: 0.0,
+                end: 1.0,
+                step: -0.1
+            }
+        );
+        assert!(matches!(
+            StepRange::inclusive(0.0, 1e300, 1e-300),
+            Err(StepRangeError::TooManySteps { .. })
+        ));
+    }
+
+    #[test]
+    fn iterates_from_both_ends() {
+        let range = StepRange::inclusive(0.0, 0.3, 0.1).unwrap();
+        let forward: Vec<f64> = range.clone().collect();
+        let mut backward: Vec<f64> = range.clone().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(range.clone().next_back(), Some(0.3));
+
+        let mut range = StepRange::inclusive(0.0, 10.0, 1.0).unwrap();
+        assert_eq!(range.next(), Some(0.0));
+        assert_eq!(range.next_back(), Some(10.0));
+        assert_eq!(range.len(), 9);
+        assert_eq!(range.nth(2), Some(3.0));
+        assert_eq!(range.len(), 6);
+        assert_eq!(range.get(10), Some(10.0));
+        assert_eq!(range.nth(100), None);
+        assert_eq!(range.len(), 0);
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+    }
+
+    #[test]
+    fn temperatures_step_by_deltas() {
+        let temps: Vec<f64> = StepRange::inclusive(
+            Celsius::new(-40.0).unwrap(),
+            Celsius::new(0.0).unwrap(),
+            Delta::new(10.0),
+        )
+        .unwrap()
+        .map(|t| t.as_f64())
+        .collect();
+        assert_eq!(temps, [-40.0, -30.0, -20.0, -10.0, 0.0]);
+    }
+}
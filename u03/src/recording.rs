@@ -0,0 +1,445 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::ranges::Linear;
+use crate::sensors::{Reading, Sample, Sensor};
+use crate::tables::csv_field;
+use crate::tempr::{Celsius, Fahrenheit, Kelvin, Temperature};
+
+const CSV_HEADER: &str = "timestamp_ns,value,unit,sensor";
+
+/// Values that can be written to a recording, tagged with their unit.
+pub trait Recordable: Linear {
+    const UNIT: &'static str;
+}
+
+impl Recordable for f64 {
+    const UNIT: &'static str = "";
+}
+
+macro_rules! impl_recordable_for_temperatures {
+    ($($typ:ty),*) => {
+        $(impl Recordable for $typ {
+            const UNIT: &'static str = <$typ as Temperature>::SYMBOL;
+        })*
+    };
+}
+
+impl_recordable_for_temperatures!(Kelvin, Celsius, Fahrenheit);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// RFC 4180 CSV with a `timestamp_ns,value,unit,sensor` header.
+    Csv,
+    /// One JSON object per line with the same fields as the CSV.
+    JsonLines,
+}
+
+impl Format {
+    /// `.csv` is CSV; `.jsonl`, `.ndjson` and `.json` are JSON lines.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(Format::Csv),
+            "jsonl" | "ndjson" | "json" => Some(Format::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("unrecognised recording format for {0}")]
+    UnknownFormat(String),
+    #[error("line {line}: {message}")]
+    Malformed { line: usize, message: String },
+    #[error("record {record}: expected a reading in `{expected}` but found `{found}`")]
+    UnitMismatch {
+        record: usize,
+        expected: &'static str,
+        found: String,
+    },
+}
+
+/// One recorded reading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub timestamp_ns: u64,
+    pub value: f64,
+    pub unit: String,
+    pub sensor: String,
+}
+
+impl Record {
+    pub fn at(&self) -> Duration {
+        Duration::from_nanos(self.timestamp_ns)
+    }
+}
+
+/// Writes readings, from any number of sensors, to a recording.
+pub struct Recorder<W: Write> {
+    writer: W,
+    format: Format,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(mut writer: W, format: Format) -> io::Result<Self> {
+        if format == Format::Csv {
+            write!(writer, "{}\r\n", CSV_HEADER)?;
+        }
+        Ok(Self { writer, format })
+    }
+
+    pub fn write<T: Recordable>(&mut self, sensor: &str, reading: &Reading<T>) -> io::Result<()> {
+        let timestamp_ns = reading.at().as_nanos() as u64;
+        let value = reading.value().to_f64();
+        match self.format {
+            Format::Csv => write!(
+                self.writer,
+                "{},{},{},{}\r\n",
+                timestamp_ns,
+                value,
+                csv_field(T::UNIT),
+                csv_field(sensor)
+            ),
+            Format::JsonLines => {
+                let record = Record {
+                    timestamp_ns,
+                    value,
+                    unit: T::UNIT.to_string(),
+                    sensor: sensor.to_string(),
+                };
+                serde_json::to_writer(&mut self.writer, &record)?;
+                writeln!(self.writer)
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl Recorder<io::BufWriter<File>> {
+    pub fn create(path: &Path) -> Result<Self, RecordingError> {
+        let format = Format::from_path(path)
+            .ok_or_else(|| RecordingError::UnknownFormat(path.display().to_string()))?;
+        Ok(Self::new(io::BufWriter::new(File::create(path)?), format)?)
+    }
+}
+
+/// Passes readings through unchanged while writing each one to a recorder.
+/// See `Sensor::record`.
+pub struct Recorded<S, W: Write> {
+    sensor: S,
+    id: String,
+    recorder: Recorder<W>,
+    error: Option<io::Error>,
+}
+
+impl<S, W: Write> Recorded<S, W> {
+    pub(crate) fn new(sensor: S, id: &str, recorder: Recorder<W>) -> Self {
+        Self {
+            sensor,
+            id: id.to_string(),
+            recorder,
+            error: None,
+        }
+    }
+
+    /// Flushes the recording and hands back its writer, or the first error
+    /// hit while writing it (after which nothing more was written).
+    pub fn finish(self) -> io::Result<W> {
+        match self.error {
+            Some(err) => Err(err),
+            None => self.recorder.finish(),
+        }
+    }
+}
+
+impl<S, W> Sensor for Recorded<S, W>
+where
+    S: Sensor,
+    S::Value: Recordable,
+    W: Write,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        let sample = self.sensor.sample(now);
+        if let (Sample::Reading(reading), None) = (&sample, &self.error) {
+            if let Err(err) = self.recorder.write(&self.id, reading) {
+                self.error = Some(err);
+            }
+        }
+        sample
+    }
+}
+
+/// Reads every record in a recording, in file order. A quoted CSV field
+/// may run over several lines, as `Recorder` writes ids with line breaks.
+pub fn load<R: BufRead>(mut reader: R, format: Format) -> Result<Vec<Record>, RecordingError> {
+    let mut records = Vec::new();
+    let mut text = String::new();
+    let mut lines_read = 0;
+    loop {
+        text.clear();
+        if reader.read_line(&mut text)? == 0 {
+            break;
+        }
+        lines_read += 1;
+        let line_no = lines_read;
+        // an odd number of quotes leaves a field open
+        while format == Format::Csv
+            && text.matches('"').count() % 2 == 1
+            && reader.read_line(&mut text)? > 0
+        {
+            lines_read += 1;
+        }
+        let line = text.strip_suffix('\n').unwrap_or(&text);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() || (format == Format::Csv && line_no == 1 && line == CSV_HEADER) {
+            continue;
+        }
+        let malformed = |message: String| RecordingError::Malformed {
+            line: line_no,
+            message,
+        };
+        let record = match format {
+            Format::JsonLines => {
+                serde_json::from_str(line).map_err(|e| malformed(e.to_string()))?
+            }
+            Format::Csv => {
+                let fields = split_csv_line(line).map_err(malformed)?;
+                let [timestamp_ns, value, unit, sensor]: [String; 4] =
+                    fields.try_into().map_err(|fields: Vec<String>| {
+                        malformed(format!("expected 4 fields, found {}", fields.len()))
+                    })?;
+                Record {
+                    timestamp_ns: timestamp_ns.parse().map_err(|e| {
+                        malformed(format!("bad timestamp `{}`: {}", timestamp_ns, e))
+                    })?,
+                    value: value
+                        .parse()
+                        .map_err(|e| malformed(format!("bad value `{}`: {}", value, e)))?,
+                    unit,
+                    sensor,
+                }
+            }
+        };
+        records.push(record);
+    }
+    Ok(records)
+}
+
+pub fn load_path(path: &Path) -> Result<Vec<Record>, RecordingError> {
+    let format = Format::from_path(path)
+        .ok_or_else(|| RecordingError::UnknownFormat(path.display().to_string()))?;
+    load(BufReader::new(File::open(path)?), format)
+}
+
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quoted field".to_string()),
+                }
+            }
+            match chars.next() {
+                None => {
+                    fields.push(field);
+                    return Ok(fields);
+                }
+                Some(',') => fields.push(field),
+                Some(c) => return Err(format!("unexpected `{}` after quoted field", c)),
+            }
+        } else {
+            loop {
+                match chars.next() {
+                    None => {
+                        fields.push(field);
+                        return Ok(fields);
+                    }
+                    Some(',') => break,
+                    Some(c) => field.push(c),
+                }
+            }
+            fields.push(field);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    /// Each reading becomes available once as much time has passed since
+    /// the first `sample` as had passed since the first reading.
+    Original,
+    /// Every `sample` gets the next reading.
+    AsFastAsPossible,
+}
+
+/// A sensor that plays back a recording, keeping the recorded timestamps,
+/// and reports `Sample::Ended` once it runs out rather than starting over.
+pub struct Replay<T> {
+    records: Vec<Record>,
+    next: usize,
+    timing: Timing,
+    started: Option<Duration>,
+    value: PhantomData<T>,
+}
+
+impl<T: Recordable> Replay<T> {
+    /// Plays back the records of `sensor`, or every record if `None`.
+    /// Every record played back must be in `T`'s unit.
+    pub fn new(
+        records: Vec<Record>,
+        sensor: Option<&str>,
+        timing: Timing,
+    ) -> Result<Self, RecordingError> {
+        let mut selected = Vec::new();
+        for (i, record) in records.into_iter().enumerate() {
+            if sensor.is_some_and(|id| id != record.sensor) {
+                continue;
+            }
+            if record.unit != T::UNIT {
+                return Err(RecordingError::UnitMismatch {
+                    record: i + 1,
+                    expected: T::UNIT,
+                    found: record.unit,
+                });
+            }
+            selected.push(record);
+        }
+        Ok(Self {
+            records: selected,
+            next: 0,
+            timing,
+            started: None,
+            value: PhantomData,
+        })
+    }
+
+    pub fn from_path(
+        path: &Path,
+        sensor: Option<&str>,
+        timing: Timing,
+    ) -> Result<Self, RecordingError> {
+        Self::new(load_path(path)?, sensor, timing)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.records.len() - self.next
+    }
+}
+
+impl<T: Recordable> Sensor for Replay<T> {
+    type Value = T;
+
+    fn sample(&mut self, now: Duration) -> Sample<T> {
+        let Some(record) = self.records.get(self.next) else {
+            return Sample::Ended;
+        };
+        if self.timing == Timing::Original {
+            let started = *self.started.get_or_insert(now);
+            let offset = record.at().saturating_sub(self.records[0].at());
+            if now.saturating_sub(started) < offset {
+                return Sample::Missing;
+            }
+        }
+        self.next += 1;
+        Sample::Reading(Reading::new(record.at(), T::from_f64(record.value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(format: Format, sensors: &[&str]) -> Vec<u8> {
+        let mut recorder = Recorder::new(Vec::new(), format).unwrap();
+        for (i, sensor) in sensors.iter().enumerate() {
+            let reading = Reading::new(
+                Duration::from_millis(i as u64 * 125),
+                Celsius::new_unchecked(20.0 + i as f64 / 4.0),
+            );
+            recorder.write(sensor, &reading).unwrap();
+        }
+        recorder.finish().unwrap()
+    }
+
+    #[test]
+    fn recordings_load_back() {
+        let sensors = [
+            "plain",
+            "with, comma",
+            "\"quoted\"",
+            "two\nlines",
+            "crlf\r\nline",
+            "ends with newline\n",
+            "",
+        ];
+        for format in [Format::Csv, Format::JsonLines] {
+            let records = load(&record(format, &sensors)[..], format).unwrap();
+            let ids: Vec<&str> = records.iter().map(|r| r.sensor.as_str()).collect();
+            assert_eq!(ids, sensors, "{:?}", format);
+            for (i, record) in records.iter().enumerate() {
+                assert_eq!(record.at(), Duration::from_millis(i as u64 * 125));
+                assert_eq!(record.value, 20.0 + i as f64 / 4.0);
+                assert_eq!(record.unit, Celsius::SYMBOL);
+            }
+        }
+    }
+
+    #[test]
+    fn replay_plays_back_a_recording() {
+        let csv = record(Format::Csv, &["a\nb", "other", "a\nb"]);
+        let records = load(&csv[..], Format::Csv).unwrap();
+        let mut replay =
+            Replay::<Celsius>::new(records, Some("a\nb"), Timing::AsFastAsPossible).unwrap();
+        assert_eq!(replay.remaining(), 2);
+        let mut values = Vec::new();
+        while let Sample::Reading(reading) = replay.sample(Duration::ZERO) {
+            values.push((reading.at(), reading.value().as_f64()));
+        }
+        assert_eq!(
+            values,
+            [(Duration::ZERO, 20.0), (Duration::from_millis(250), 20.5)]
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_where_the_record_starts() {
+        let csv = "timestamp_ns,value,unit,sensor\r\n1,2,K,\"a\r\nb\"\r\nx,2,K,c\r\n";
+        match load(csv.as_bytes(), Format::Csv) {
+            Err(RecordingError::Malformed { line, message }) => {
+                assert_eq!(line, 4);
+                assert!(message.contains("bad timestamp"), "{}", message);
+            }
+            other => panic!("{:?}", other),
+        }
+        let unterminated = "1,2,K,\"open\r\n2,3,K,c\r\n";
+        match load(unterminated.as_bytes(), Format::Csv) {
+            Err(RecordingError::Malformed { line, message }) => {
+                assert_eq!((line, message.as_str()), (1, "unterminated quoted field"));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+}
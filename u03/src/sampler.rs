@@ -0,0 +1,651 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::sensors::{Reading, Sample, Sensor};
+
+/// Where the sampler gets its time from, so that it can run against the
+/// wall clock or against a `VirtualClock` stepped by hand.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+
+    /// Blocks until `deadline`, returning `false` instead if `stop` is set
+    /// (and `wake` called) before then.
+    fn wait_until(&self, deadline: Duration, stop: &AtomicBool) -> bool;
+
+    /// Wakes every thread in `wait_until` to re-check its stop flag.
+    fn wake(&self);
+
+    /// Called by each sampling thread when it starts and finishes.
+    fn attach(&self) {}
+    fn detach(&self) {}
+}
+
+pub struct SystemClock {
+    start: Instant,
+    lock: Mutex<()>,
+    cvar: Condvar,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            lock: Mutex::new(()),
+            cvar: Condvar::new(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn wait_until(&self, deadline: Duration, stop: &AtomicBool) -> bool {
+        let mut guard = self.lock.lock().unwrap();
+        loop {
+            if stop.load(Ordering::SeqCst) {
+                return false;
+            }
+            let now = self.now();
+            if now >= deadline {
+                return true;
+            }
+            guard = self.cvar.wait_timeout(guard, deadline - now).unwrap().0;
+        }
+    }
+
+    fn wake(&self) {
+        let _guard = self.lock.lock().unwrap();
+        self.cvar.notify_all();
+    }
+}
+
+#[derive(Debug, Default)]
+struct VirtualState {
+    now: Duration,
+    workers: usize,
+    deadlines: Vec<Duration>,
+}
+
+impl VirtualState {
+    // every sampling thread is parked on a deadline that hasn't come yet
+    fn is_idle(&self) -> bool {
+        self.deadlines.len() >= self.workers && self.deadlines.iter().all(|&d| d > self.now)
+    }
+}
+
+/// A clock that only moves when told to.
+///
+/// `advance` steps through every deadline that falls within the interval
+/// one at a time and waits for the sampling threads to settle after each,
+/// so a run is the same every time and takes no real time. Anything a
+/// sensor blocks on other than this clock will stall `advance`, and a
+/// sensor simulating a slow read by waiting on this clock holds up
+/// `Sampler::shutdown` until the clock is advanced past its wait.
+#[derive(Debug, Default)]
+pub struct VirtualClock {
+    state: Mutex<VirtualState>,
+    tick: Condvar,
+    idle: Condvar,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let target = self.now() + by;
+        self.advance_to(target);
+    }
+
+    pub fn advance_to(&self, target: Duration) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            state = self.idle.wait_while(state, |s| !s.is_idle()).unwrap();
+            match state.deadlines.iter().min() {
+                Some(&next) if next <= target => {
+                    state.now = next;
+                    self.tick.notify_all();
+                }
+                _ => break,
+            }
+        }
+        state.now = state.now.max(target);
+        self.tick.notify_all();
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.state.lock().unwrap().now
+    }
+
+    fn wait_until(&self, deadline: Duration, stop: &AtomicBool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.deadlines.push(deadline);
+        self.idle.notify_all();
+        state = self
+            .tick
+            .wait_while(state, |s| s.now < deadline && !stop.load(Ordering::SeqCst))
+            .unwrap();
+        if let Some(i) = state.deadlines.iter().position(|&d| d == deadline) {
+            state.deadlines.swap_remove(i);
+        }
+        !stop.load(Ordering::SeqCst)
+    }
+
+    fn wake(&self) {
+        let _state = self.state.lock().unwrap();
+        self.tick.notify_all();
+    }
+
+    fn attach(&self) {
+        self.state.lock().unwrap().workers += 1;
+    }
+
+    fn detach(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.workers -= 1;
+        self.idle.notify_all();
+    }
+}
+
+/// Keeps the last `capacity` items, dropping the oldest to make room.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn latest(&self) -> Option<&T> {
+        self.items.back()
+    }
+
+    /// Oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SensorId(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T> {
+    Reading(SensorId, Reading<T>),
+    /// The sensor was still busy when `count` of its deadlines, starting
+    /// with `first`, came and went; those samples were skipped.
+    Missed {
+        sensor: SensorId,
+        first: Duration,
+        count: u64,
+    },
+    Ended(SensorId),
+}
+
+impl<T> Event<T> {
+    pub fn sensor(&self) -> SensorId {
+        match self {
+            Event::Reading(sensor, _) | Event::Ended(sensor) => *sensor,
+            Event::Missed { sensor, .. } => *sensor,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SensorStats {
+    pub id: SensorId,
+    pub name: String,
+    pub samples: u64,
+    pub missed: u64,
+    pub panicked: bool,
+}
+
+type Subscribers<T> = Arc<Mutex<Vec<(Option<SensorId>, mpsc::Sender<Event<T>>)>>>;
+
+struct Worker<T> {
+    name: String,
+    buffer: Arc<Mutex<RingBuffer<Reading<T>>>>,
+    samples: Arc<AtomicU64>,
+    missed: Arc<AtomicU64>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+/// Samples any number of sensors, each on its own thread and at its own
+/// rate, keeping the latest readings of each in a ring buffer and passing
+/// every reading on to subscribers.
+///
+/// A sensor that takes longer than its period only delays itself: the
+/// deadlines it overran are skipped and reported as `Event::Missed`.
+/// Dropping the sampler stops and joins every thread.
+pub struct Sampler<T, C: Clock> {
+    clock: Arc<C>,
+    stop: Arc<AtomicBool>,
+    subscribers: Subscribers<T>,
+    workers: Vec<Worker<T>>,
+}
+
+impl<T, C> Sampler<T, C>
+where
+    T: Clone + Send + 'static,
+    C: Clock + 'static,
+{
+    pub fn new(clock: Arc<C>) -> Self {
+        Self {
+            clock,
+            stop: Arc::new(AtomicBool::new(false)),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            workers: Vec::new(),
+        }
+    }
+
+    /// Starts sampling `sensor` straight away and then every `period`.
+    pub fn add<S>(&mut self, name: &str, sensor: S, period: Duration, capacity: usize) -> SensorId
+    where
+        S: Sensor<Value = T> + Send + 'static,
+    {
+        assert!(!period.is_zero(), "sampling period must not be zero");
+        let id = SensorId(self.workers.len());
+        let buffer = Arc::new(Mutex::new(RingBuffer::new(capacity)));
+        let samples = Arc::new(AtomicU64::new(0));
+        let missed = Arc::new(AtomicU64::new(0));
+        let run = Run {
+            id,
+            sensor,
+            period,
+            clock: Arc::clone(&self.clock),
+            stop: Arc::clone(&self.stop),
+            subscribers: Arc::clone(&self.subscribers),
+            buffer: Arc::clone(&buffer),
+            samples: Arc::clone(&samples),
+            missed: Arc::clone(&missed),
+        };
+        // attach before the thread exists so a virtual clock can't race ahead of it
+        self.clock.attach();
+        let handle = thread::Builder::new()
+            .name(format!("sensor-{}", name))
+            .spawn(move || run.run())
+            .expect("failed to spawn sampling thread");
+        self.workers.push(Worker {
+            name: name.to_string(),
+            buffer,
+            samples,
+            missed,
+            handle: Some(handle),
+        });
+        id
+    }
+
+    /// Every event from every sensor.
+    pub fn subscribe(&self) -> mpsc::Receiver<Event<T>> {
+        self.subscribe_to(None)
+    }
+
+    /// Events from the one sensor only. Sampling starts as soon as a sensor
+    /// is added, so subscribe with `subscribe` beforehand to be sure of
+    /// seeing its first reading.
+    pub fn subscribe_sensor(&self, id: SensorId) -> mpsc::Receiver<Event<T>> {
+        self.subscribe_to(Some(id))
+    }
+
+    /// The buffered readings of a sensor, oldest first.
+    pub fn readings(&self, id: SensorId) -> Vec<Reading<T>> {
+        self.workers[id.0]
+            .buffer
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn latest(&self, id: SensorId) -> Option<Reading<T>> {
+        self.workers[id.0].buffer.lock().unwrap().latest().cloned()
+    }
+
+    pub fn missed_deadlines(&self, id: SensorId) -> u64 {
+        self.workers[id.0].missed.load(Ordering::SeqCst)
+    }
+
+    pub fn clock(&self) -> &Arc<C> {
+        &self.clock
+    }
+
+    /// Stops every sampling thread, waits for them to finish (a sensor in
+    /// the middle of a slow read gets to complete it) and reports on each.
+    pub fn shutdown(mut self) -> Vec<SensorStats> {
+        self.stop_and_join()
+    }
+
+    fn subscribe_to(&self, filter: Option<SensorId>) -> mpsc::Receiver<Event<T>> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push((filter, tx));
+        rx
+    }
+
+    fn stop_and_join(&mut self) -> Vec<SensorStats> {
+        self.stop.store(true, Ordering::SeqCst);
+        self.clock.wake();
+        self.workers
+            .iter_mut()
+            .enumerate()
+            .map(|(i, worker)| {
+                let panicked = match worker.handle.take() {
+                    Some(handle) => handle.join().is_err(),
+                    None => false,
+                };
+                SensorStats {
+                    id: SensorId(i),
+                    name: worker.name.clone(),
+                    samples: worker.samples.load(Ordering::SeqCst),
+                    missed: worker.missed.load(Ordering::SeqCst),
+                    panicked,
+                }
+            })
+            .collect()
+    }
+}
+
+impl<T, C: Clock> Drop for Sampler<T, C> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.clock.wake();
+        for worker in &mut self.workers {
+            if let Some(handle) = worker.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+struct Run<S, T, C> {
+    id: SensorId,
+    sensor: S,
+    period: Duration,
+    clock: Arc<C>,
+    stop: Arc<AtomicBool>,
+    subscribers: Subscribers<T>,
+    buffer: Arc<Mutex<RingBuffer<Reading<T>>>>,
+    samples: Arc<AtomicU64>,
+    missed: Arc<AtomicU64>,
+}
+
+impl<S, T, C> Run<S, T, C>
+where
+    S: Sensor<Value = T>,
+    T: Clone,
+    C: Clock,
+{
+    fn run(mut self) {
+        // detach even if the sensor panics, or a virtual clock waits forever
+        struct Detach<'a, C: Clock>(&'a C);
+        impl<C: Clock> Drop for Detach<'_, C> {
+            fn drop(&mut self) {
+                self.0.detach();
+            }
+        }
+        let clock = Arc::clone(&self.clock);
+        let _detach = Detach(&*clock);
+
+        let mut deadline = clock.now();
+        while clock.wait_until(deadline, &self.stop) {
+            match self.sensor.sample(clock.now()) {
+                Sample::Reading(reading) => {
+                    self.buffer.lock().unwrap().push(reading.clone());
+                    self.samples.fetch_add(1, Ordering::SeqCst);
+                    self.publish(Event::Reading(self.id, reading));
+                }
+                Sample::Missing => {}
+                Sample::Ended => {
+                    self.publish(Event::Ended(self.id));
+                    return;
+                }
+            }
+
+            deadline += self.period;
+            let now = clock.now();
+            if now > deadline {
+                let behind = (now - deadline).as_nanos();
+                let period = self.period.as_nanos();
+                let count = behind.div_ceil(period) as u64;
+                self.missed.fetch_add(count, Ordering::SeqCst);
+                self.publish(Event::Missed {
+                    sensor: self.id,
+                    first: deadline,
+                    count,
+                });
+                deadline += Duration::from_nanos((period * count as u128) as u64);
+            }
+        }
+    }
+
+    fn publish(&self, event: Event<T>) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|(filter, tx)| match filter {
+                Some(id) if *id != self.id => true,
+                _ => tx.send(event.clone()).is_ok(),
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    /// Counts up from 0, one per reading.
+    fn counter() -> impl FnMut() -> u32 {
+        let mut n = 0;
+        move || {
+            n += 1;
+            n - 1
+        }
+    }
+
+    fn times(readings: &[Reading<u32>]) -> Vec<Duration> {
+        readings.iter().map(Reading::at).collect()
+    }
+
+    #[test]
+    fn sensors_fill_their_buffers_at_their_own_rates() {
+        let mut sampler = Sampler::new(Arc::new(VirtualClock::new()));
+        let fast = sampler.add("fast", counter(), ms(10), 4);
+        let slow = sampler.add("slow", counter(), ms(25), 16);
+        sampler.clock().advance(ms(100));
+
+        // 11 readings at 0, 10, ..., 100, of which the buffer keeps 4
+        assert_eq!(
+            times(&sampler.readings(fast)),
+            [ms(70), ms(80), ms(90), ms(100)]
+        );
+        let values: Vec<u32> = sampler.readings(fast).iter().map(|r| *r.value()).collect();
+        assert_eq!(values, [7, 8, 9, 10]);
+        assert_eq!(
+            times(&sampler.readings(slow)),
+            [ms(0), ms(25), ms(50), ms(75), ms(100)]
+        );
+        assert_eq!(sampler.latest(slow), Some(Reading::new(ms(100), 4)));
+
+        let stats = sampler.shutdown();
+        assert_eq!((stats[0].samples, stats[0].missed), (11, 0));
+        assert_eq!((stats[1].samples, stats[1].missed), (5, 0));
+    }
+
+    #[test]
+    fn a_slow_sensor_misses_deadlines_without_holding_up_others() {
+        let clock = Arc::new(VirtualClock::new());
+        let mut sampler = Sampler::new(Arc::clone(&clock));
+        let events = sampler.subscribe();
+        let fast = sampler.add("fast", counter(), ms(10), 64);
+        let slow_clock = Arc::clone(&clock);
+        let never = AtomicBool::new(false);
+        // each read takes 250ms of virtual time, against a 100ms period
+        let slow = sampler.add(
+            "slow",
+            move || {
+                let start = slow_clock.now();
+                slow_clock.wait_until(start + ms(250), &never);
+                0
+            },
+            ms(100),
+            64,
+        );
+        clock.advance(ms(250));
+
+        assert_eq!(sampler.readings(fast).len(), 26);
+        assert_eq!(sampler.missed_deadlines(fast), 0);
+        assert_eq!(times(&sampler.readings(slow)), [ms(0)]);
+        // the read starting at 0 ends at 250, overrunning 100 and 200
+        assert_eq!(sampler.missed_deadlines(slow), 2);
+        let stats = sampler.shutdown();
+        assert_eq!((stats[1].samples, stats[1].missed), (1, 2));
+
+        let missed: Vec<Event<u32>> = events
+            .iter()
+            .filter(|event| matches!(event, Event::Missed { .. }))
+            .collect();
+        assert_eq!(
+            missed,
+            [Event::Missed {
+                sensor: slow,
+                first: ms(100),
+                count: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn subscribers_receive_readings_in_order() {
+        let mut sampler = Sampler::new(Arc::new(VirtualClock::new()));
+        let all = sampler.subscribe();
+        // sensors are numbered as they're added, so this is the second one
+        let second = sampler.subscribe_sensor(SensorId(1));
+        let first_id = sampler.add("first", counter(), ms(10), 8);
+        let second_id = sampler.add("second", counter(), ms(20), 8);
+        assert_eq!(second_id, SensorId(1));
+        sampler.clock().advance(ms(40));
+        drop(sampler);
+
+        let all: Vec<Event<u32>> = all.iter().collect();
+        let of =
+            |id| -> Vec<Event<u32>> { all.iter().filter(|e| e.sensor() == id).cloned().collect() };
+        let expected = |period: u64, count: u64| -> Vec<Event<u32>> {
+            (0..count)
+                .map(|i| Event::Reading(SensorId(0), Reading::new(ms(i * period), i as u32)))
+                .collect()
+        };
+        assert_eq!(of(first_id), expected(10, 5));
+        let second: Vec<Event<u32>> = second.iter().collect();
+        let expected_second: Vec<Event<u32>> = expected(20, 3)
+            .into_iter()
+            .map(|e| match e {
+                Event::Reading(_, reading) => Event::Reading(second_id, reading),
+                e => e,
+            })
+            .collect();
+        assert_eq!(second, expected_second);
+        assert_eq!(of(second_id), expected_second);
+    }
+
+    /// Reports `Ended` after `left` readings.
+    struct Finite {
+        left: u32,
+    }
+
+    impl Sensor for Finite {
+        type Value = u32;
+
+        fn sample(&mut self, now: Duration) -> Sample<u32> {
+            if self.left == 0 {
+                return Sample::Ended;
+            }
+            self.left -= 1;
+            Sample::Reading(Reading::new(now, self.left))
+        }
+    }
+
+    #[test]
+    fn shutdown_joins_and_reports_every_sensor() {
+        let mut sampler = Sampler::new(Arc::new(VirtualClock::new()));
+        let events = sampler.subscribe();
+        sampler.add("steady", counter(), ms(10), 8);
+        let finite = sampler.add("finite", Finite { left: 2 }, ms(10), 8);
+        sampler.add("broken", || -> u32 { panic!("sensor failure") }, ms(10), 8);
+        sampler.clock().advance(ms(50));
+
+        let stats = sampler.shutdown();
+        let summary: Vec<(&str, u64, bool)> = stats
+            .iter()
+            .map(|s| (s.name.as_str(), s.samples, s.panicked))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("steady", 6, false),
+                ("finite", 2, false),
+                ("broken", 0, true)
+            ]
+        );
+        // every thread has gone, and with it every sender
+        let ended: Vec<Event<u32>> = events
+            .iter()
+            .filter(|e| matches!(e, Event::Ended(_)))
+            .collect();
+        assert_eq!(ended, [Event::Ended(finite)]);
+    }
+
+    #[test]
+    fn dropping_the_sampler_joins_its_threads() {
+        let mut sampler = Sampler::new(Arc::new(VirtualClock::new()));
+        let events = sampler.subscribe();
+        let id = sampler.add("steady", counter(), ms(10), 8);
+        sampler.clock().advance(ms(30));
+        drop(sampler);
+        // the iterator only finishes once the sampling thread has let go
+        assert_eq!(events.iter().filter(|e| e.sensor() == id).count(), 4);
+    }
+}
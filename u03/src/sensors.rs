@@ -0,0 +1,539 @@
+use rand::Rng;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::faults::{AnnotateFaults, DropFaults, FaultDetector};
+use crate::ranges::Linear;
+use crate::recording::{Recordable, Recorded, Recorder};
+use crate::tempr::Temperature;
+
+/// A value together with when it was taken, as an offset from whatever
+/// epoch the caller samples against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading<T> {
+    at: Duration,
+    value: T,
+}
+
+impl<T> Reading<T> {
+    pub fn new(at: Duration, value: T) -> Self {
+        Self { at, value }
+    }
+
+    pub fn at(&self) -> Duration {
+        self.at
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Reading<U> {
+        Reading::new(self.at, f(self.value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sample<T> {
+    Reading(Reading<T>),
+    /// Nothing to report this time, but there may be later.
+    Missing,
+    /// The sensor will never report again.
+    Ended,
+}
+
+impl<T> Sample<T> {
+    pub fn reading(self) -> Option<Reading<T>> {
+        match self {
+            Sample::Reading(r) => Some(r),
+            _ => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Sample<U> {
+        match self {
+            Sample::Reading(r) => Sample::Reading(r.map(f)),
+            Sample::Missing => Sample::Missing,
+            Sample::Ended => Sample::Ended,
+        }
+    }
+}
+
+/// Something that can be asked for a reading at a given time.
+///
+/// Any `FnMut() -> T` closure or function is a sensor that always has a
+/// reading, stamped with the time it was asked at. The provided methods
+/// build filters on top of a sensor the way iterator adapters do.
+pub trait Sensor {
+    type Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value>;
+
+    fn by_ref(&mut self) -> ByRef<'_, Self>
+    where
+        Self: Sized,
+    {
+        ByRef(self)
+    }
+
+    /// Scales each value by `gain` then shifts it by `offset`.
+    fn calibrate(self, offset: <Self::Value as Linear>::Step, gain: f64) -> Calibrate<Self>
+    where
+        Self: Sized,
+        Self::Value: Linear,
+    {
+        Calibrate {
+            sensor: self,
+            offset: Self::Value::step_to_f64(offset),
+            gain,
+        }
+    }
+
+    fn clamp(self, min: Self::Value, max: Self::Value) -> Clamp<Self>
+    where
+        Self: Sized,
+        Self::Value: Linear,
+    {
+        Clamp {
+            sensor: self,
+            min: min.to_f64(),
+            max: max.to_f64(),
+        }
+    }
+
+    /// Mean of the last `window` readings.
+    fn moving_average(self, window: usize) -> MovingAverage<Self>
+    where
+        Self: Sized,
+        Self::Value: Linear,
+    {
+        MovingAverage {
+            sensor: self,
+            window: window.max(1),
+            values: VecDeque::new(),
+            sum: 0.0,
+        }
+    }
+
+    /// Exponential smoothing, `alpha` being the weight of the newest reading.
+    fn smooth(self, alpha: f64) -> Smooth<Self>
+    where
+        Self: Sized,
+        Self::Value: Linear,
+    {
+        Smooth {
+            sensor: self,
+            alpha: alpha.clamp(0.0, 1.0),
+            state: None,
+        }
+    }
+
+    /// Median of the last `window` readings.
+    fn median(self, window: usize) -> Median<Self>
+    where
+        Self: Sized,
+        Self::Value: Linear,
+    {
+        Median {
+            sensor: self,
+            window: window.max(1),
+            values: VecDeque::new(),
+        }
+    }
+
+    /// Adds normally distributed noise with the given standard deviation.
+    fn noise<R: Rng>(self, std_dev: <Self::Value as Linear>::Step, rng: R) -> Noise<Self, R>
+    where
+        Self: Sized,
+        Self::Value: Linear,
+    {
+        Noise {
+            sensor: self,
+            std_dev: Self::Value::step_to_f64(std_dev),
+            rng,
+        }
+    }
+
+    /// Loses each reading with probability `p`, clamped to `0..=1`. A NaN
+    /// `p` loses nothing.
+    fn dropout<R: Rng>(self, p: f64, rng: R) -> Dropout<Self, R>
+    where
+        Self: Sized,
+    {
+        Dropout {
+            sensor: self,
+            p: if p.is_nan() { 0.0 } else { p.clamp(0.0, 1.0) },
+            rng,
+        }
+    }
+
+    /// Runs each reading past `detector`, reporting the faulty ones as
+    /// `Sample::Missing`.
+    fn drop_faults(self, detector: FaultDetector) -> DropFaults<Self>
+    where
+        Self: Sized,
+        Self::Value: Linear,
+    {
+        DropFaults::new(self, detector)
+    }
+
+    /// Runs each reading past `detector`, passing every one on with the
+    /// faults found in it.
+    fn annotate_faults(self, detector: FaultDetector) -> AnnotateFaults<Self>
+    where
+        Self: Sized,
+        Self::Value: Linear,
+    {
+        AnnotateFaults::new(self, detector)
+    }
+
+    /// Writes every reading to `recorder` under the sensor id `id`.
+    fn record<W: Write>(self, id: &str, recorder: Recorder<W>) -> Recorded<Self, W>
+    where
+        Self: Sized,
+        Self::Value: Recordable,
+    {
+        Recorded::new(self, id, recorder)
+    }
+
+    fn convert<U: Temperature>(self) -> Convert<Self, U>
+    where
+        Self: Sized,
+        Self::Value: Temperature,
+    {
+        Convert {
+            sensor: self,
+            scale: PhantomData,
+        }
+    }
+}
+
+impl<F, T> Sensor for F
+where
+    F: FnMut() -> T,
+{
+    type Value = T;
+
+    fn sample(&mut self, now: Duration) -> Sample<T> {
+        Sample::Reading(Reading::new(now, self()))
+    }
+}
+
+pub struct ByRef<'a, S>(&'a mut S);
+
+impl<S: Sensor> Sensor for ByRef<'_, S> {
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        self.0.sample(now)
+    }
+}
+
+pub struct Calibrate<S> {
+    sensor: S,
+    offset: f64,
+    gain: f64,
+}
+
+impl<S> Sensor for Calibrate<S>
+where
+    S: Sensor,
+    S::Value: Linear,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        let (offset, gain) = (self.offset, self.gain);
+        self.sensor
+            .sample(now)
+            .map(|v| S::Value::from_f64(v.to_f64() * gain + offset))
+    }
+}
+
+pub struct Clamp<S> {
+    sensor: S,
+    min: f64,
+    max: f64,
+}
+
+impl<S> Sensor for Clamp<S>
+where
+    S: Sensor,
+    S::Value: Linear,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        let (min, max) = (self.min, self.max);
+        self.sensor
+            .sample(now)
+            .map(|v| S::Value::from_f64(v.to_f64().max(min).min(max)))
+    }
+}
+
+pub struct MovingAverage<S> {
+    sensor: S,
+    window: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+}
+
+impl<S> Sensor for MovingAverage<S>
+where
+    S: Sensor,
+    S::Value: Linear,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        self.sensor.sample(now).map(|v| {
+            let v = v.to_f64();
+            self.values.push_back(v);
+            self.sum += v;
+            if self.values.len() > self.window {
+                self.sum -= self.values.pop_front().unwrap_or(0.0);
+            }
+            S::Value::from_f64(self.sum / self.values.len() as f64)
+        })
+    }
+}
+
+pub struct Smooth<S> {
+    sensor: S,
+    alpha: f64,
+    state: Option<f64>,
+}
+
+impl<S> Sensor for Smooth<S>
+where
+    S: Sensor,
+    S::Value: Linear,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        self.sensor.sample(now).map(|v| {
+            let v = v.to_f64();
+            let smoothed = match self.state {
+                Some(s) => self.alpha * v + (1.0 - self.alpha) * s,
+                None => v,
+            };
+            self.state = Some(smoothed);
+            S::Value::from_f64(smoothed)
+        })
+    }
+}
+
+pub struct Median<S> {
+    sensor: S,
+    window: usize,
+    values: VecDeque<f64>,
+}
+
+impl<S> Sensor for Median<S>
+where
+    S: Sensor,
+    S::Value: Linear,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        self.sensor.sample(now).map(|v| {
+            self.values.push_back(v.to_f64());
+            if self.values.len() > self.window {
+                self.values.pop_front();
+            }
+            let mut sorted: Vec<f64> = self.values.iter().copied().collect();
+            sorted.sort_by(f64::total_cmp);
+            let mid = sorted.len() / 2;
+            let median = if sorted.len().is_multiple_of(2) {
+                (sorted[mid - 1] + sorted[mid]) / 2.0
+            } else {
+                sorted[mid]
+            };
+            S::Value::from_f64(median)
+        })
+    }
+}
+
+pub struct Noise<S, R> {
+    sensor: S,
+    std_dev: f64,
+    rng: R,
+}
+
+impl<S, R> Sensor for Noise<S, R>
+where
+    S: Sensor,
+    S::Value: Linear,
+    R: Rng,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        let sample = self.sensor.sample(now);
+        if let Sample::Reading(_) = sample {
+            // Box-Muller; 1 - u keeps the log away from zero
+            let u: f64 = self.rng.gen();
+            let v: f64 = self.rng.gen();
+            let z = (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos();
+            let noise = z * self.std_dev;
+            sample.map(|v| S::Value::from_f64(v.to_f64() + noise))
+        } else {
+            sample
+        }
+    }
+}
+
+pub struct Dropout<S, R> {
+    sensor: S,
+    p: f64,
+    rng: R,
+}
+
+impl<S, R> Sensor for Dropout<S, R>
+where
+    S: Sensor,
+    R: Rng,
+{
+    type Value = S::Value;
+
+    fn sample(&mut self, now: Duration) -> Sample<Self::Value> {
+        match self.sensor.sample(now) {
+            Sample::Reading(_) if self.rng.gen_bool(self.p) => Sample::Missing,
+            sample => sample,
+        }
+    }
+}
+
+pub struct Convert<S, U> {
+    sensor: S,
+    scale: PhantomData<U>,
+}
+
+impl<S, U> Sensor for Convert<S, U>
+where
+    S: Sensor,
+    S::Value: Temperature,
+    U: Temperature,
+{
+    type Value = U;
+
+    fn sample(&mut self, now: Duration) -> Sample<U> {
+        self.sensor.sample(now).map(|v| v.to_scale())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tempr::{Celsius, Fahrenheit};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn feed(values: &[f64]) -> impl FnMut() -> f64 + '_ {
+        let mut values = values.iter().copied();
+        move || values.next().expect("sampled past the end of the feed")
+    }
+
+    fn values<S: Sensor>(mut sensor: S, count: u64) -> Vec<Option<S::Value>> {
+        (0..count)
+            .map(|i| {
+                let reading = sensor.sample(Duration::from_secs(i)).reading()?;
+                assert_eq!(reading.at(), Duration::from_secs(i));
+                Some(reading.into_value())
+            })
+            .collect()
+    }
+
+    fn readings<S: Sensor>(sensor: S, count: u64) -> Vec<S::Value> {
+        values(sensor, count)
+            .into_iter()
+            .map(|v| v.expect("missing reading"))
+            .collect()
+    }
+
+    #[test]
+    fn calibrate_scales_then_shifts() {
+        let sensor = feed(&[10.0, 20.0, -5.0]).calibrate(1.0, 2.0);
+        assert_eq!(readings(sensor, 3), [21.0, 41.0, -9.0]);
+    }
+
+    #[test]
+    fn clamp_holds_values_inside_the_bounds() {
+        let sensor = feed(&[-5.0, 5.0, 15.0]).clamp(0.0, 10.0);
+        assert_eq!(readings(sensor, 3), [0.0, 5.0, 10.0]);
+    }
+
+    #[test]
+    fn moving_average_covers_the_last_window() {
+        let sensor = feed(&[1.0, 2.0, 3.0, 4.0, 5.0]).moving_average(3);
+        assert_eq!(readings(sensor, 5), [1.0, 1.5, 2.0, 3.0, 4.0]);
+
+        // a zero window still averages the newest reading
+        let sensor = feed(&[1.0, 2.0, 3.0]).moving_average(0);
+        assert_eq!(readings(sensor, 3), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn smooth_weights_the_newest_reading_by_alpha() {
+        let sensor = feed(&[0.0, 10.0, 10.0, 10.0]).smooth(0.5);
+        assert_eq!(readings(sensor, 4), [0.0, 5.0, 7.5, 8.75]);
+
+        let sensor = feed(&[0.0, 10.0, 4.0]).smooth(2.0);
+        assert_eq!(readings(sensor, 3), [0.0, 10.0, 4.0]);
+    }
+
+    #[test]
+    fn median_ignores_a_lone_outlier() {
+        let sensor = feed(&[5.0, 1.0, 9.0, 2.0, 8.0]).median(3);
+        assert_eq!(readings(sensor, 5), [5.0, 3.0, 5.0, 2.0, 8.0]);
+
+        let sensor = feed(&[20.0, 20.5, 99.0, 21.0, 20.8]).median(3);
+        assert_eq!(readings(sensor, 5)[2..], [20.5, 21.0, 21.0]);
+    }
+
+    #[test]
+    fn noise_is_repeatable_and_has_the_requested_spread() {
+        let noisy = |seed| readings((|| 0.0).noise(1.0, StdRng::seed_from_u64(seed)), 4000);
+        let samples = noisy(7);
+        assert_eq!(samples, noisy(7));
+        assert_ne!(samples, noisy(8));
+
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let var = samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.1, "mean {mean}");
+        assert!((var.sqrt() - 1.0).abs() < 0.1, "std dev {}", var.sqrt());
+
+        let quiet = (|| 3.0).noise(0.0, StdRng::seed_from_u64(7));
+        assert!(readings(quiet, 10).iter().all(|&v| v == 3.0));
+    }
+
+    #[test]
+    fn dropout_loses_about_p_of_the_readings() {
+        let dropout = |p| values((|| 1.0).dropout(p, StdRng::seed_from_u64(11)), 4000);
+        let lost = |samples: Vec<Option<f64>>| samples.iter().filter(|v| v.is_none()).count();
+
+        assert_eq!(lost(dropout(0.0)), 0);
+        assert_eq!(lost(dropout(1.0)), 4000);
+        assert_eq!(lost(dropout(-3.0)), 0);
+        assert_eq!(lost(dropout(7.0)), 4000);
+        assert_eq!(lost(dropout(f64::NAN)), 0);
+        let quarter = lost(dropout(0.25));
+        assert!((850..1150).contains(&quarter), "lost {quarter}");
+        assert_eq!(dropout(0.25), dropout(0.25));
+    }
+
+    #[test]
+    fn convert_changes_scale_but_not_time() {
+        let sensor = (|| Celsius::new_unchecked(100.0)).convert::<Fahrenheit>();
+        let converted = readings(sensor, 2);
+        assert!(converted.iter().all(|f| (f.as_f64() - 212.0).abs() < 1e-9));
+    }
+}
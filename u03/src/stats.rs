@@ -0,0 +1,435 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::ranges::Linear;
+use crate::sensors::Reading;
+use crate::tempr::Temperature;
+
+const DEFAULT_SKETCH_SIZE: usize = 256;
+
+/// Approximate quantiles over a stream in bounded memory, after Karnin,
+/// Lang and Liberty's compactor sketch (without the randomness).
+///
+/// Level `i` holds values standing in for `2^i` originals each. Whenever a
+/// level fills up it's sorted and every other value is promoted to the
+/// next level, alternating which half is kept so the errors cancel out.
+/// The rank of a quantile is off by roughly `log2(n / k) / k` of `n`.
+#[derive(Debug, Clone)]
+pub struct QuantileSketch {
+    k: usize,
+    levels: Vec<Vec<f64>>,
+    // which half the next compaction of each level keeps
+    odd: Vec<bool>,
+}
+
+impl QuantileSketch {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k: k.max(2),
+            levels: vec![Vec::new()],
+            odd: vec![false],
+        }
+    }
+
+    pub fn insert(&mut self, v: f64) {
+        self.levels[0].push(v);
+        self.compact();
+    }
+
+    /// Folds `other` in, as if every value inserted into it had been
+    /// inserted here instead.
+    pub fn merge(&mut self, other: &QuantileSketch) {
+        for (i, level) in other.levels.iter().enumerate() {
+            if i == self.levels.len() {
+                self.levels.push(Vec::new());
+                self.odd.push(false);
+            }
+            self.levels[i].extend_from_slice(level);
+        }
+        self.compact();
+    }
+
+    /// The value with about a fraction `q` of the values below it.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let mut weighted: Vec<(f64, u64)> = self
+            .levels
+            .iter()
+            .enumerate()
+            .flat_map(|(i, level)| level.iter().map(move |&v| (v, 1u64 << i)))
+            .collect();
+        weighted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total: u64 = weighted.iter().map(|&(_, w)| w).sum();
+        let target = q.clamp(0.0, 1.0) * total as f64;
+        let mut seen = 0;
+        for &(v, w) in &weighted {
+            seen += w;
+            if seen as f64 > target {
+                return Some(v);
+            }
+        }
+        weighted.last().map(|&(v, _)| v)
+    }
+
+    fn compact(&mut self) {
+        let mut i = 0;
+        while i < self.levels.len() {
+            if self.levels[i].len() >= self.k {
+                let mut level = std::mem::take(&mut self.levels[i]);
+                level.sort_by(f64::total_cmp);
+                // an odd one out stays behind at this level
+                if level.len() % 2 == 1 {
+                    self.levels[i].extend(level.pop());
+                }
+                let start = usize::from(self.odd[i]);
+                self.odd[i] = !self.odd[i];
+                if i + 1 == self.levels.len() {
+                    self.levels.push(Vec::new());
+                    self.odd.push(false);
+                }
+                self.levels[i + 1].extend(level.into_iter().skip(start).step_by(2));
+            }
+            i += 1;
+        }
+    }
+}
+
+/// A variance, in squared units of `T`. Unlike a `Delta` it can't be
+/// converted by the plain conversion factor between scales: 1 °C² is
+/// 3.24 °F², not 1.8, which is what `to_scale` takes care of.
+pub struct Variance<T> {
+    squared: f64,
+    scale: PhantomData<T>,
+}
+
+impl<T> Variance<T> {
+    pub fn new(squared: f64) -> Self {
+        Self {
+            squared,
+            scale: PhantomData,
+        }
+    }
+
+    /// The variance in squared units of `T`.
+    pub fn value(self) -> f64 {
+        self.squared
+    }
+}
+
+impl<T: Temperature> Variance<T> {
+    pub fn to_scale<U: Temperature>(self) -> Variance<U> {
+        let factor = T::DEGREE / U::DEGREE;
+        Variance::new(self.squared * factor * factor)
+    }
+}
+
+// derives would put the bounds on the scale marker rather than the value
+
+impl<T> Clone for Variance<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Variance<T> {}
+
+impl<T> fmt::Debug for Variance<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Variance({})", self.squared)
+    }
+}
+
+impl<T> PartialEq for Variance<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.squared == other.squared
+    }
+}
+
+/// Running statistics over a series of values, in the values' own scale:
+/// the mean of some `Celsius` is a `Celsius`, its spread a `Delta<Celsius>`.
+///
+/// Mean and variance use Welford's method, so they don't lose precision
+/// over long series the way summing squares does. Accumulators filled on
+/// different threads can be merged. Non-finite values are skipped.
+#[derive(Debug, Clone)]
+pub struct Stats<T> {
+    count: u64,
+    skipped: u64,
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+    sketch: QuantileSketch,
+    kind: PhantomData<T>,
+}
+
+impl<T: Linear> Default for Stats<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Linear> Stats<T> {
+    pub fn new() -> Self {
+        Self::with_sketch_size(DEFAULT_SKETCH_SIZE)
+    }
+
+    /// Bigger sketches give more accurate quantiles for more memory.
+    pub fn with_sketch_size(k: usize) -> Self {
+        Self {
+            count: 0,
+            skipped: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            sketch: QuantileSketch::new(k),
+            kind: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        let v = value.to_f64();
+        if !v.is_finite() {
+            self.skipped += 1;
+            return;
+        }
+        self.count += 1;
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+        let delta = v - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (v - self.mean);
+        self.sketch.insert(v);
+    }
+
+    /// Folds `other` in, as if every value pushed to it had been pushed
+    /// here instead (Chan et al.'s pairwise update).
+    pub fn merge(&mut self, other: &Stats<T>) {
+        self.skipped += other.skipped;
+        if other.count == 0 {
+            return;
+        }
+        let (n_a, n_b) = (self.count as f64, other.count as f64);
+        let n = n_a + n_b;
+        let delta = other.mean - self.mean;
+        self.mean += delta * n_b / n;
+        self.m2 += other.m2 + delta * delta * n_a * n_b / n;
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sketch.merge(&other.sketch);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// How many non-finite values were left out.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.any().then(|| T::from_f64(self.min))
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.any().then(|| T::from_f64(self.max))
+    }
+
+    pub fn mean(&self) -> Option<T> {
+        self.any().then(|| T::from_f64(self.mean))
+    }
+
+    /// Sample variance, in squared degrees of `T`'s scale.
+    pub fn variance(&self) -> Option<Variance<T>> {
+        (self.count > 1).then(|| Variance::new(self.m2 / (self.count - 1) as f64))
+    }
+
+    /// Sample standard deviation. It's a delta rather than a `T` as it
+    /// says nothing about where the values sit.
+    pub fn std_dev(&self) -> Option<T::Step> {
+        self.variance().map(|v| T::step_from_f64(v.value().sqrt()))
+    }
+
+    /// Approximately the value with a fraction `q` of the values below it.
+    /// The ends, `q` of 0 and 1, are exactly the min and max.
+    pub fn quantile(&self, q: f64) -> Option<T> {
+        if !self.any() {
+            return None;
+        }
+        let v = match q {
+            q if q <= 0.0 => self.min,
+            q if q >= 1.0 => self.max,
+            q => self.sketch.quantile(q)?,
+        };
+        Some(T::from_f64(v))
+    }
+
+    pub fn median(&self) -> Option<T> {
+        self.quantile(0.5)
+    }
+
+    fn any(&self) -> bool {
+        self.count > 0
+    }
+}
+
+impl<T: Linear> Extend<T> for Stats<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|v| self.push(v));
+    }
+}
+
+impl<T: Linear> Extend<Reading<T>> for Stats<T> {
+    fn extend<I: IntoIterator<Item = Reading<T>>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|r| self.push(r.into_value()));
+    }
+}
+
+impl<T: Linear> FromIterator<T> for Stats<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stats = Self::new();
+        stats.extend(iter);
+        stats
+    }
+}
+
+impl<T: Linear> FromIterator<Reading<T>> for Stats<T> {
+    fn from_iter<I: IntoIterator<Item = Reading<T>>>(iter: I) -> Self {
+        let mut stats = Self::new();
+        stats.extend(iter);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tempr::{Celsius, Fahrenheit};
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    /// Mean and sample variance the textbook way, summing deviations from
+    /// the mean on a second pass.
+    fn two_pass(values: &[f64]) -> (f64, f64) {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let m2 = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>();
+        (mean, m2 / (n - 1.0))
+    }
+
+    fn close(a: f64, b: f64, relative: f64) -> bool {
+        (a - b).abs() <= relative * a.abs().max(b.abs())
+    }
+
+    fn sample(seed: u64, n: usize) -> Vec<f64> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n).map(|_| rng.gen_range(-50.0..150.0)).collect()
+    }
+
+    #[test]
+    fn welford_matches_two_pass() {
+        // a large offset is what sinks the sum-of-squares formula
+        for offset in [0.0, 1e6, 1e9] {
+            let values: Vec<f64> = sample(1, 10_000).iter().map(|v| v + offset).collect();
+            let stats: Stats<f64> = values.iter().copied().collect();
+            let (mean, variance) = two_pass(&values);
+            assert_eq!(stats.count(), 10_000);
+            assert!(
+                close(stats.mean().unwrap(), mean, 1e-12),
+                "offset {}",
+                offset
+            );
+            assert!(
+                close(stats.variance().unwrap().value(), variance, 1e-6),
+                "offset {}: {:?} vs {}",
+                offset,
+                stats.variance(),
+                variance
+            );
+        }
+    }
+
+    #[test]
+    fn merged_accumulators_match_one() {
+        let values = sample(2, 5_000);
+        let whole: Stats<f64> = values.iter().copied().collect();
+        for parts in [1, 2, 3, 7, 64] {
+            let mut merged = Stats::<f64>::new();
+            merged.merge(&Stats::new());
+            for chunk in values.chunks(values.len().div_ceil(parts)) {
+                merged.merge(&chunk.iter().copied().collect());
+            }
+            assert_eq!(merged.count(), whole.count());
+            assert_eq!((merged.min(), merged.max()), (whole.min(), whole.max()));
+            assert!(close(merged.mean().unwrap(), whole.mean().unwrap(), 1e-12));
+            let (a, b) = (merged.variance().unwrap(), whole.variance().unwrap());
+            assert!(close(a.value(), b.value(), 1e-12), "{} parts", parts);
+        }
+    }
+
+    #[test]
+    fn non_finite_values_are_skipped() {
+        let stats: Stats<f64> = [1.0, f64::NAN, 3.0, f64::INFINITY].into_iter().collect();
+        assert_eq!((stats.count(), stats.skipped()), (2, 2));
+        assert_eq!(stats.mean(), Some(2.0));
+        assert_eq!(Stats::<f64>::new().variance(), None);
+    }
+
+    #[test]
+    fn quantiles_stay_within_the_rank_bound() {
+        let k = 64;
+        let n = 50_000;
+        let mut values: Vec<f64> = (0..n).map(f64::from).collect();
+        values.shuffle(&mut StdRng::seed_from_u64(3));
+        let mut stats = Stats::<f64>::with_sketch_size(k);
+        let mut parts = vec![Stats::<f64>::with_sketch_size(k); 4];
+        for (i, &v) in values.iter().enumerate() {
+            stats.push(v);
+            parts[i % 4].push(v);
+        }
+        let mut merged = Stats::<f64>::with_sketch_size(k);
+        for part in &parts {
+            merged.merge(part);
+        }
+        // with the values 0..n, a value is its own rank
+        let bound = (f64::from(n) / k as f64).log2() / k as f64;
+        for q in [0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99] {
+            for (what, stats) in [("pushed", &stats), ("merged", &merged)] {
+                let rank = stats.quantile(q).unwrap() / f64::from(n);
+                assert!((rank - q).abs() <= bound, "{} q {}: rank {}", what, q, rank);
+            }
+        }
+        assert_eq!(stats.quantile(0.0), Some(0.0));
+        assert_eq!(stats.quantile(1.0), Some(f64::from(n - 1)));
+    }
+
+    #[test]
+    fn variance_converts_by_the_square_of_the_factor() {
+        let celsius = [10.0, 20.0, 30.0, 45.0];
+        let in_c: Stats<Celsius> = celsius.iter().map(|&c| Celsius::new_unchecked(c)).collect();
+        let in_f: Stats<Fahrenheit> = celsius
+            .iter()
+            .map(|&c| Celsius::new_unchecked(c).to_fahrenheit())
+            .collect();
+        let converted = in_c.variance().unwrap().to_scale::<Fahrenheit>();
+        assert!(close(
+            converted.value(),
+            in_f.variance().unwrap().value(),
+            1e-12
+        ));
+        assert!(close(
+            converted.value(),
+            in_c.variance().unwrap().value() * 3.24,
+            1e-12
+        ));
+        let sd = in_c.std_dev().unwrap().to_scale::<Fahrenheit>();
+        assert!(close(
+            sd.degrees(),
+            in_f.std_dev().unwrap().degrees(),
+            1e-12
+        ));
+    }
+}
@@ -1,3 +1,542 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::iter;
+use std::str::FromStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const ELLIPSIS: char = '…';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Style {
+    /// `====` rules with `|` separated cells.
+    #[default]
+    Plain,
+    /// GitHub flavoured Markdown.
+    Markdown,
+    /// RFC 4180 CSV.
+    Csv,
+    Html,
+    /// Unicode box-drawing frame.
+    Box,
+}
+
+impl Style {
+    pub const ALL: [Style; 5] = [
+        Style::Plain,
+        Style::Markdown,
+        Style::Csv,
+        Style::Html,
+        Style::Box,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Style::Plain => "plain",
+            Style::Markdown => "markdown",
+            Style::Csv => "csv",
+            Style::Html => "html",
+            Style::Box => "box",
+        }
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown table style `{0}` (expected plain, markdown, csv, html or box)")]
+pub struct UnknownStyle(String);
+
+impl FromStr for Style {
+    type Err = UnknownStyle;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "plain" => Ok(Style::Plain),
+            "markdown" | "md" => Ok(Style::Markdown),
+            "csv" => Ok(Style::Csv),
+            "html" => Ok(Style::Html),
+            "box" => Ok(Style::Box),
+            _ => Err(UnknownStyle(s.to_string())),
+        }
+    }
+}
+
+struct Rule {
+    left: char,
+    fill: char,
+    junction: char,
+    right: char,
+}
+
+struct Frame {
+    top: Rule,
+    middle: Rule,
+    bottom: Rule,
+    vertical: char,
+}
+
+const PLAIN_RULE: Rule = Rule {
+    left: '=',
+    fill: '=',
+    junction: '=',
+    right: '=',
+};
+
+const PLAIN: Frame = Frame {
+    top: PLAIN_RULE,
+    middle: PLAIN_RULE,
+    bottom: PLAIN_RULE,
+    vertical: '|',
+};
+
+const BOX: Frame = Frame {
+    top: Rule {
+        left: '┌',
+        fill: '─',
+        junction: '┬',
+        right: '┐',
+    },
+    middle: Rule {
+        left: '├',
+        fill: '─',
+        junction: '┼',
+        right: '┤',
+    },
+    bottom: Rule {
+        left: '└',
+        fill: '─',
+        junction: '┴',
+        right: '┘',
+    },
+    vertical: '│',
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// What to do with a cell that is wider than its column's maximum width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    #[default]
+    Truncate,
+    Wrap,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Column {
+    align: Option<Align>,
+    min_width: usize,
+    max_width: Option<usize>,
+    overflow: Overflow,
+}
+
+/// Collects rows and lays them out with each column sized to its content.
+///
+/// Widths are measured in terminal columns rather than bytes or chars, so
+/// "°F" or wide glyphs line up. Columns whose cells are all plain decimal
+/// numbers are right aligned unless told otherwise; everything else is left
+/// aligned.
+#[derive(Debug, Clone)]
+pub struct TableWriter {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    columns: Vec<Column>,
+    style: Style,
+}
+
+impl TableWriter {
+    pub fn new<T: AsRef<str>>(headers: &[T]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.as_ref().to_string()).collect(),
+            rows: Vec::new(),
+            columns: vec![Column::default(); headers.len()],
+            style: Style::default(),
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    pub fn align(mut self, col: usize, align: Align) -> Self {
+        self.column_mut(col).align = Some(align);
+        self
+    }
+
+    pub fn min_width(mut self, col: usize, width: usize) -> Self {
+        self.column_mut(col).min_width = width;
+        self
+    }
+
+    /// Caps a column's width in the plain, box and Markdown styles. CSV and
+    /// HTML always carry cells whole, leaving the reader to size them.
+    pub fn max_width(mut self, col: usize, width: usize, overflow: Overflow) -> Self {
+        let column = self.column_mut(col);
+        column.max_width = Some(width.max(1));
+        column.overflow = overflow;
+        self
+    }
+
+    pub fn push_row<U: AsRef<str>>(&mut self, row: &[U]) {
+        let row: Vec<String> = row.iter().map(|c| c.as_ref().to_string()).collect();
+        if row.len() > self.columns.len() {
+            self.columns.resize(row.len(), Column::default());
+        }
+        self.rows.push(row);
+    }
+
+    pub fn extend_rows<U, R, I>(&mut self, rows: I)
+    where
+        U: AsRef<str>,
+        R: AsRef<[U]>,
+        I: IntoIterator<Item = R>,
+    {
+        for row in rows {
+            self.push_row(row.as_ref());
+        }
+    }
+
+    pub fn format_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        match self.style {
+            Style::Plain => self.format_framed_to(w, &PLAIN),
+            Style::Box => self.format_framed_to(w, &BOX),
+            Style::Markdown => self.format_markdown_to(w),
+            Style::Csv => self.format_csv_to(w),
+            Style::Html => self.format_html_to(w),
+        }
+    }
+
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "{}", self)
+    }
+
+    fn column_mut(&mut self, col: usize) -> &mut Column {
+        if col >= self.columns.len() {
+            self.columns.resize(col + 1, Column::default());
+        }
+        &mut self.columns[col]
+    }
+
+    fn cell(row: &[String], col: usize) -> &str {
+        row.get(col).map(String::as_str).unwrap_or("")
+    }
+
+    fn layout(&self) -> Vec<(usize, Align)> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(col, column)| {
+                let natural = self
+                    .all_rows()
+                    .flat_map(|row| Self::cell(row, col).lines())
+                    .map(UnicodeWidthStr::width)
+                    .max()
+                    .unwrap_or(0);
+                let mut width = natural.max(column.min_width);
+                if let Some(max_width) = column.max_width {
+                    width = width.min(max_width);
+                }
+                let align = column.align.unwrap_or_else(|| {
+                    let mut cells = self
+                        .rows
+                        .iter()
+                        .map(|row| Self::cell(row, col).trim())
+                        .filter(|cell| !cell.is_empty())
+                        .peekable();
+                    if cells.peek().is_some() && cells.all(is_numeric) {
+                        Align::Right
+                    } else {
+                        Align::Left
+                    }
+                });
+                (width, align)
+            })
+            .collect()
+    }
+
+    fn fitted_cells(&self, layout: &[(usize, Align)], row: &[String]) -> Vec<Vec<String>> {
+        self.columns
+            .iter()
+            .zip(layout)
+            .enumerate()
+            .map(|(col, (column, (width, _)))| fit(Self::cell(row, col), *width, column.overflow))
+            .collect()
+    }
+
+    fn all_rows(&self) -> impl Iterator<Item = &[String]> {
+        iter::once(&self.headers[..]).chain(self.rows.iter().map(Vec::as_slice))
+    }
+
+    fn format_framed_to<W: fmt::Write>(&self, w: &mut W, frame: &Frame) -> fmt::Result {
+        let layout = self.layout();
+        let format_rule_to = |w: &mut W, rule: &Rule| -> fmt::Result {
+            w.write_char(rule.left)?;
+            for (i, (width, _)) in layout.iter().enumerate() {
+                if i != 0 {
+                    w.write_char(rule.junction)?;
+                }
+                for _ in 0..width + 2 {
+                    w.write_char(rule.fill)?;
+                }
+            }
+            w.write_char(rule.right)?;
+            writeln!(w)
+        };
+        let format_row_to = |w: &mut W, row: &[String]| -> fmt::Result {
+            let cells = self.fitted_cells(&layout, row);
+            let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+            for line in 0..height {
+                w.write_char(frame.vertical)?;
+                for (lines, (width, align)) in cells.iter().zip(&layout) {
+                    let text = lines.get(line).map(String::as_str).unwrap_or("");
+                    write!(w, " {} {}", pad(text, *width, *align), frame.vertical)?;
+                }
+                writeln!(w)?;
+            }
+            Ok(())
+        };
+
+        format_rule_to(w, &frame.top)?;
+        format_row_to(w, &self.headers)?;
+        format_rule_to(w, &frame.middle)?;
+        for row in &self.rows {
+            format_row_to(w, row)?;
+        }
+        format_rule_to(w, &frame.bottom)
+    }
+
+    fn format_markdown_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        let layout = self.layout();
+        // Markdown rows can't span lines, so wrapped lines become <br>s
+        let rows: Vec<Vec<String>> = self
+            .all_rows()
+            .map(|row| {
+                self.fitted_cells(&layout, row)
+                    .into_iter()
+                    .map(|lines| lines.join("<br>").replace('|', "\\|"))
+                    .collect()
+            })
+            .collect();
+        let widths: Vec<usize> = (0..layout.len())
+            .map(|col| {
+                rows.iter()
+                    .map(|row| row[col].width())
+                    .max()
+                    .unwrap_or(0)
+                    .max(3)
+            })
+            .collect();
+        let format_row_to = |w: &mut W, row: &[String]| -> fmt::Result {
+            write!(w, "|")?;
+            for (cell, (width, (_, align))) in row.iter().zip(widths.iter().zip(&layout)) {
+                write!(w, " {} |", pad(cell, *width, *align))?;
+            }
+            writeln!(w)
+        };
+
+        format_row_to(w, &rows[0])?;
+        write!(w, "|")?;
+        for (width, (_, align)) in widths.iter().zip(&layout) {
+            let (left, right) = match align {
+                Align::Left => (':', '-'),
+                Align::Right => ('-', ':'),
+                Align::Center => (':', ':'),
+            };
+            write!(w, " {}{}{} |", left, "-".repeat(width - 2), right)?;
+        }
+        writeln!(w)?;
+        for row in &rows[1..] {
+            format_row_to(w, row)?;
+        }
+        Ok(())
+    }
+
+    fn format_csv_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        for row in self.all_rows() {
+            for col in 0..self.columns.len() {
+                if col != 0 {
+                    w.write_char(',')?;
+                }
+                w.write_str(&csv_field(Self::cell(row, col)))?;
+            }
+            w.write_str("\r\n")?;
+        }
+        Ok(())
+    }
+
+    fn format_html_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        let layout = self.layout();
+        let format_row_to = |w: &mut W, row: &[String], tag: &str| -> fmt::Result {
+            write!(w, "    <tr>")?;
+            for (col, (_, align)) in layout.iter().enumerate() {
+                let align = match align {
+                    Align::Left => "left",
+                    Align::Right => "right",
+                    Align::Center => "center",
+                };
+                write!(
+                    w,
+                    "<{tag} style=\"text-align: {align}\">{}</{tag}>",
+                    escape_html(Self::cell(row, col)).replace('\n', "<br>"),
+                )?;
+            }
+            writeln!(w, "</tr>")
+        };
+
+        writeln!(w, "<table>")?;
+        writeln!(w, "  <thead>")?;
+        format_row_to(w, &self.headers, "th")?;
+        writeln!(w, "  </thead>")?;
+        writeln!(w, "  <tbody>")?;
+        for row in &self.rows {
+            format_row_to(w, row, "td")?;
+        }
+        writeln!(w, "  </tbody>")?;
+        writeln!(w, "</table>")
+    }
+}
+
+/// Whether a cell is a plain decimal number such as `-12`, `3.5` or `1e-3`.
+/// Stricter than `f64::from_str`, which would also take "NaN" or "inf".
+fn is_numeric(cell: &str) -> bool {
+    fn unsigned(s: &str) -> &str {
+        s.strip_prefix(['+', '-']).unwrap_or(s)
+    }
+    fn digits(s: &str) -> bool {
+        s.bytes().all(|b| b.is_ascii_digit())
+    }
+    let (mantissa, exponent) = cell.split_once(['e', 'E']).unwrap_or((cell, "0"));
+    let (whole, fraction) = unsigned(mantissa)
+        .split_once('.')
+        .unwrap_or((unsigned(mantissa), ""));
+    let exponent = unsigned(exponent);
+    !(whole.is_empty() && fraction.is_empty())
+        && digits(whole)
+        && digits(fraction)
+        && !exponent.is_empty()
+        && digits(exponent)
+}
+
+/// Quotes a CSV field if it needs it, per RFC 4180.
+pub(crate) fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains(['"', ',', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+impl fmt::Display for TableWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.format_to(f)
+    }
+}
+
+fn fit(cell: &str, width: usize, overflow: Overflow) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in cell.lines() {
+        if line.width() <= width {
+            lines.push(line.to_string());
+        } else {
+            match overflow {
+                Overflow::Truncate => lines.push(truncate(line, width)),
+                Overflow::Wrap => lines.extend(wrap(line, width)),
+            }
+        }
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+fn truncate(line: &str, width: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    for c in line.chars() {
+        let cw = c.width().unwrap_or(0);
+        if used + cw + 1 > width {
+            break;
+        }
+        out.push(c);
+        used += cw;
+    }
+    out.push(ELLIPSIS);
+    out
+}
+
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut cur = String::new();
+    let mut used = 0;
+    for word in line.split_whitespace() {
+        let ww = word.width();
+        if used > 0 && used + 1 + ww <= width {
+            cur.push(' ');
+            cur.push_str(word);
+            used += 1 + ww;
+            continue;
+        }
+        if used > 0 {
+            lines.push(std::mem::take(&mut cur));
+            used = 0;
+        }
+        // words longer than the column are broken wherever they run out of room
+        for c in word.chars() {
+            let cw = c.width().unwrap_or(0);
+            if used > 0 && used + cw > width {
+                lines.push(std::mem::take(&mut cur));
+                used = 0;
+            }
+            cur.push(c);
+            used += cw;
+        }
+    }
+    if !cur.is_empty() || lines.is_empty() {
+        lines.push(cur);
+    }
+    lines
+}
+
+fn pad(text: &str, width: usize, align: Align) -> String {
+    let fill = width.saturating_sub(text.width());
+    let (left, right) = match align {
+        Align::Left => (0, fill),
+        Align::Right => (fill, 0),
+        Align::Center => (fill / 2, fill - fill / 2),
+    };
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
 pub fn print_table<T, U>(
     col_width: usize,
     headers: &[T],
@@ -6,23 +545,148 @@ pub fn print_table<T, U>(
     T: AsRef<str>,
     U: AsRef<str>,
 {
-    let rule_width = (col_width + 2) * headers.len() + headers.len() + 1;
-    let rule = "=".repeat(rule_width);
-    println!("{}", rule);
-    let mut head = String::new();
-    head.push('|');
-    for col in headers {
-        head.push_str(&format!(" {:width$} |", col.as_ref(), width = col_width));
-    }
-    println!("{}", head);
-    println!("{}", rule);
+    let mut table = TableWriter::new(headers);
+    for col in 0..headers.len() {
+        table = table.min_width(col, col_width);
+    }
     while let Some(cols) = get_row() {
-        let mut row = String::new();
-        row.push('|');
-        for col in cols {
-            row.push_str(&format!(" {:width$} |", col.as_ref(), width = col_width));
+        table.push_row(&cols);
+    }
+    print!("{}", table);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cities() -> TableWriter {
+        let mut table = TableWriter::new(&["city", "°C"]);
+        table.extend_rows([["東京", "21.5"], ["Oslo", "-3"]]);
+        table
+    }
+
+    #[test]
+    fn wide_glyphs_take_two_columns() {
+        let table = cities().to_string();
+        assert_eq!(
+            table,
+            "===============\n\
+             | city |   °C |\n\
+             ===============\n\
+             | 東京 | 21.5 |\n\
+             | Oslo |   -3 |\n\
+             ===============\n"
+        );
+        assert!(table.lines().all(|line| line.width() == 15));
+    }
+
+    #[test]
+    fn each_style_lays_out_the_same_rows() {
+        let expected = [
+            (Style::Markdown, "| city |   °C |\n| :--- | ---: |\n| 東京 | 21.5 |\n| Oslo |   -3 |\n"),
+            (Style::Csv, "city,°C\r\n東京,21.5\r\nOslo,-3\r\n"),
+            (
+                Style::Html,
+                "<table>\n  <thead>\n    \
+                 <tr><th style=\"text-align: left\">city</th><th style=\"text-align: right\">°C</th></tr>\n  \
+                 </thead>\n  <tbody>\n    \
+                 <tr><td style=\"text-align: left\">東京</td><td style=\"text-align: right\">21.5</td></tr>\n    \
+                 <tr><td style=\"text-align: left\">Oslo</td><td style=\"text-align: right\">-3</td></tr>\n  \
+                 </tbody>\n</table>\n",
+            ),
+            (
+                Style::Box,
+                "┌──────┬──────┐\n│ city │   °C │\n├──────┼──────┤\n\
+                 │ 東京 │ 21.5 │\n│ Oslo │   -3 │\n└──────┴──────┘\n",
+            ),
+        ];
+        for (style, table) in expected {
+            assert_eq!(cities().style(style).to_string(), table, "{style}");
+        }
+        for style in Style::ALL {
+            assert_eq!(style.name().parse::<Style>().unwrap(), style);
+        }
+        assert!("tsv".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn overflowing_cells_are_truncated_or_wrapped() {
+        let note = "a long sentence 東京東京";
+        let mut truncated = TableWriter::new(&["note"]).max_width(0, 8, Overflow::Truncate);
+        truncated.push_row(&[note]);
+        assert_eq!(
+            truncated.to_string(),
+            "============\n| note     |\n============\n| a long … |\n============\n"
+        );
+
+        let mut wrapped = TableWriter::new(&["note"]).max_width(0, 8, Overflow::Wrap);
+        wrapped.push_row(&[note]);
+        assert_eq!(
+            wrapped.to_string(),
+            "============\n| note     |\n============\n\
+             | a long   |\n| sentence |\n| 東京東京 |\n============\n"
+        );
+
+        // a wide glyph that would straddle the limit moves to the next line
+        assert_eq!(wrap("東京都", 5), ["東京", "都"]);
+        assert_eq!(truncate("東京都", 4), "東…");
+    }
+
+    #[test]
+    fn only_plain_numbers_are_right_aligned() {
+        for number in ["0", "-3", "+4.5", ".5", "5.", "1e-3", "2.5E+10"] {
+            assert!(is_numeric(number), "{number}");
+        }
+        for word in [
+            "",
+            ".",
+            "-",
+            "e5",
+            "1e",
+            "NaN",
+            "inf",
+            "-infinity",
+            "1.2.3",
+            "12 °C",
+        ] {
+            assert!(!is_numeric(word), "{word}");
         }
-        println!("{}", row);
+
+        let mut table = TableWriter::new(&["reading"]);
+        table.extend_rows([["12.5"], ["NaN"], ["inf"]]);
+        assert!(table.to_string().contains("| 12.5    |"));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert!(matches!(csv_field("plain"), Cow::Borrowed(_)));
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+
+        let mut table = TableWriter::new(&["name", "note"]);
+        table.push_row(&["Smith, J.", "6'2\" tall"]);
+        assert_eq!(
+            table.style(Style::Csv).to_string(),
+            "name,note\r\n\"Smith, J.\",\"6'2\"\" tall\"\r\n"
+        );
+    }
+
+    #[test]
+    fn html_cells_are_escaped_and_kept_whole() {
+        assert_eq!(
+            escape_html("<b>Tom & \"Jerry's\"</b>"),
+            "&lt;b&gt;Tom &amp; &quot;Jerry&#39;s&quot;&lt;/b&gt;"
+        );
+
+        let mut table = TableWriter::new(&["x"])
+            .max_width(0, 3, Overflow::Truncate)
+            .style(Style::Html);
+        table.push_row(&["a < b && c\nnext"]);
+        assert!(table
+            .to_string()
+            .contains("<td style=\"text-align: left\">a &lt; b &amp;&amp; c<br>next</td>"));
     }
-    println!("{}", rule);
 }
@@ -1,11 +1,51 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops;
+
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+pub enum TemperatureError {
+    #[error("{0} {1} is not a finite temperature")]
+    NotFinite(f64, &'static str),
+    #[error("{0} {1} is below absolute zero")]
+    BelowAbsoluteZero(f64, &'static str),
+}
+
+pub trait Temperature: Copy + fmt::Display {
+    const SYMBOL: &'static str;
+    const ABSOLUTE_ZERO: f64;
+    /// Size of one degree of this scale in kelvins.
+    const DEGREE: f64;
+
+    fn new_unchecked(t: f64) -> Self;
+    fn as_f64(self) -> f64;
+    fn to_kelvin(self) -> Kelvin;
+    fn from_kelvin(k: Kelvin) -> Self;
+
+    /// Conversions go through kelvin; over -273.15..=10,000 °C a round trip
+    /// back to the original scale stays within 1e-10 degrees.
+    fn to_scale<U: Temperature>(self) -> U {
+        U::from_kelvin(self.to_kelvin())
+    }
+
+    fn check(t: f64) -> Result<Self, TemperatureError> {
+        if !t.is_finite() {
+            Err(TemperatureError::NotFinite(t, Self::SYMBOL))
+        } else if t < Self::ABSOLUTE_ZERO {
+            Err(TemperatureError::BelowAbsoluteZero(t, Self::SYMBOL))
+        } else {
+            Ok(Self::new_unchecked(t))
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Kelvin(f64);
 
 impl Kelvin {
-    pub fn new(t: f64) -> Self {
-        Self(t)
+    pub fn new(t: f64) -> Result<Self, TemperatureError> {
+        <Self as Temperature>::check(t)
     }
 
     pub fn to_celsius(self) -> Celsius {
@@ -27,8 +67,8 @@ impl fmt::Display for Kelvin {
 pub struct Fahrenheit(f64);
 
 impl Fahrenheit {
-    pub fn new(t: f64) -> Self {
-        Self(t)
+    pub fn new(t: f64) -> Result<Self, TemperatureError> {
+        <Self as Temperature>::check(t)
     }
 
     pub fn to_celsius(self) -> Celsius {
@@ -50,8 +90,8 @@ impl fmt::Display for Fahrenheit {
 pub struct Celsius(f64);
 
 impl Celsius {
-    pub const fn new(t: f64) -> Self {
-        Self(t)
+    pub fn new(t: f64) -> Result<Self, TemperatureError> {
+        <Self as Temperature>::check(t)
     }
 
     pub fn to_fahrenheit(self) -> Fahrenheit {
@@ -69,6 +109,72 @@ impl fmt::Display for Celsius {
     }
 }
 
+impl Temperature for Kelvin {
+    const SYMBOL: &'static str = "K";
+    const ABSOLUTE_ZERO: f64 = 0.0;
+    const DEGREE: f64 = 1.0;
+
+    fn new_unchecked(t: f64) -> Self {
+        Self(t)
+    }
+
+    fn as_f64(self) -> f64 {
+        self.0
+    }
+
+    fn to_kelvin(self) -> Kelvin {
+        self
+    }
+
+    fn from_kelvin(k: Kelvin) -> Self {
+        k
+    }
+}
+
+impl Temperature for Celsius {
+    const SYMBOL: &'static str = "C";
+    const ABSOLUTE_ZERO: f64 = -273.15;
+    const DEGREE: f64 = 1.0;
+
+    fn new_unchecked(t: f64) -> Self {
+        Self(t)
+    }
+
+    fn as_f64(self) -> f64 {
+        self.0
+    }
+
+    fn to_kelvin(self) -> Kelvin {
+        Celsius::to_kelvin(self)
+    }
+
+    fn from_kelvin(k: Kelvin) -> Self {
+        k.to_celsius()
+    }
+}
+
+impl Temperature for Fahrenheit {
+    const SYMBOL: &'static str = "°F";
+    const ABSOLUTE_ZERO: f64 = -459.67;
+    const DEGREE: f64 = 5.0 / 9.0;
+
+    fn new_unchecked(t: f64) -> Self {
+        Self(t)
+    }
+
+    fn as_f64(self) -> f64 {
+        self.0
+    }
+
+    fn to_kelvin(self) -> Kelvin {
+        Fahrenheit::to_kelvin(self)
+    }
+
+    fn from_kelvin(k: Kelvin) -> Self {
+        k.to_fahrenheit()
+    }
+}
+
 macro_rules! impl_common_temp_features {
     ($($typ:ty),*) => {
         $(
@@ -105,6 +211,30 @@ macro_rules! impl_common_temp_features {
             }
         }
 
+        impl std::ops::Sub for $typ {
+            type Output = Delta<Self>;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                Delta::new(self.0 - rhs.0)
+            }
+        }
+
+        impl std::ops::Add<Delta<$typ>> for $typ {
+            type Output = Self;
+
+            fn add(self, rhs: Delta<$typ>) -> Self::Output {
+                Self(self.0 + rhs.degrees())
+            }
+        }
+
+        impl std::ops::Sub<Delta<$typ>> for $typ {
+            type Output = Self;
+
+            fn sub(self, rhs: Delta<$typ>) -> Self::Output {
+                Self(self.0 - rhs.degrees())
+            }
+        }
+
         impl $typ {
             pub fn as_f64(self) -> f64 {
                 self.0
@@ -115,3 +245,406 @@ macro_rules! impl_common_temp_features {
 }
 
 impl_common_temp_features!(Kelvin, Celsius, Fahrenheit);
+
+macro_rules! impl_cross_scale_features {
+    ($($typ:ty => $($other:ty),*);*) => {
+        $($(
+
+        impl std::cmp::PartialEq<$other> for $typ {
+            fn eq(&self, other: &$other) -> bool {
+                self.to_kelvin().0 == other.to_kelvin().0
+            }
+        }
+
+        impl std::cmp::PartialOrd<$other> for $typ {
+            fn partial_cmp(&self, other: &$other) -> Option<std::cmp::Ordering> {
+                self.to_kelvin().0.partial_cmp(&other.to_kelvin().0)
+            }
+        }
+
+        impl std::ops::Sub<$other> for $typ {
+            type Output = Delta<Self>;
+
+            fn sub(self, rhs: $other) -> Self::Output {
+                self - rhs.to_scale::<Self>()
+            }
+        }
+        )*)*
+    };
+}
+
+impl_cross_scale_features!(
+    Kelvin => Celsius, Fahrenheit;
+    Celsius => Kelvin, Fahrenheit;
+    Fahrenheit => Kelvin, Celsius
+);
+
+/// A temperature difference measured in degrees of the scale `T`.
+pub struct Delta<T> {
+    degrees: f64,
+    scale: PhantomData<T>,
+}
+
+impl<T: Temperature> Delta<T> {
+    pub const fn new(degrees: f64) -> Self {
+        Self {
+            degrees,
+            scale: PhantomData,
+        }
+    }
+
+    pub fn degrees(self) -> f64 {
+        self.degrees
+    }
+
+    pub fn abs(self) -> Self {
+        Self::new(self.degrees.abs())
+    }
+
+    pub fn to_scale<U: Temperature>(self) -> Delta<U> {
+        Delta::new(self.degrees * T::DEGREE / U::DEGREE)
+    }
+}
+
+// derives would put the bounds on the scale marker rather than the degrees
+
+impl<T> Clone for Delta<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Delta<T> {}
+
+impl<T: Temperature> fmt::Debug for Delta<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Delta({} {})", self.degrees, T::SYMBOL)
+    }
+}
+
+impl<T: Temperature> fmt::Display for Delta<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+.2} {}", self.degrees, T::SYMBOL)
+    }
+}
+
+impl<T> PartialEq for Delta<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.degrees == other.degrees
+    }
+}
+
+impl<T> PartialOrd for Delta<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.degrees.partial_cmp(&other.degrees)
+    }
+}
+
+impl<T: Temperature> ops::Add for Delta<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.degrees + rhs.degrees)
+    }
+}
+
+impl<T: Temperature> ops::Sub for Delta<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.degrees - rhs.degrees)
+    }
+}
+
+impl<T: Temperature> ops::Neg for Delta<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.degrees)
+    }
+}
+
+impl<T: Temperature> ops::Mul<f64> for Delta<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self::new(self.degrees * rhs)
+    }
+}
+
+impl<T: Temperature> ops::Div<f64> for Delta<T> {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Self::new(self.degrees / rhs)
+    }
+}
+
+/// The difference `a - b` expressed in the scale `D`, whatever the scales
+/// of `a` and `b`.
+pub fn difference<D, A, B>(a: A, b: B) -> Delta<D>
+where
+    D: Temperature,
+    A: Temperature,
+    B: Temperature,
+{
+    Delta::new(a.to_scale::<D>().as_f64() - b.to_scale::<D>().as_f64())
+}
+
+/// Whether `a` and `b` are within `tolerance` of each other, for comparing
+/// temperatures that went through float conversions.
+pub fn approx_eq<D, A, B>(a: A, b: B, tolerance: Delta<D>) -> bool
+where
+    D: Temperature,
+    A: Temperature,
+    B: Temperature,
+{
+    difference::<D, A, B>(a, b).abs() <= tolerance.abs()
+}
+
+/// A temperature known to be finite and at or above absolute zero, which
+/// makes it totally ordered (usable for sorting and as a `BTreeMap` key).
+#[derive(Debug, Clone, Copy)]
+pub struct Valid<T>(T);
+
+impl<T: Temperature> Valid<T> {
+    pub fn new(t: T) -> Result<Self, TemperatureError> {
+        // normalise -0.0 so that equal temperatures hash the same
+        T::check(t.as_f64() + 0.0).map(Self)
+    }
+
+    pub fn get(self) -> T {
+        self.0
+    }
+
+    pub fn as_f64(self) -> f64 {
+        self.0.as_f64()
+    }
+}
+
+impl<T: Temperature> TryFrom<f64> for Valid<T> {
+    type Error = TemperatureError;
+
+    fn try_from(t: f64) -> Result<Self, Self::Error> {
+        Self::new(T::new_unchecked(t))
+    }
+}
+
+impl<T: Temperature> PartialEq for Valid<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Temperature> Eq for Valid<T> {}
+
+impl<T: Temperature> PartialOrd for Valid<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Temperature> Ord for Valid<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_f64().total_cmp(&other.as_f64())
+    }
+}
+
+impl<T: Temperature> Hash for Valid<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.as_f64().to_bits())
+    }
+}
+
+impl<T: Temperature> fmt::Display for Valid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Celsius temperatures from absolute zero to 10,000 °C.
+    fn sweep() -> impl Iterator<Item = Celsius> {
+        (0..=102_732).map(|i| Celsius::new_unchecked(-273.15 + i as f64 * 0.1))
+    }
+
+    fn assert_close(a: f64, b: f64, what: &str) {
+        assert!((a - b).abs() <= 1e-10, "{}: {} vs {}", what, a, b);
+    }
+
+    #[test]
+    fn round_trips_stay_within_bound() {
+        for c in sweep() {
+            let k = c.to_scale::<Kelvin>();
+            let f = c.to_scale::<Fahrenheit>();
+            assert_close(k.to_scale::<Celsius>().as_f64(), c.as_f64(), "C -> K -> C");
+            assert_close(f.to_scale::<Celsius>().as_f64(), c.as_f64(), "C -> F -> C");
+            assert_close(
+                k.to_scale::<Fahrenheit>().to_scale::<Kelvin>().as_f64(),
+                k.as_f64(),
+                "K -> F -> K",
+            );
+            assert_close(
+                f.to_scale::<Kelvin>().to_scale::<Fahrenheit>().as_f64(),
+                f.as_f64(),
+                "F -> K -> F",
+            );
+            assert_close(
+                f.to_scale::<Kelvin>()
+                    .to_scale::<Celsius>()
+                    .to_scale::<Fahrenheit>()
+                    .as_f64(),
+                f.as_f64(),
+                "F -> K -> C -> F",
+            );
+        }
+    }
+
+    #[test]
+    fn known_points_convert() {
+        assert_close(
+            Celsius::new_unchecked(100.0).to_fahrenheit().as_f64(),
+            212.0,
+            "boiling",
+        );
+        assert_close(
+            Fahrenheit::new_unchecked(-40.0).to_celsius().as_f64(),
+            -40.0,
+            "-40",
+        );
+        assert_close(
+            Kelvin::new_unchecked(0.0).to_fahrenheit().as_f64(),
+            -459.67,
+            "zero",
+        );
+    }
+
+    #[test]
+    fn approx_eq_uses_the_tolerance_scale() {
+        let body = Celsius::new_unchecked(37.0);
+        assert!(approx_eq(
+            body,
+            Fahrenheit::new_unchecked(98.6),
+            Delta::<Kelvin>::new(1e-9)
+        ));
+        assert!(!approx_eq(
+            body,
+            Fahrenheit::new_unchecked(98.7),
+            Delta::<Fahrenheit>::new(0.05)
+        ));
+        assert!(approx_eq(
+            body,
+            Fahrenheit::new_unchecked(98.7),
+            Delta::<Fahrenheit>::new(0.11)
+        ));
+        // 0.1 °C is 0.18 °F, so the same gap passes one tolerance but not the other
+        let warmer = Celsius::new_unchecked(37.1);
+        assert!(approx_eq(body, warmer, Delta::<Celsius>::new(0.1 + 1e-9)));
+        assert!(!approx_eq(body, warmer, Delta::<Fahrenheit>::new(0.1)));
+        // negative tolerances count by their size
+        assert!(approx_eq(body, warmer, Delta::<Celsius>::new(-0.2)));
+    }
+
+    #[test]
+    fn compares_across_scales() {
+        let freezing = Celsius::new_unchecked(0.0);
+        assert!(freezing < Fahrenheit::new_unchecked(33.0));
+        assert!(freezing > Fahrenheit::new_unchecked(31.0));
+        assert!(freezing > Kelvin::new_unchecked(273.0));
+        assert!(Kelvin::new_unchecked(274.0) > freezing);
+        assert!(Fahrenheit::new_unchecked(-40.0) == Celsius::new_unchecked(-40.0));
+        assert!(Fahrenheit::new_unchecked(-40.0) <= Celsius::new_unchecked(-40.0));
+        assert_eq!(
+            Celsius::new_unchecked(f64::NAN).partial_cmp(&Kelvin::new_unchecked(1.0)),
+            None
+        );
+    }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn checked_constructors_reject_impossible_temperatures() {
+        assert_eq!(Kelvin::new(0.0).map(Kelvin::as_f64), Ok(0.0));
+        assert_eq!(Celsius::new(-273.15).map(Celsius::as_f64), Ok(-273.15));
+        assert_eq!(
+            Fahrenheit::new(-459.67).map(Fahrenheit::as_f64),
+            Ok(-459.67)
+        );
+        assert_eq!(
+            Kelvin::new(-0.01),
+            Err(TemperatureError::BelowAbsoluteZero(-0.01, "K"))
+        );
+        assert_eq!(
+            Celsius::new(-273.16),
+            Err(TemperatureError::BelowAbsoluteZero(-273.16, "C"))
+        );
+        assert_eq!(
+            Fahrenheit::new(-500.0).unwrap_err().to_string(),
+            "-500 °F is below absolute zero"
+        );
+        assert_eq!(
+            Celsius::new(f64::INFINITY),
+            Err(TemperatureError::NotFinite(f64::INFINITY, "C"))
+        );
+        assert_eq!(
+            Kelvin::new(f64::NEG_INFINITY),
+            Err(TemperatureError::NotFinite(f64::NEG_INFINITY, "K"))
+        );
+        // NaN never equals itself, so match rather than compare
+        assert!(matches!(
+            Fahrenheit::new(f64::NAN),
+            Err(TemperatureError::NotFinite(t, "°F")) if t.is_nan()
+        ));
+        assert_eq!(
+            Kelvin::new(f64::NAN).unwrap_err().to_string(),
+            "NaN K is not a finite temperature"
+        );
+    }
+
+    #[test]
+    fn valid_temperatures_are_totally_ordered() {
+        let mut temps: Vec<Valid<Celsius>> = [21.5, -3.0, 100.0, -273.15, 0.0]
+            .into_iter()
+            .map(|t| Valid::try_from(t).unwrap())
+            .collect();
+        temps.sort();
+        let sorted: Vec<f64> = temps.iter().map(|t| t.as_f64()).collect();
+        assert_eq!(sorted, [-273.15, -3.0, 0.0, 21.5, 100.0]);
+        assert_eq!(temps.iter().max().map(|t| t.get().as_f64()), Some(100.0));
+
+        assert!(Valid::<Kelvin>::try_from(f64::NAN).is_err());
+        assert_eq!(
+            Valid::<Celsius>::try_from(-300.0),
+            Err(TemperatureError::BelowAbsoluteZero(-300.0, "C"))
+        );
+        assert_eq!(
+            Valid::new(Fahrenheit::new_unchecked(f64::INFINITY)),
+            Err(TemperatureError::NotFinite(f64::INFINITY, "°F"))
+        );
+    }
+
+    #[test]
+    fn negative_zero_is_the_same_valid_temperature() {
+        let zero = Valid::new(Celsius::new_unchecked(0.0)).unwrap();
+        let negative_zero = Valid::new(Celsius::new_unchecked(-0.0)).unwrap();
+        // total_cmp alone would put -0.0 below 0.0
+        assert_eq!(zero, negative_zero);
+        assert_eq!(zero.cmp(&negative_zero), Ordering::Equal);
+        assert_eq!(hash_of(&zero), hash_of(&negative_zero));
+        assert!(negative_zero.as_f64().is_sign_positive());
+
+        let set: std::collections::HashSet<Valid<Kelvin>> = [0.0, -0.0, 1.0]
+            .into_iter()
+            .map(|t| Valid::try_from(t).unwrap())
+            .collect();
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::tempr::{Celsius, Fahrenheit, Temperature};
+
+// Magnus coefficients (Sonntag 1990), good from -45 C to 60 C.
+const MAGNUS_A: f64 = 17.62;
+const MAGNUS_B: f64 = 243.12;
+const MAGNUS_RANGE: (f64, f64) = (-45.0, 60.0);
+
+/// Whether a temperature and the reading that goes with it are both
+/// ordinary numbers; none of the formulas mean anything otherwise.
+fn finite<T: Temperature>(t: T, reading: f64) -> bool {
+    t.as_f64().is_finite() && reading.is_finite()
+}
+
+/// Dew point from air temperature and relative humidity (percent), using
+/// the Magnus approximation. Within its range it agrees with tabulated
+/// values to about 0.35 C.
+pub fn dew_point<T: Temperature>(t: T, rh: f64) -> Option<T> {
+    if !finite(t, rh) {
+        return None;
+    }
+    let c = t.to_scale::<Celsius>().as_f64();
+    if !(MAGNUS_RANGE.0..=MAGNUS_RANGE.1).contains(&c) || rh <= 0.0 || rh > 100.0 {
+        return None;
+    }
+    let gamma = (rh / 100.0).ln() + MAGNUS_A * c / (MAGNUS_B + c);
+    let dp = Celsius::new_unchecked(MAGNUS_B * gamma / (MAGNUS_A - gamma));
+    Some(dp.to_scale())
+}
+
+/// Heat index as computed by the US National Weather Service: Steadman's
+/// simple formula, or the Rothfusz regression (with its low and high
+/// humidity adjustments) once that reaches 80 °F. The regression is good
+/// to ±1.3 °F of NOAA's table. Only defined from 80 °F upwards.
+pub fn heat_index<T: Temperature>(t: T, rh: f64) -> Option<T> {
+    if !finite(t, rh) {
+        return None;
+    }
+    let f = t.to_scale::<Fahrenheit>().as_f64();
+    if f < 80.0 || !(0.0..=100.0).contains(&rh) {
+        return None;
+    }
+    let simple = 0.5 * (f + 61.0 + (f - 68.0) * 1.2 + rh * 0.094);
+    if (simple + f) / 2.0 < 80.0 {
+        return Some(Fahrenheit::new_unchecked(simple).to_scale());
+    }
+    let mut hi = -42.379 + 2.049_015_23 * f + 10.143_331_27 * rh
+        - 0.224_755_41 * f * rh
+        - 0.006_837_83 * f * f
+        - 0.054_817_17 * rh * rh
+        + 0.001_228_74 * f * f * rh
+        + 0.000_852_82 * f * rh * rh
+        - 0.000_001_99 * f * f * rh * rh;
+    if rh < 13.0 && (80.0..=112.0).contains(&f) {
+        hi -= ((13.0 - rh) / 4.0) * ((17.0 - (f - 95.0).abs()) / 17.0).sqrt();
+    } else if rh > 85.0 && (80.0..=87.0).contains(&f) {
+        hi += ((rh - 85.0) / 10.0) * ((87.0 - f) / 5.0);
+    }
+    Some(Fahrenheit::new_unchecked(hi).to_scale())
+}
+
+/// Wind chill from the 2001 NWS/MSC formula, with the wind speed in km/h
+/// measured at 10 m. Matches NOAA's wind chill chart to within its whole
+/// degree rounding. Only defined at or below 10 C with at least 4.8 km/h
+/// of wind.
+pub fn wind_chill<T: Temperature>(t: T, wind_kmh: f64) -> Option<T> {
+    if !finite(t, wind_kmh) {
+        return None;
+    }
+    let c = t.to_scale::<Celsius>().as_f64();
+    if c > 10.0 || wind_kmh < 4.8 {
+        return None;
+    }
+    let v = wind_kmh.powf(0.16);
+    let wc = 13.12 + 0.6215 * c - 11.37 * v + 0.3965 * c * v;
+    Some(Celsius::new_unchecked(wc).to_scale())
+}
+
+/// What the air "feels like", the way NWS forecasts report it: the wind
+/// chill when it's cold and windy, the heat index when it's hot, and
+/// otherwise the air temperature itself.
+pub fn apparent_temperature<T: Temperature>(t: T, rh: f64, wind_kmh: f64) -> Option<T> {
+    if !finite(t, rh) || !wind_kmh.is_finite() {
+        return None;
+    }
+    if !(0.0..=100.0).contains(&rh) || wind_kmh < 0.0 {
+        return None;
+    }
+    wind_chill(t, wind_kmh)
+        .or_else(|| heat_index(t, rh))
+        .or(Some(t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// mph to km/h, for NOAA's wind chill chart.
+    const KMH_PER_MPH: f64 = 1.609_344;
+
+    #[test]
+    fn heat_index_matches_noaa_table() {
+        // (°F, % RH, NOAA heat index chart)
+        let table = [
+            (80.0, 40.0, 80.0),
+            (86.0, 50.0, 88.0),
+            (86.0, 90.0, 105.0),
+            (90.0, 40.0, 91.0),
+            (90.0, 50.0, 95.0),
+            (90.0, 70.0, 105.0),
+            (90.0, 100.0, 132.0),
+            (96.0, 55.0, 112.0),
+            (100.0, 40.0, 109.0),
+            (100.0, 55.0, 124.0),
+            (110.0, 40.0, 136.0),
+        ];
+        for (f, rh, expected) in table {
+            let hi = heat_index(Fahrenheit::new_unchecked(f), rh)
+                .unwrap()
+                .as_f64();
+            // the Rothfusz regression's stated error against the table
+            assert!(
+                (hi - expected).abs() <= 1.3,
+                "{} °F at {}%: {} vs {}",
+                f,
+                rh,
+                hi,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn wind_chill_matches_noaa_chart() {
+        // (°F, mph, NOAA wind chill chart)
+        let chart = [
+            (40.0, 5.0, 36.0),
+            (40.0, 30.0, 28.0),
+            (30.0, 10.0, 21.0),
+            (30.0, 25.0, 16.0),
+            (0.0, 5.0, -11.0),
+            (0.0, 15.0, -19.0),
+            (0.0, 60.0, -33.0),
+            (-20.0, 10.0, -41.0),
+            (-20.0, 20.0, -48.0),
+        ];
+        for (f, mph, expected) in chart {
+            let wc = wind_chill(Fahrenheit::new_unchecked(f), mph * KMH_PER_MPH)
+                .unwrap()
+                .as_f64();
+            // the chart rounds to whole degrees
+            assert!(
+                (wc - expected).abs() <= 0.5,
+                "{} °F at {} mph: {} vs {}",
+                f,
+                mph,
+                wc,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn dew_point_matches_noaa_calculator() {
+        // (°C, % RH, NOAA dew point calculator)
+        let table = [
+            (20.0, 50.0, 9.3),
+            (30.0, 70.0, 23.9),
+            (25.0, 60.0, 16.7),
+            (10.0, 80.0, 6.7),
+            (35.0, 40.0, 19.4),
+            (0.0, 90.0, -1.4),
+        ];
+        for (c, rh, expected) in table {
+            let dp = dew_point(Celsius::new_unchecked(c), rh).unwrap().as_f64();
+            assert!(
+                (dp - expected).abs() <= 0.35,
+                "{} C at {}%: {} vs {}",
+                c,
+                rh,
+                dp,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_domain_inputs_give_none() {
+        let mild = Celsius::new_unchecked(20.0);
+        assert_eq!(heat_index(Fahrenheit::new_unchecked(79.0), 50.0), None);
+        assert_eq!(wind_chill(Celsius::new_unchecked(11.0), 20.0), None);
+        assert_eq!(wind_chill(Celsius::new_unchecked(0.0), 4.0), None);
+        assert_eq!(dew_point(mild, 0.0), None);
+        assert_eq!(dew_point(mild, 101.0), None);
+        assert_eq!(apparent_temperature(mild, 50.0, 10.0), Some(mild));
+    }
+
+    #[test]
+    fn non_finite_inputs_give_none() {
+        for bad in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let t = Celsius::new_unchecked(bad);
+            let cold = Celsius::new_unchecked(-5.0);
+            let hot = Fahrenheit::new_unchecked(95.0);
+            assert_eq!(dew_point(t, 50.0), None);
+            assert_eq!(dew_point(cold, bad), None);
+            assert_eq!(heat_index(t, 50.0), None);
+            assert_eq!(heat_index(hot, bad), None);
+            assert_eq!(wind_chill(t, 20.0), None);
+            assert_eq!(wind_chill(cold, bad), None);
+            assert_eq!(apparent_temperature(t, 50.0, 20.0), None);
+            assert_eq!(apparent_temperature(cold, bad, 20.0), None);
+            assert_eq!(apparent_temperature(cold, 50.0, bad), None);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use u04::chessboard::pgn::Game;
+use u04::chessboard::{ChessBoard, Color, Glyphs, Move, RenderOptions};
+
+const HELP: &str = "\
+Enter moves in SAN (Nf3, exd5, O-O, e8=Q) or coordinates (g1f3, e7e8q).
+  board         show the board again
+  fen           show the position as FEN
+  moves         list the legal moves
+  flip          turn the board around
+  glyphs        switch between letters and chess symbols
+  colors        switch terminal colours on or off
+  svg FILE      draw the board as an SVG image
+  undo, redo    take back a move, or play it again
+  resign        the side to move resigns
+  draw          both players agree to a draw
+  save FILE     write the game as PGN
+  load FILE     read a game from PGN
+  new           start over
+  new960 [N]    start Chess960 position N, or a random one
+  quit          leave";
+
+/// Two players at one keyboard.
+struct Session {
+    game: Game,
+    /// Moves taken back, the next one to redo last.
+    undone: Vec<Move>,
+    flipped: bool,
+    unicode: bool,
+    ansi: bool,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            game: Game::new(),
+            undone: Vec::new(),
+            flipped: false,
+            unicode: false,
+            ansi: false,
+        }
+    }
+
+    /// A fresh game, seen the same way as before.
+    fn restart(&mut self) {
+        self.game = Game::new();
+        self.undone.clear();
+    }
+
+    fn restart_960(&mut self, index: Option<&str>) {
+        let board = match index.map(str::parse) {
+            None => ChessBoard::random_chess960(),
+            Some(Ok(index)) => match ChessBoard::chess960(index) {
+                Ok(board) => board,
+                Err(e) => return println!("{}", e),
+            },
+            Some(Err(_)) => return println!("new960 takes a number from 0 to 959"),
+        };
+        self.game = Game::from_board(board);
+        self.undone.clear();
+        self.show();
+    }
+
+    fn view(&self) -> RenderOptions {
+        let glyphs = if self.unicode {
+            Glyphs::Unicode
+        } else {
+            Glyphs::Ascii
+        };
+        let view = RenderOptions::new()
+            .labels(true)
+            .flipped(self.flipped)
+            .glyphs(glyphs)
+            .ansi(self.ansi);
+        match self.game.moves().last() {
+            Some(mv) => view.last_move(mv),
+            None => view,
+        }
+    }
+
+    fn prompt(&self) -> String {
+        let board = self.game.board();
+        match board.side_to_move() {
+            Color::White => format!("{}. white> ", board.fullmove_number()),
+            Color::Black => format!("{}... black> ", board.fullmove_number()),
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.game.result() != "*"
+    }
+
+    /// Prints the board and anything the last move brought about.
+    fn show(&mut self) {
+        let board = self.game.board();
+        print!("{}", board.render(&self.view()));
+        if let Some(outcome) = self.game.outcome() {
+            self.game.set_result(outcome.result());
+            println!("{} ({})", outcome, outcome.result());
+        } else if self.is_over() {
+            println!("game over ({})", self.game.result());
+        } else if board.in_check() {
+            println!("{} is in check", board.side_to_move());
+        }
+    }
+
+    fn play(&mut self, text: &str) {
+        if self.is_over() {
+            println!(
+                "the game is over ({}); `undo` or `new` to go on",
+                self.game.result()
+            );
+            return;
+        }
+        match self.game.board().parse_move(text) {
+            Ok(mv) => {
+                self.game.play(mv).expect("parsed moves are legal");
+                self.undone.clear();
+                self.show();
+            }
+            Err(e) => println!("illegal: {}", e),
+        }
+    }
+
+    fn undo(&mut self) {
+        self.game.set_result("*");
+        match self.game.pop() {
+            Some(played) => {
+                println!("took back {}", played.san);
+                self.undone.push(played.mv);
+                self.show();
+            }
+            None => println!("nothing to undo"),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.undone.pop() {
+            Some(mv) => {
+                self.game.set_result("*");
+                self.game.play(mv).expect("undone moves replay");
+                println!(
+                    "replayed {}",
+                    self.game.main_line().moves.last().unwrap().san
+                );
+                self.show();
+            }
+            None => println!("nothing to redo"),
+        }
+    }
+
+    fn finish(&mut self, result: &str, message: &str) {
+        if self.is_over() {
+            println!("the game is already over ({})", self.game.result());
+            return;
+        }
+        self.game.set_result(result);
+        println!("{} ({})", message, result);
+    }
+
+    fn save(&self, path: &str) {
+        match fs::write(path, self.game.to_pgn()) {
+            Ok(()) => println!("saved to {}", path),
+            Err(e) => println!("couldn't save to {}: {}", path, e),
+        }
+    }
+
+    fn svg(&self, path: &str) {
+        match fs::write(path, self.game.board().to_svg(&self.view())) {
+            Ok(()) => println!("drew the board in {}", path),
+            Err(e) => println!("couldn't write {}: {}", path, e),
+        }
+    }
+
+    fn load(&mut self, path: &str) {
+        let loaded = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Game::from_pgn(&text).map_err(|e| e.to_string()));
+        match loaded {
+            Ok(game) => {
+                self.game = game;
+                self.undone.clear();
+                println!("loaded {} plies from {}", self.game.len(), path);
+                self.show();
+            }
+            Err(e) => println!("couldn't load {}: {}", path, e),
+        }
+    }
+
+    fn list_moves(&self) {
+        let board = self.game.board();
+        let mut sans: Vec<String> = board
+            .legal_moves()
+            .into_iter()
+            .map(|mv| board.san(mv))
+            .collect();
+        sans.sort();
+        println!("{}", sans.join(" "));
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut session = Session::new();
+    println!("Type `help` for commands.");
+    session.show();
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("{}", session.prompt());
+        io::stdout().flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            println!();
+            break;
+        };
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            continue;
+        };
+        let argument = words.next();
+        match (command, argument) {
+            ("help" | "?", _) => println!("{}", HELP),
+            ("quit" | "exit", _) => break,
+            ("board", _) => session.show(),
+            ("fen", _) => println!("{}", session.game.board().to_fen()),
+            ("moves", _) => session.list_moves(),
+            ("undo", _) => session.undo(),
+            ("redo", _) => session.redo(),
+            ("new", _) => {
+                session.restart();
+                session.show();
+            }
+            ("new960", index) => session.restart_960(index),
+            ("flip", _) => {
+                session.flipped = !session.flipped;
+                session.show();
+            }
+            ("glyphs", _) => {
+                session.unicode = !session.unicode;
+                session.show();
+            }
+            ("colors", _) => {
+                session.ansi = !session.ansi;
+                session.show();
+            }
+            ("resign", _) => {
+                let loser = session.game.board().side_to_move();
+                let result = match loser {
+                    Color::White => "0-1",
+                    Color::Black => "1-0",
+                };
+                session.finish(result, &format!("{} resigns", loser));
+            }
+            ("draw", _) => session.finish("1/2-1/2", "drawn by agreement"),
+            ("save" | "load" | "svg", None) => println!("{} needs a file name", command),
+            ("save", Some(path)) => session.save(path),
+            ("load", Some(path)) => session.load(path),
+            ("svg", Some(path)) => session.svg(path),
+            (text, None) => session.play(text),
+            _ => println!("didn't understand `{}`; try `help`", line.trim()),
+        }
+    }
+    Ok(())
+}
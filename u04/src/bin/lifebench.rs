@@ -0,0 +1,99 @@
+use std::process;
+use std::thread;
+use std::time::Instant;
+use u04::conway::{self, Backend, Rule, Universe, CATALOGUE};
+
+/// Odd sizes, to catch mistakes at the edges of the packed words.
+const CHECK_SIZES: [(usize, usize); 9] = [
+    (1, 1),
+    (1, 7),
+    (7, 1),
+    (3, 3),
+    (63, 5),
+    (64, 64),
+    (65, 9),
+    (130, 17),
+    (200, 150),
+];
+const CHECK_GENERATIONS: usize = 40;
+
+/// Checks the backends agree, serially and in parallel, on random starts
+/// under every catalogued rule.
+fn check() -> bool {
+    let mut ok = true;
+    for named in &CATALOGUE {
+        for &(width, height) in &CHECK_SIZES {
+            let start = Universe::randomly_seeded(width, height);
+            if let Some(generation) = conway::first_mismatch(&start, named.rule, CHECK_GENERATIONS)
+            {
+                println!(
+                    "MISMATCH: {} on {}x{} at generation {}",
+                    named.name, width, height, generation
+                );
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+/// Cell updates per second stepping a `size` square universe.
+fn bench(backend: Backend, threads: usize, size: usize, generations: usize) -> f64 {
+    let mut cur = Universe::with_backend(size, size, backend);
+    cur.seed(35);
+    let mut next = cur.clone();
+    let started = Instant::now();
+    for _ in 0..generations {
+        if threads > 1 {
+            cur.next_into_parallel(&mut next, Rule::LIFE, threads)
+                .unwrap_or_else(|e| {
+                    eprintln!("error: {}", e);
+                    process::exit(2);
+                });
+        } else {
+            cur.next_into(&mut next);
+        }
+        std::mem::swap(&mut cur, &mut next);
+    }
+    (size * size * generations) as f64 / started.elapsed().as_secs_f64()
+}
+
+fn main() {
+    // usage: lifebench [size] [generations] [threads]; best run with --release
+    let mut args = std::env::args().skip(1).map(|s| s.parse().ok());
+    let size = args.next().flatten().unwrap_or(4096);
+    let generations = args.next().flatten().unwrap_or(10);
+    let threads = args
+        .next()
+        .flatten()
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1);
+
+    if !check() {
+        process::exit(1);
+    }
+    println!(
+        "backends agree on {} rules and {} sizes, serially and in parallel",
+        CATALOGUE.len(),
+        CHECK_SIZES.len()
+    );
+    for backend in [Backend::Rows, Backend::Packed] {
+        let counts = if threads > 1 {
+            vec![1, threads]
+        } else {
+            vec![1]
+        };
+        for threads in counts {
+            let rate = bench(backend, threads, size, generations);
+            println!(
+                "{:?}, {} thread(s): {}x{}, {} generations, {:.1} million cells/s",
+                backend,
+                threads,
+                size,
+                size,
+                generations,
+                rate / 1e6
+            );
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::process;
+use std::time::Duration;
+use u04::chessboard::engine::{Engine, Limits, MATE_IN_TWO};
+
+fn main() {
+    // usage: mates [seconds per puzzle]; best run with --release
+    let seconds = std::env::args()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(10.0);
+    let limits = Limits::new()
+        .depth(4)
+        .time(Duration::from_secs_f64(seconds));
+
+    let mut engine = Engine::new();
+    let mut failed = false;
+    for puzzle in &MATE_IN_TWO {
+        let board = puzzle.board();
+        engine.clear();
+        let result = engine.search(&board, limits);
+        let key = result.best_move.map(|mv| board.san(mv));
+        let solved = result.mate_in() == Some(2) && key.as_deref() == Some(puzzle.key);
+        failed |= !solved;
+        println!(
+            "{}: {} ({} nodes, {:.2?}) {}",
+            puzzle.name,
+            result.pv_san(&board),
+            result.nodes,
+            result.elapsed,
+            if solved { "ok" } else { "FAILED" }
+        );
+    }
+    if failed {
+        process::exit(1);
+    }
+}
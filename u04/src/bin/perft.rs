@@ -0,0 +1,35 @@
+use std::process;
+use std::time::Instant;
+use u04::chessboard::perft::{self, REFERENCE_POSITIONS};
+
+fn main() {
+    // usage: perft [max depth]; best run with --release
+    let max_depth = std::env::args()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(usize::MAX);
+
+    let mut failed = false;
+    for reference in &REFERENCE_POSITIONS {
+        println!("{}", reference.name);
+        let board = reference.board();
+        for (depth, &expected) in reference.nodes.iter().enumerate().take(max_depth) {
+            let depth = depth as u32 + 1;
+            let started = Instant::now();
+            let nodes = perft::perft(&board, depth);
+            let verdict = if nodes == expected { "ok" } else { "MISMATCH" };
+            failed |= nodes != expected;
+            println!(
+                "  depth {}: {:>9} (expected {:>9}) {:>8.2?} {}",
+                depth,
+                nodes,
+                expected,
+                started.elapsed(),
+                verdict
+            );
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
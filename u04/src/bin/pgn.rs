@@ -0,0 +1,88 @@
+use std::fs;
+use std::process;
+use u04::chessboard::pgn::{self, Game};
+
+const USAGE: &str = "usage: pgn FILE [--fen] [--ply N] [--export]";
+
+struct Options {
+    path: String,
+    fen: bool,
+    ply: Option<usize>,
+    export: bool,
+}
+
+fn options() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    let mut options = Options {
+        path: String::new(),
+        fen: false,
+        ply: None,
+        export: false,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fen" => options.fen = true,
+            "--export" => options.export = true,
+            "--ply" => {
+                let n = args.next().ok_or("--ply needs a number")?;
+                options.ply = Some(n.parse().map_err(|_| format!("bad ply `{}`", n))?);
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return Err(format!("unexpected `{}`", arg)),
+        }
+    }
+    options.path = path.ok_or("no PGN file given")?;
+    Ok(options)
+}
+
+fn show(game: &Game, ply: usize, fen: bool) {
+    let board = game.board_at(ply).expect("ply is within the game");
+    if ply > 0 {
+        let played = &game.main_line().moves[ply - 1];
+        println!("ply {}: {}", ply, played.san);
+    } else {
+        println!("start");
+    }
+    if fen {
+        println!("{}", board.to_fen());
+    } else {
+        println!("{}", board);
+    }
+}
+
+fn main() {
+    let options = options().unwrap_or_else(|e| {
+        eprintln!("error: {}\n{}", e, USAGE);
+        process::exit(2);
+    });
+    let text = fs::read_to_string(&options.path).unwrap_or_else(|e| {
+        eprintln!("error: {}: {}", options.path, e);
+        process::exit(1);
+    });
+    let games = pgn::read_games(&text).unwrap_or_else(|e| {
+        eprintln!("error: {}: {}", options.path, e);
+        process::exit(1);
+    });
+
+    for game in &games {
+        if options.export {
+            println!("{}", game);
+            continue;
+        }
+        println!(
+            "{} - {} {}",
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?"),
+            game.result()
+        );
+        match options.ply {
+            Some(ply) if ply > game.len() => {
+                eprintln!("error: the game has only {} plies", game.len());
+                process::exit(1);
+            }
+            Some(ply) => show(game, ply, options.fen),
+            None => (0..=game.len()).for_each(|ply| show(game, ply, options.fen)),
+        }
+    }
+}
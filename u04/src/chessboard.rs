@@ -1,44 +1,155 @@
 use crate::grids;
 use std::fmt;
 
-#[derive(Debug, Clone)]
-pub struct ChessBoard([[char; 8]; 8]);
+pub mod engine;
+mod fen;
+mod movegen;
+pub mod perft;
+pub mod pgn;
+mod pieces;
+mod render;
+mod rules;
+mod san;
+mod setup;
+mod zobrist;
+
+pub use fen::{FenError, START_FEN};
+pub use movegen::{Move, MoveKind};
+pub use pieces::{BadSquare, CastleSide, CastlingRights, Color, Piece, PieceKind, Square};
+pub use render::{Glyphs, RenderOptions, Rendered};
+pub use rules::{MoveError, Outcome};
+pub use san::SanError;
+pub use setup::{PositionBuilder, PositionError, CHESS960_POSITIONS, CLASSICAL_INDEX};
+
+const START_LAYOUT: [[char; 8]; 8] = [
+    ['r', 'n', 'b', 'q', 'k', 'b', 'n', 'r'],
+    ['p', 'p', 'p', 'p', 'p', 'p', 'p', 'p'],
+    [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+    [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+    [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+    [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+    ['P', 'P', 'P', 'P', 'P', 'P', 'P', 'P'],
+    ['R', 'N', 'B', 'Q', 'K', 'B', 'N', 'R'],
+];
+
+/// A position: where the pieces are, whose move it is and what they're
+/// still allowed to do.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChessBoard {
+    squares: [Option<Piece>; 64],
+    side_to_move: Color,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+}
 
 impl ChessBoard {
+    /// Rows run from rank 8 down to rank 1, as the board is printed.
+    /// Uppercase letters are white pieces, lowercase black, and anything
+    /// else an empty square. White is to move, and nobody can castle.
     pub fn new(layout: [[char; 8]; 8]) -> Self {
-        Self(layout)
+        let mut board = Self::blank();
+        for (row, chars) in layout.iter().enumerate() {
+            for (file, &c) in chars.iter().enumerate() {
+                let square = Square::new(file as u8, 7 - row as u8).unwrap();
+                board.squares[square.index()] = Piece::from_char(c);
+            }
+        }
+        board
     }
 
     pub fn blank() -> Self {
-        Self([[' '; 8]; 8])
+        Self {
+            squares: [None; 64],
+            side_to_move: Color::White,
+            castling: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
     }
 
     pub fn at_start() -> Self {
-        Self([
-            ['r', 'n', 'b', 'q', 'k', 'b', 'n', 'r'],
-            ['p', 'p', 'p', 'p', 'p', 'p', 'p', 'p'],
-            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-            ['P', 'P', 'P', 'P', 'P', 'P', 'P', 'P'],
-            ['R', 'N', 'B', 'Q', 'K', 'B', 'N', 'R'],
-        ])
+        let mut board = Self::new(START_LAYOUT);
+        board.castling = CastlingRights::standard();
+        board
     }
 
     pub fn reset(&mut self) {
-        self.0[0] = ['r', 'n', 'b', 'q', 'k', 'b', 'n', 'r'];
-        self.0[1].fill('p');
-        for row in &mut self.0[2..6] {
-            row.fill(' ');
+        *self = Self::at_start();
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.squares[square.index()]
+    }
+
+    pub fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.squares[square.index()] = piece;
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        Square::all().filter_map(|sq| self.piece_at(sq).map(|p| (sq, p)))
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    pub fn set_side_to_move(&mut self, color: Color) {
+        self.side_to_move = color;
+    }
+
+    pub fn castling(&self) -> CastlingRights {
+        self.castling
+    }
+
+    pub fn set_castling(&mut self, castling: CastlingRights) {
+        self.castling = castling;
+    }
+
+    /// The square a pawn skipped over with a double step last move.
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    pub fn set_en_passant(&mut self, square: Option<Square>) {
+        self.en_passant = square;
+    }
+
+    /// Moves since the last capture or pawn move, for the fifty-move rule.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// Starts at 1 and goes up after each of black's moves.
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn set_clocks(&mut self, halfmove_clock: u32, fullmove_number: u32) {
+        self.halfmove_clock = halfmove_clock;
+        self.fullmove_number = fullmove_number.max(1);
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.pieces()
+            .find(|&(_, p)| p == Piece::new(color, PieceKind::King))
+            .map(|(sq, _)| sq)
+    }
+
+    /// The layout `new` takes, rank 8 first.
+    pub fn to_layout(&self) -> [[char; 8]; 8] {
+        let mut layout = [[' '; 8]; 8];
+        for (square, piece) in self.pieces() {
+            layout[7 - square.rank() as usize][square.file() as usize] = piece.to_char();
         }
-        self.0[6].fill('P');
-        self.0[7] = ['R', 'N', 'B', 'Q', 'K', 'B', 'N', 'R'];
+        layout
     }
 }
 
 impl fmt::Display for ChessBoard {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        grids::format_grid_to(f, &self.0)
+        grids::format_grid_to(f, &self.to_layout())
     }
 }
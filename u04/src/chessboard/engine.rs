@@ -0,0 +1,690 @@
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+use super::pgn::Game;
+use super::{ChessBoard, Color, Move, PieceKind};
+
+/// A score beyond any material count: being mated `n` plies from the root
+/// scores `-(MATE - n)`.
+const MATE: i32 = 100_000;
+const INFINITY: i32 = 1_000_000;
+const MAX_PLY: usize = 64;
+/// Scores this close to `MATE` are mates rather than evaluations.
+const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+/// Centipawns, pawn to king, by `PieceKind::index`.
+const VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+/// How much each piece counts towards the middlegame, of 24 at the start.
+const PHASE: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+// Piece-square tables from white's side, laid out as the board prints:
+// rank 8 first. Black's squares are mirrored.
+#[rustfmt::skip]
+const PAWN: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+/// The king hides while there are pieces about...
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+/// ...and comes to the centre once they're gone.
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+/// Material plus piece-square tables, in centipawns for the side to move.
+pub fn evaluate(board: &ChessBoard) -> i32 {
+    let mut phase = 0;
+    let mut score = 0;
+    let mut kings = [0; 2];
+    for (square, piece) in board.pieces() {
+        phase += PHASE[piece.kind.index()];
+        // the tables read from white's side, rank 8 first
+        let row = match piece.color {
+            Color::White => 7 - square.rank(),
+            Color::Black => square.rank(),
+        };
+        let index = row as usize * 8 + square.file() as usize;
+        let sign = if piece.color == board.side_to_move() {
+            1
+        } else {
+            -1
+        };
+        let placement = match piece.kind {
+            PieceKind::Pawn => PAWN[index],
+            PieceKind::Knight => KNIGHT[index],
+            PieceKind::Bishop => BISHOP[index],
+            PieceKind::Rook => ROOK[index],
+            PieceKind::Queen => QUEEN[index],
+            PieceKind::King => {
+                kings[piece.color.index()] = index;
+                0
+            }
+        };
+        score += sign * (VALUES[piece.kind.index()] + placement);
+    }
+
+    let phase = phase.min(24);
+    for color in Color::ALL {
+        let index = kings[color.index()];
+        let king = (KING_MIDDLEGAME[index] * phase + KING_ENDGAME[index] * (24 - phase)) / 24;
+        score += if color == board.side_to_move() {
+            king
+        } else {
+            -king
+        };
+    }
+    score
+}
+
+/// When to stop searching. With neither limit set, the search only stops
+/// at its own maximum depth, which can take a very long time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    depth: Option<u32>,
+    time: Option<Duration>,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plies to search, not counting checks and captures at the end.
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    /// Centipawns for the side to move, or a mate score.
+    pub score: i32,
+    /// The deepest iteration that finished.
+    pub depth: u32,
+    /// The line both sides are expected to play, starting with `best_move`.
+    pub pv: Vec<Move>,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl SearchResult {
+    /// Moves until mate: positive if the side to move gives it, negative if
+    /// it's on the receiving end.
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score >= MATE_BOUND {
+            Some((MATE - self.score + 1) / 2)
+        } else if self.score <= -MATE_BOUND {
+            Some(-(MATE + self.score) / 2)
+        } else {
+            None
+        }
+    }
+
+    /// The principal variation in SAN, with move numbers: `1. Qd8+ Bxd8 2. Re8#`.
+    pub fn pv_san(&self, board: &ChessBoard) -> String {
+        let mut board = board.clone();
+        let mut text = Vec::new();
+        for &mv in &self.pv {
+            if !board.is_legal(mv) {
+                break;
+            }
+            let san = board.san(mv);
+            let number = board.fullmove_number();
+            text.push(match board.side_to_move() {
+                Color::White => format!("{}. {}", number, san),
+                Color::Black if text.is_empty() => format!("{}... {}", number, san),
+                Color::Black => san,
+            });
+            board.make_move(mv);
+        }
+        text.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    /// The score is at least this: the search failed high.
+    Lower,
+    /// The score is at most this: no move raised alpha.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    key: u64,
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best: Option<Move>,
+}
+
+/// Iterative-deepening alpha-beta (principal variation search) with a
+/// transposition table, quiescence search, and killer and history move
+/// ordering.
+pub struct Engine {
+    table: Vec<Option<Entry>>,
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    /// Quiet moves that caused cutoffs, by from and to square.
+    history: Vec<[i32; 64]>,
+    /// Positions earlier in the game and on the current search path, for
+    /// spotting repetitions.
+    path: Vec<u64>,
+    nodes: u64,
+    deadline: Option<Instant>,
+    stopped: bool,
+}
+
+impl Engine {
+    /// With a transposition table of about a million entries.
+    pub fn new() -> Self {
+        Self::with_table_size(1 << 20)
+    }
+
+    /// `entries` is rounded up to a power of two.
+    pub fn with_table_size(entries: usize) -> Self {
+        Self {
+            table: vec![None; entries.max(1).next_power_of_two()],
+            killers: [[None; 2]; MAX_PLY],
+            history: vec![[0; 64]; 64],
+            path: Vec::new(),
+            nodes: 0,
+            deadline: None,
+            stopped: false,
+        }
+    }
+
+    /// Forgets everything learned from earlier searches.
+    pub fn clear(&mut self) {
+        self.table.fill(None);
+        self.killers = [[None; 2]; MAX_PLY];
+        self.history.fill([0; 64]);
+    }
+
+    pub fn search(&mut self, board: &ChessBoard, limits: Limits) -> SearchResult {
+        self.search_from(board, Vec::new(), limits)
+    }
+
+    /// Searches the position at the end of `game`, steering clear of (or
+    /// towards) repeating its earlier positions.
+    pub fn search_game(&mut self, game: &Game, limits: Limits) -> SearchResult {
+        let mut earlier: Vec<u64> = game.boards().map(|board| board.zobrist()).collect();
+        earlier.pop();
+        self.search_from(game.board(), earlier, limits)
+    }
+
+    fn search_from(
+        &mut self,
+        board: &ChessBoard,
+        earlier: Vec<u64>,
+        limits: Limits,
+    ) -> SearchResult {
+        let started = Instant::now();
+        self.path = earlier;
+        self.nodes = 0;
+        self.stopped = false;
+        self.deadline = limits.time.map(|time| started + time);
+        self.killers = [[None; 2]; MAX_PLY];
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            depth: 0,
+            pv: Vec::new(),
+            nodes: 0,
+            elapsed: Duration::ZERO,
+        };
+        let max_depth = limits
+            .depth
+            .unwrap_or(MAX_PLY as u32 / 2)
+            .clamp(1, MAX_PLY as u32 / 2);
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = self.negamax(board, depth, 0, -INFINITY, INFINITY, &mut pv);
+            if self.stopped {
+                // a cut-short iteration is only worth anything if nothing
+                // finished before it
+                if result.pv.is_empty() {
+                    result.pv = pv;
+                }
+                break;
+            }
+            result.score = score;
+            result.depth = depth;
+            result.pv = pv;
+            // iterative deepening finds the shortest mate first
+            if score.abs() >= MATE_BOUND && MATE - score.abs() <= depth as i32 {
+                break;
+            }
+        }
+        result.best_move = result
+            .pv
+            .first()
+            .copied()
+            .or_else(|| board.legal_moves().first().copied());
+        result.nodes = self.nodes;
+        result.elapsed = started.elapsed();
+        result
+    }
+
+    fn out_of_time(&mut self) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(1024) {
+            self.stopped = self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.stopped
+    }
+
+    fn negamax(
+        &mut self,
+        board: &ChessBoard,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        mut beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        self.nodes += 1;
+        if self.out_of_time() {
+            return 0;
+        }
+        let hash = board.zobrist();
+        if ply > 0 {
+            if board.halfmove_clock() >= 100
+                || self.path.contains(&hash)
+                || board.has_insufficient_material()
+            {
+                return 0;
+            }
+            // no line from here can beat a mate already found nearer the root
+            alpha = alpha.max(-MATE + ply as i32);
+            beta = beta.min(MATE - ply as i32 - 1);
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(board);
+        }
+
+        let in_check = board.in_check();
+        // look one ply further out of checks, so mates aren't cut off
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth == 0 {
+            return self.quiesce(board, ply, alpha, beta);
+        }
+
+        let is_pv = beta - alpha > 1;
+        let entry = self.probe(hash);
+        if let Some(entry) = entry.filter(|e| !is_pv && e.depth >= depth) {
+            let score = score_from_table(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return score,
+                Bound::Upper if score <= alpha => return score,
+                _ => {}
+            }
+        }
+
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        self.order(&mut moves, entry.and_then(|e| e.best), ply);
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        self.path.push(hash);
+        for (i, &mv) in moves.iter().enumerate() {
+            let mut child = board.clone();
+            child.make_move(mv);
+            let score = if i == 0 {
+                -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha, &mut child_pv)
+            } else {
+                // prove the move is no better with a null window, and only
+                // search it properly if that fails
+                let score = -self.negamax(
+                    &child,
+                    depth - 1,
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                    &mut child_pv,
+                );
+                if score > alpha && score < beta {
+                    -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha, &mut child_pv)
+                } else {
+                    score
+                }
+            };
+            if self.stopped {
+                self.path.pop();
+                return 0;
+            }
+            if score > best {
+                best = score;
+                best_move = Some(mv);
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(mv);
+                    pv.extend_from_slice(&child_pv);
+                }
+            }
+            if alpha >= beta {
+                if !mv.is_capture() && mv.promotion.is_none() {
+                    self.remember_cutoff(mv, depth, ply);
+                }
+                break;
+            }
+        }
+        self.path.pop();
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.store(Entry {
+            key: hash,
+            depth,
+            score: score_to_table(best, ply),
+            bound,
+            best: best_move,
+        });
+        best
+    }
+
+    /// Plays out captures and promotions (and every way out of check) until
+    /// the position is quiet, so a search never stops halfway through an
+    /// exchange.
+    fn quiesce(&mut self, board: &ChessBoard, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.out_of_time() {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(board);
+        }
+
+        let in_check = board.in_check();
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+        let mut best = -INFINITY;
+        if !in_check {
+            // standing pat: the side to move needn't capture anything
+            best = evaluate(board);
+            if best >= beta {
+                return best;
+            }
+            alpha = alpha.max(best);
+            moves.retain(|mv| mv.is_capture() || mv.promotion.is_some());
+        }
+        self.order(&mut moves, None, ply);
+
+        for mv in moves {
+            let mut child = board.clone();
+            child.make_move(mv);
+            let score = -self.quiesce(&child, ply + 1, -beta, -alpha);
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+
+    /// Best first: the table's move, winning captures, promotions, then
+    /// quiet moves that cut off elsewhere.
+    fn order(&self, moves: &mut [Move], table_move: Option<Move>, ply: usize) {
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        moves.sort_by_cached_key(|&mv| {
+            Reverse(if Some(mv) == table_move {
+                INFINITY
+            } else if let Some(captured) = mv.captured {
+                // most valuable victim, least valuable attacker
+                200_000 + 10 * VALUES[captured.index()] - VALUES[mv.piece.index()]
+            } else if let Some(kind) = mv.promotion {
+                190_000 + VALUES[kind.index()]
+            } else if Some(mv) == killers[0] {
+                180_000
+            } else if Some(mv) == killers[1] {
+                179_000
+            } else {
+                self.history[mv.from.index()][mv.to.index()]
+            })
+        });
+    }
+
+    fn remember_cutoff(&mut self, mv: Move, depth: u32, ply: usize) {
+        if let Some(killers) = self.killers.get_mut(ply) {
+            if killers[0] != Some(mv) {
+                killers[1] = killers[0];
+                killers[0] = Some(mv);
+            }
+        }
+        let entry = &mut self.history[mv.from.index()][mv.to.index()];
+        *entry = (*entry + (depth * depth) as i32).min(170_000);
+    }
+
+    fn probe(&self, hash: u64) -> Option<Entry> {
+        let slot = hash as usize & (self.table.len() - 1);
+        self.table[slot].filter(|entry| entry.key == hash)
+    }
+
+    fn store(&mut self, entry: Entry) {
+        let slot = entry.key as usize & (self.table.len() - 1);
+        self.table[slot] = Some(entry);
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Mate scores count plies from the root, but a table entry can be reached
+/// at any ply, so they're stored counting from the entry's own position.
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// A position with one first move that forces mate.
+pub struct Puzzle {
+    pub name: &'static str,
+    pub fen: &'static str,
+    /// The key move, in SAN.
+    pub key: &'static str,
+}
+
+impl Puzzle {
+    pub fn board(&self) -> ChessBoard {
+        ChessBoard::from_fen(self.fen).expect("puzzle positions are valid FEN")
+    }
+}
+
+/// Mate-in-two problems, each with a single key move.
+pub const MATE_IN_TWO: [Puzzle; 6] = [
+    Puzzle {
+        name: "Morphy's problem",
+        fen: "kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1",
+        key: "Ra6",
+    },
+    Puzzle {
+        name: "Legall's mate",
+        fen: "r2qkbnr/ppp2ppp/2np4/4N3/2B1P3/2N5/PPPP1PPP/R1BbK2R w KQkq - 0 6",
+        key: "Bxf7+",
+    },
+    Puzzle {
+        name: "Anastasia's mate",
+        fen: "5r1k/4Nppp/8/7Q/8/8/8/K3R3 w - - 0 1",
+        key: "Qxh7+",
+    },
+    Puzzle {
+        name: "back rank after a queen sacrifice",
+        fen: "r1b2k1r/ppp1bppp/8/1B1Q4/5q2/2P5/PPP2PPP/R3R1K1 w - - 1 1",
+        key: "Qd8+",
+    },
+    Puzzle {
+        name: "queen sacrifice on g6",
+        fen: "r2qk2r/pb4pp/1n2Pb2/2B2Q2/p1p5/2P5/2B2PPP/RN2R1K1 w - - 1 1",
+        key: "Qg6+",
+    },
+    Puzzle {
+        name: "black rook sacrifice on g1",
+        fen: "6k1/pp4p1/2p5/2bp4/8/P5Pb/1P3rrP/2BRRN1K b - - 0 1",
+        key: "Rg1+",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_the_mate_in_two_puzzles() {
+        let mut engine = Engine::new();
+        for puzzle in &MATE_IN_TWO {
+            let board = puzzle.board();
+            engine.clear();
+            let result = engine.search(&board, Limits::new().depth(3));
+            let key = result.best_move.map(|mv| board.san(mv));
+            assert_eq!(key.as_deref(), Some(puzzle.key), "{}", puzzle.name);
+            assert_eq!(result.mate_in(), Some(2), "{}", puzzle.name);
+
+            // the line starts with the key and ends in mate three plies on
+            assert_eq!(result.pv.len(), 3, "{}: {:?}", puzzle.name, result.pv);
+            assert_eq!(result.pv.first().copied(), result.best_move);
+            let mut after = board.clone();
+            for &mv in &result.pv {
+                assert!(
+                    after.is_legal(mv),
+                    "{}: {}",
+                    puzzle.name,
+                    result.pv_san(&board)
+                );
+                after.make_move(mv);
+            }
+            assert!(
+                after.is_checkmate(),
+                "{}: {}",
+                puzzle.name,
+                result.pv_san(&board)
+            );
+        }
+    }
+
+    #[test]
+    fn writes_the_principal_variation_in_san() {
+        let puzzle = &MATE_IN_TWO[3];
+        let board = puzzle.board();
+        let result = Engine::new().search(&board, Limits::new().depth(3));
+        assert_eq!(result.pv_san(&board), "1. Qd8+ Bxd8 2. Re8#");
+    }
+}
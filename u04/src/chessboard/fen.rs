@@ -0,0 +1,409 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use super::{CastleSide, CastlingRights, ChessBoard, Color, Piece, PieceKind, Square};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FenError {
+    #[error("expected 6 fields (or 4, without the clocks) but found {0}")]
+    FieldCount(usize),
+    #[error("expected 8 ranks but found {0}")]
+    RankCount(usize),
+    #[error("rank {rank}: {message}")]
+    Rank { rank: u8, message: String },
+    #[error("side to move: expected `w` or `b` but found `{0}`")]
+    SideToMove(String),
+    #[error("castling: {0}")]
+    Castling(String),
+    #[error("en passant: {0}")]
+    EnPassant(String),
+    #[error("halfmove clock: `{0}` is not a whole number")]
+    HalfmoveClock(String),
+    #[error("fullmove number: `{0}` is not a whole number from 1 up")]
+    FullmoveNumber(String),
+}
+
+impl ChessBoard {
+    /// Reads Forsyth–Edwards Notation. Castling may be given as `KQkq`,
+    /// or by rook file (`HAha`, as Shredder-FEN and X-FEN do for Chess960).
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 && fields.len() != 4 {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let mut board = ChessBoard::blank();
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+        for (row, text) in ranks.iter().enumerate() {
+            let rank = 7 - row as u8;
+            parse_rank(&mut board, rank, text).map_err(|message| FenError::Rank {
+                rank: rank + 1,
+                message,
+            })?;
+        }
+
+        board.side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::SideToMove(other.to_string())),
+        };
+        board.castling = parse_castling(&board, fields[2])?;
+        board.en_passant = parse_en_passant(board.side_to_move, fields[3])?;
+
+        if let [halfmove, fullmove] = fields[4..] {
+            board.halfmove_clock = halfmove
+                .parse()
+                .map_err(|_| FenError::HalfmoveClock(halfmove.to_string()))?;
+            board.fullmove_number = fullmove
+                .parse()
+                .ok()
+                .filter(|&n| n >= 1)
+                .ok_or_else(|| FenError::FullmoveNumber(fullmove.to_string()))?;
+        }
+        Ok(board)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.piece_at(Square::new(file, rank).unwrap()) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            write!(fen, "{}", empty).unwrap();
+                            empty = 0;
+                        }
+                        fen.push(piece.to_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                write!(fen, "{}", empty).unwrap();
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match self.side_to_move {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+
+        fen.push(' ');
+        let castling = self.castling_field();
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+
+        match self.en_passant {
+            Some(square) => write!(fen, " {}", square).unwrap(),
+            None => fen.push_str(" -"),
+        }
+        write!(fen, " {} {}", self.halfmove_clock, self.fullmove_number).unwrap();
+        fen
+    }
+
+    fn castling_field(&self) -> String {
+        let mut field = String::new();
+        for color in Color::ALL {
+            for side in CastleSide::ALL {
+                let Some(file) = self.castling.rook_file(color, side) else {
+                    continue;
+                };
+                // K and Q mean the outermost rook on that side, so only
+                // other rooks need their file spelled out
+                let letter = if outermost_rook(self, color, side) == Some(file) {
+                    match side {
+                        CastleSide::King => 'K',
+                        CastleSide::Queen => 'Q',
+                    }
+                } else {
+                    (b'A' + file) as char
+                };
+                field.push(match color {
+                    Color::White => letter,
+                    Color::Black => letter.to_ascii_lowercase(),
+                });
+            }
+        }
+        field
+    }
+}
+
+impl FromStr for ChessBoard {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ChessBoard::from_fen(s)
+    }
+}
+
+fn parse_rank(board: &mut ChessBoard, rank: u8, text: &str) -> Result<(), String> {
+    let mut file = 0u8;
+    let mut after_digit = false;
+    for c in text.chars() {
+        if let Some(n) = c.to_digit(10) {
+            if !(1..=8).contains(&n) {
+                return Err(format!("`{}` is not a count of 1 to 8 empty squares", c));
+            }
+            if after_digit {
+                return Err(format!(
+                    "`{}` has two counts of empty squares in a row",
+                    text
+                ));
+            }
+            after_digit = true;
+            file += n as u8;
+        } else {
+            after_digit = false;
+            let piece = Piece::from_char(c).ok_or_else(|| format!("`{}` is not a piece", c))?;
+            if file < 8 {
+                board.set_piece(Square::new(file, rank).unwrap(), Some(piece));
+            }
+            file += 1;
+        }
+        if file > 8 {
+            return Err(format!("`{}` covers more than 8 squares", text));
+        }
+    }
+    if file < 8 {
+        return Err(format!("`{}` covers only {} squares", text, file));
+    }
+    Ok(())
+}
+
+fn parse_castling(board: &ChessBoard, field: &str) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::none();
+    if field == "-" {
+        return Ok(rights);
+    }
+    for c in field.chars() {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let king = board
+            .king_square(color)
+            .filter(|k| k.rank() == color.back_rank())
+            .ok_or_else(|| {
+                FenError::Castling(format!(
+                    "`{}` but {}'s king isn't on its back rank",
+                    c, color
+                ))
+            })?;
+        let (side, file) = match c.to_ascii_uppercase() {
+            'K' => (
+                CastleSide::King,
+                outermost_rook(board, color, CastleSide::King),
+            ),
+            'Q' => (
+                CastleSide::Queen,
+                outermost_rook(board, color, CastleSide::Queen),
+            ),
+            f @ 'A'..='H' => {
+                let file = f as u8 - b'A';
+                let side = if file > king.file() {
+                    CastleSide::King
+                } else {
+                    CastleSide::Queen
+                };
+                (side, Some(file))
+            }
+            _ => {
+                return Err(FenError::Castling(format!(
+                    "`{}` is not a castling right",
+                    c
+                )))
+            }
+        };
+        let rook = Piece::new(color, PieceKind::Rook);
+        let file = file
+            .filter(|&f| board.piece_at(Square::new(f, color.back_rank()).unwrap()) == Some(rook))
+            .ok_or_else(|| {
+                FenError::Castling(format!("`{}` but there's no rook to castle with", c))
+            })?;
+        if rights.has(color, side) {
+            return Err(FenError::Castling(format!("`{}` repeats a right", c)));
+        }
+        rights.set(color, side, Some(file));
+    }
+    Ok(rights)
+}
+
+/// The file of the rook furthest from the king on `side`, on `color`'s
+/// back rank.
+fn outermost_rook(board: &ChessBoard, color: Color, side: CastleSide) -> Option<u8> {
+    let king = board.king_square(color)?;
+    let rook = Some(Piece::new(color, PieceKind::Rook));
+    let is_rook =
+        |file: &u8| board.piece_at(Square::new(*file, color.back_rank()).unwrap()) == rook;
+    match side {
+        CastleSide::King => (king.file() + 1..8).rev().find(is_rook),
+        CastleSide::Queen => (0..king.file()).find(is_rook),
+    }
+}
+
+fn parse_en_passant(side_to_move: Color, field: &str) -> Result<Option<Square>, FenError> {
+    if field == "-" {
+        return Ok(None);
+    }
+    let square: Square = field
+        .parse()
+        .map_err(|e: super::BadSquare| FenError::EnPassant(e.to_string()))?;
+    // the square skipped over is behind the pawn that just moved
+    let expected = (!side_to_move).back_rank() as i8 - 2 * side_to_move.forward();
+    if square.rank() as i8 != expected {
+        return Err(FenError::EnPassant(format!(
+            "`{}` is not on rank {} as it must be with {} to move",
+            field,
+            expected + 1,
+            side_to_move
+        )));
+    }
+    Ok(Some(square))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(fen: &str) {
+        let board = ChessBoard::from_fen(fen).unwrap();
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(ChessBoard::from_fen(&board.to_fen()).unwrap(), board);
+    }
+
+    fn rank_error(fen: &str) -> (u8, String) {
+        match ChessBoard::from_fen(fen) {
+            Err(FenError::Rank { rank, message }) => (rank, message),
+            other => panic!("expected a rank error from {fen}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn standard_positions_round_trip() {
+        round_trip(START_FEN);
+        round_trip("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+        round_trip("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 12 40");
+        round_trip("8/8/4k3/8/8/3K4/8/8 b - - 99 120");
+        assert_eq!(START_FEN.parse::<ChessBoard>(), Ok(ChessBoard::at_start()));
+    }
+
+    #[test]
+    fn en_passant_squares_round_trip() {
+        round_trip("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+        round_trip("rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 2");
+    }
+
+    #[test]
+    fn clocks_default_when_left_out() {
+        let board = ChessBoard::from_fen("8/8/4k3/8/8/3K4/8/8 w - -").unwrap();
+        assert_eq!(board.to_fen(), "8/8/4k3/8/8/3K4/8/8 w - - 0 1");
+    }
+
+    #[test]
+    fn shredder_castling_reads_as_the_same_rights() {
+        let shredder = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1";
+        let board = ChessBoard::from_fen(shredder).unwrap();
+        assert_eq!(board, ChessBoard::at_start());
+        assert_eq!(board.to_fen(), START_FEN);
+    }
+
+    #[test]
+    fn x_fen_spells_out_only_inner_rooks() {
+        // with rooks on g1 and h1, K would mean h1, so castling with g1 is G
+        round_trip("1r2k1rr/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR w GQgq - 0 1");
+        let shredder = "1r2k1rr/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR w GBgb - 0 1";
+        assert_eq!(
+            ChessBoard::from_fen(shredder).unwrap().to_fen(),
+            "1r2k1rr/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR w GQgq - 0 1"
+        );
+        round_trip("1r2k1rr/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR b Kg - 3 9");
+    }
+
+    #[test]
+    fn malformed_ranks_are_named() {
+        assert_eq!(
+            rank_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN w - - 0 1"),
+            (1, "`RNBQKBN` covers only 7 squares".to_string())
+        );
+        assert_eq!(
+            rank_error("rnbqkbnr/ppppXppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"),
+            (7, "`X` is not a piece".to_string())
+        );
+        assert_eq!(
+            rank_error("rnbqkbnr/pppppppp/44/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"),
+            (
+                6,
+                "`44` has two counts of empty squares in a row".to_string()
+            )
+        );
+        assert_eq!(
+            rank_error("rnbqkbnr/pppppppp/8/9/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"),
+            (5, "`9` is not a count of 1 to 8 empty squares".to_string())
+        );
+        assert_eq!(
+            rank_error("rnbqkbnr/pppppppp/8/8/4p4/8/PPPPPPPP/RNBQKBNR w - - 0 1"),
+            (4, "`4p4` covers more than 8 squares".to_string())
+        );
+        let err = ChessBoard::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN w - - 0 1")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "rank 1: `RNBQKBN` covers only 7 squares");
+        assert_eq!(
+            ChessBoard::from_fen("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"),
+            Err(FenError::RankCount(7))
+        );
+    }
+
+    #[test]
+    fn malformed_fields_are_named() {
+        let with = |field: usize, text: &str| {
+            let mut fields: Vec<&str> = START_FEN.split(' ').collect();
+            fields[field] = text;
+            ChessBoard::from_fen(&fields.join(" "))
+        };
+        assert_eq!(
+            ChessBoard::from_fen("8/8/8/8/8/8/8/8 w -"),
+            Err(FenError::FieldCount(3))
+        );
+        assert_eq!(with(1, "x"), Err(FenError::SideToMove("x".to_string())));
+        assert_eq!(
+            with(2, "KQkqX"),
+            Err(FenError::Castling(
+                "`X` is not a castling right".to_string()
+            ))
+        );
+        assert_eq!(
+            with(2, "KK"),
+            Err(FenError::Castling("`K` repeats a right".to_string()))
+        );
+        assert_eq!(
+            with(2, "C"),
+            Err(FenError::Castling(
+                "`C` but there's no rook to castle with".to_string()
+            ))
+        );
+        assert_eq!(
+            with(3, "e3").unwrap_err().to_string(),
+            "en passant: `e3` is not on rank 6 as it must be with white to move"
+        );
+        assert!(matches!(with(3, "z9"), Err(FenError::EnPassant(_))));
+        assert_eq!(
+            with(4, "-1"),
+            Err(FenError::HalfmoveClock("-1".to_string()))
+        );
+        assert_eq!(with(5, "0"), Err(FenError::FullmoveNumber("0".to_string())));
+        assert_eq!(
+            with(5, "one").unwrap_err().to_string(),
+            "fullmove number: `one` is not a whole number from 1 up"
+        );
+    }
+}
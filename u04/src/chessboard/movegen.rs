@@ -0,0 +1,402 @@
+use std::fmt;
+
+use super::{CastleSide, ChessBoard, Color, Piece, PieceKind, Square};
+
+const KNIGHT: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const ROOK: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const BISHOP: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveKind {
+    Normal,
+    /// A pawn's first move two squares forward, which allows en passant.
+    DoublePush,
+    EnPassant,
+    /// `to` is where the king lands; the rook comes from the castling
+    /// rights.
+    Castle(CastleSide),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub piece: PieceKind,
+    pub from: Square,
+    pub to: Square,
+    pub captured: Option<PieceKind>,
+    pub promotion: Option<PieceKind>,
+    pub kind: MoveKind,
+}
+
+impl Move {
+    fn new(piece: PieceKind, from: Square, to: Square, captured: Option<PieceKind>) -> Self {
+        Self {
+            piece,
+            from,
+            to,
+            captured,
+            promotion: None,
+            kind: MoveKind::Normal,
+        }
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+}
+
+/// Coordinate notation, as UCI uses: `e2e4`, `e7e8q`, `e1g1`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(kind) = self.promotion {
+            write!(f, "{}", kind.letter().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl ChessBoard {
+    /// Every move the side to move can make, leaving its own king safe.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let us = self.side_to_move;
+        let mut moves = self.pseudo_legal_moves();
+        moves.retain(|&mv| {
+            let mut after = self.clone();
+            after.make_move(mv);
+            after
+                .king_square(us)
+                .is_none_or(|king| !after.is_attacked(king, !us))
+        });
+        moves
+    }
+
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.legal_moves().contains(&mv)
+    }
+
+    /// Every move by the rules of how pieces move, whether or not it leaves
+    /// the mover's king in check. Castling is only generated when the king
+    /// doesn't start in, pass through or land on an attacked square.
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let us = self.side_to_move;
+        let mut moves = Vec::with_capacity(48);
+        for (from, piece) in self.pieces().filter(|(_, p)| p.color == us) {
+            match piece.kind {
+                PieceKind::Pawn => self.pawn_moves(from, &mut moves),
+                PieceKind::Knight => self.step_moves(from, piece.kind, &KNIGHT, &mut moves),
+                PieceKind::Bishop => self.slide_moves(from, piece.kind, &BISHOP, &mut moves),
+                PieceKind::Rook => self.slide_moves(from, piece.kind, &ROOK, &mut moves),
+                PieceKind::Queen => {
+                    self.slide_moves(from, piece.kind, &BISHOP, &mut moves);
+                    self.slide_moves(from, piece.kind, &ROOK, &mut moves);
+                }
+                PieceKind::King => {
+                    self.step_moves(from, piece.kind, &KING, &mut moves);
+                    self.castle_moves(from, &mut moves);
+                }
+            }
+        }
+        moves
+    }
+
+    fn pawn_moves(&self, from: Square, moves: &mut Vec<Move>) {
+        let us = self.side_to_move;
+        let forward = us.forward();
+        let last_rank = (!us).back_rank();
+        let mut push = |mv: Move| {
+            if mv.to.rank() == last_rank {
+                for kind in PieceKind::PROMOTIONS {
+                    moves.push(Move {
+                        promotion: Some(kind),
+                        ..mv
+                    });
+                }
+            } else {
+                moves.push(mv);
+            }
+        };
+
+        if let Some(one) = from
+            .offset(0, forward)
+            .filter(|&sq| self.piece_at(sq).is_none())
+        {
+            push(Move::new(PieceKind::Pawn, from, one, None));
+            let start_rank = (us.back_rank() as i8 + forward) as u8;
+            if from.rank() == start_rank {
+                if let Some(two) = one
+                    .offset(0, forward)
+                    .filter(|&sq| self.piece_at(sq).is_none())
+                {
+                    push(Move {
+                        kind: MoveKind::DoublePush,
+                        ..Move::new(PieceKind::Pawn, from, two, None)
+                    });
+                }
+            }
+        }
+        for side in [-1, 1] {
+            let Some(to) = from.offset(side, forward) else {
+                continue;
+            };
+            match self.piece_at(to) {
+                Some(p) if p.color != us => {
+                    push(Move::new(PieceKind::Pawn, from, to, Some(p.kind)));
+                }
+                None if self.en_passant == Some(to) => push(Move {
+                    kind: MoveKind::EnPassant,
+                    ..Move::new(PieceKind::Pawn, from, to, Some(PieceKind::Pawn))
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    fn step_moves(&self, from: Square, kind: PieceKind, steps: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for &(df, dr) in steps {
+            let Some(to) = from.offset(df, dr) else {
+                continue;
+            };
+            match self.piece_at(to) {
+                None => moves.push(Move::new(kind, from, to, None)),
+                Some(p) if p.color != self.side_to_move => {
+                    moves.push(Move::new(kind, from, to, Some(p.kind)))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn slide_moves(&self, from: Square, kind: PieceKind, rays: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for &(df, dr) in rays {
+            let mut to = from;
+            while let Some(next) = to.offset(df, dr) {
+                to = next;
+                match self.piece_at(to) {
+                    None => moves.push(Move::new(kind, from, to, None)),
+                    Some(p) => {
+                        if p.color != self.side_to_move {
+                            moves.push(Move::new(kind, from, to, Some(p.kind)));
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn castle_moves(&self, king: Square, moves: &mut Vec<Move>) {
+        let us = self.side_to_move;
+        let rank = us.back_rank();
+        if king.rank() != rank || self.is_attacked(king, !us) {
+            return;
+        }
+        for side in CastleSide::ALL {
+            let Some(rook) = self.castling_rook(us, side) else {
+                continue;
+            };
+            let king_to = Square::new(side.king_file(), rank).unwrap();
+            let rook_to = Square::new(side.rook_file(), rank).unwrap();
+            // everything the king and rook cross, ends included, must be
+            // empty but for the two of them
+            let clear = files_between(king, king_to)
+                .chain(files_between(rook, rook_to))
+                .all(|file| {
+                    let sq = Square::new(file, rank).unwrap();
+                    sq == king || sq == rook || self.piece_at(sq).is_none()
+                });
+            let safe = files_between(king, king_to)
+                .all(|file| !self.is_attacked(Square::new(file, rank).unwrap(), !us));
+            if clear && safe {
+                moves.push(Move {
+                    kind: MoveKind::Castle(side),
+                    ..Move::new(PieceKind::King, king, king_to, None)
+                });
+            }
+        }
+    }
+
+    /// Where the rook `color` may castle with on `side` stands, if it still
+    /// may and is still there.
+    pub fn castling_rook(&self, color: Color, side: CastleSide) -> Option<Square> {
+        let file = self.castling.rook_file(color, side)?;
+        let square = Square::new(file, color.back_rank())?;
+        (self.piece_at(square) == Some(Piece::new(color, PieceKind::Rook))).then_some(square)
+    }
+
+    /// Plays `mv`, which should be one of `legal_moves`. Anything else
+    /// leaves the position in a state the rules don't allow.
+    pub fn make_move(&mut self, mv: Move) {
+        let us = self.side_to_move;
+        let piece = self.piece_at(mv.from);
+        self.en_passant = None;
+        self.halfmove_clock += 1;
+        if mv.piece == PieceKind::Pawn || mv.captured.is_some() {
+            self.halfmove_clock = 0;
+        }
+
+        match mv.kind {
+            MoveKind::Castle(side) => {
+                let rook = self.castling_rook(us, side);
+                self.set_piece(mv.from, None);
+                if let Some(rook) = rook {
+                    self.set_piece(rook, None);
+                }
+                let rook_to = Square::new(side.rook_file(), us.back_rank()).unwrap();
+                self.set_piece(rook_to, Some(Piece::new(us, PieceKind::Rook)));
+                self.set_piece(mv.to, piece);
+            }
+            MoveKind::EnPassant => {
+                let taken = Square::new(mv.to.file(), mv.from.rank()).unwrap();
+                self.set_piece(taken, None);
+                self.set_piece(mv.from, None);
+                self.set_piece(mv.to, piece);
+            }
+            MoveKind::DoublePush => {
+                self.en_passant = mv.from.offset(0, us.forward());
+                self.set_piece(mv.from, None);
+                self.set_piece(mv.to, piece);
+            }
+            MoveKind::Normal => {
+                self.set_piece(mv.from, None);
+                let placed = match mv.promotion {
+                    Some(kind) => Some(Piece::new(us, kind)),
+                    None => piece,
+                };
+                self.set_piece(mv.to, placed);
+            }
+        }
+
+        if mv.piece == PieceKind::King {
+            self.castling.clear_color(us);
+        }
+        for color in Color::ALL {
+            for side in CastleSide::ALL {
+                let Some(file) = self.castling.rook_file(color, side) else {
+                    continue;
+                };
+                let corner = Square::new(file, color.back_rank()).unwrap();
+                if mv.kind == MoveKind::Normal && (mv.from == corner || mv.to == corner) {
+                    self.castling.set(color, side, None);
+                }
+            }
+        }
+
+        if us == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = !us;
+    }
+
+    /// Whether any piece of `by` attacks `square`.
+    pub fn is_attacked(&self, square: Square, by: Color) -> bool {
+        !self.attackers(square, by).is_empty()
+    }
+
+    /// The squares of the pieces of `by` that attack `square`.
+    pub fn attackers(&self, square: Square, by: Color) -> Vec<Square> {
+        let mut found = Vec::new();
+        let is = |sq: Square, kinds: &[PieceKind]| {
+            self.piece_at(sq)
+                .is_some_and(|p| p.color == by && kinds.contains(&p.kind))
+        };
+        for side in [-1, 1] {
+            if let Some(sq) = square.offset(side, -by.forward()) {
+                if is(sq, &[PieceKind::Pawn]) {
+                    found.push(sq);
+                }
+            }
+        }
+        for (steps, kind) in [(&KNIGHT, PieceKind::Knight), (&KING, PieceKind::King)] {
+            for &(df, dr) in steps {
+                if let Some(sq) = square.offset(df, dr).filter(|&sq| is(sq, &[kind])) {
+                    found.push(sq);
+                }
+            }
+        }
+        for (rays, kind) in [(&ROOK, PieceKind::Rook), (&BISHOP, PieceKind::Bishop)] {
+            for &(df, dr) in rays {
+                if let Some(sq) = self.first_piece(square, df, dr) {
+                    if is(sq, &[kind, PieceKind::Queen]) {
+                        found.push(sq);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    pub fn in_check(&self) -> bool {
+        !self.checkers().is_empty()
+    }
+
+    /// The pieces giving check to the side to move.
+    pub fn checkers(&self) -> Vec<Square> {
+        let us = self.side_to_move;
+        match self.king_square(us) {
+            Some(king) => self.attackers(king, !us),
+            None => Vec::new(),
+        }
+    }
+
+    /// The pieces of `color` that can't leave the line between their king
+    /// and an enemy rook, bishop or queen without exposing the king.
+    pub fn pinned(&self, color: Color) -> Vec<Square> {
+        let Some(king) = self.king_square(color) else {
+            return Vec::new();
+        };
+        let mut pinned = Vec::new();
+        for (rays, kind) in [(&ROOK, PieceKind::Rook), (&BISHOP, PieceKind::Bishop)] {
+            for &(df, dr) in rays {
+                let Some(first) = self.first_piece(king, df, dr) else {
+                    continue;
+                };
+                if self.piece_at(first).map(|p| p.color) != Some(color) {
+                    continue;
+                }
+                let pinner = self
+                    .first_piece(first, df, dr)
+                    .and_then(|sq| self.piece_at(sq));
+                if pinner.is_some_and(|p| {
+                    p.color != color && (p.kind == kind || p.kind == PieceKind::Queen)
+                }) {
+                    pinned.push(first);
+                }
+            }
+        }
+        pinned
+    }
+
+    fn first_piece(&self, from: Square, df: i8, dr: i8) -> Option<Square> {
+        let mut sq = from;
+        while let Some(next) = sq.offset(df, dr) {
+            sq = next;
+            if self.piece_at(sq).is_some() {
+                return Some(sq);
+            }
+        }
+        None
+    }
+}
+
+pub(super) fn files_between(a: Square, b: Square) -> impl Iterator<Item = u8> {
+    a.file().min(b.file())..=a.file().max(b.file())
+}
@@ -0,0 +1,130 @@
+use super::fen::START_FEN;
+use super::{ChessBoard, Move};
+
+/// The number of move paths `depth` plies deep, the standard check that
+/// move generation follows the rules.
+pub fn perft(board: &ChessBoard, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .into_iter()
+        .map(|mv| {
+            let mut after = board.clone();
+            after.make_move(mv);
+            perft(&after, depth - 1)
+        })
+        .sum()
+}
+
+/// `perft` split by first move, for tracking down where counts differ.
+pub fn divide(board: &ChessBoard, depth: u32) -> Vec<(Move, u64)> {
+    board
+        .legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let mut after = board.clone();
+            after.make_move(mv);
+            (mv, perft(&after, depth.saturating_sub(1)))
+        })
+        .collect()
+}
+
+/// A well-known position and its node counts at depth 1, 2, ...
+pub struct Reference {
+    pub name: &'static str,
+    pub fen: &'static str,
+    pub nodes: &'static [u64],
+}
+
+impl Reference {
+    pub fn board(&self) -> ChessBoard {
+        ChessBoard::from_fen(self.fen).expect("reference positions are valid FEN")
+    }
+}
+
+/// The usual suite, from the Chess Programming Wiki's perft results page.
+pub const REFERENCE_POSITIONS: [Reference; 6] = [
+    Reference {
+        name: "initial position",
+        fen: START_FEN,
+        nodes: &[20, 400, 8_902, 197_281, 4_865_609],
+    },
+    Reference {
+        name: "kiwipete",
+        fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        nodes: &[48, 2_039, 97_862, 4_085_603],
+    },
+    Reference {
+        name: "position 3",
+        fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        nodes: &[14, 191, 2_812, 43_238, 674_624],
+    },
+    Reference {
+        name: "position 4",
+        fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        nodes: &[6, 264, 9_467, 422_333],
+    },
+    Reference {
+        name: "position 5",
+        fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        nodes: &[44, 1_486, 62_379, 2_103_487],
+    },
+    Reference {
+        name: "position 6",
+        fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        nodes: &[46, 2_079, 89_890, 3_894_594],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(max_depth: usize) {
+        for reference in &REFERENCE_POSITIONS {
+            let board = reference.board();
+            for (depth, &expected) in reference.nodes.iter().enumerate().take(max_depth) {
+                let depth = depth as u32 + 1;
+                assert_eq!(
+                    perft(&board, depth),
+                    expected,
+                    "{} at depth {}",
+                    reference.name,
+                    depth
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn twenty_moves_from_the_start() {
+        assert_eq!(ChessBoard::at_start().legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn reference_positions_to_depth_3() {
+        check(3);
+    }
+
+    #[test]
+    #[ignore = "slow without --release"]
+    fn reference_positions_to_full_depth() {
+        check(usize::MAX);
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        for reference in &REFERENCE_POSITIONS {
+            let board = reference.board();
+            let split = divide(&board, 2);
+            assert_eq!(split.len() as u64, reference.nodes[0], "{}", reference.name);
+            let total: u64 = split.iter().map(|&(_, nodes)| nodes).sum();
+            assert_eq!(total, reference.nodes[1], "{}", reference.name);
+        }
+    }
+}
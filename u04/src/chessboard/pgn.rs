@@ -0,0 +1,810 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::fen::FenError;
+use super::rules::Outcome;
+use super::san::SanError;
+use super::{ChessBoard, Color, Move};
+
+/// Tags every exported game carries, in the order PGN wants them first.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Movetext lines are kept to this length on export.
+const LINE_WIDTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PgnError {
+    #[error("line {line}: {message}")]
+    Syntax { line: usize, message: String },
+    #[error("FEN tag: {0}")]
+    Fen(#[from] FenError),
+    #[error("line {line}, ply {ply}: {source}")]
+    Move {
+        line: usize,
+        ply: usize,
+        source: SanError,
+    },
+    #[error("no game found")]
+    Empty,
+}
+
+/// A move as it was played, with whatever the annotator said about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayedMove {
+    pub mv: Move,
+    pub san: String,
+    /// Numeric annotation glyphs: `$1` or `!` is 1, `$2` or `?` is 2, ...
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    /// Alternatives to this move, each played from the position before it.
+    pub variations: Vec<Line>,
+}
+
+/// A sequence of moves, the main line or a variation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Line {
+    /// A comment ahead of the first move.
+    pub comment: Option<String>,
+    pub moves: Vec<PlayedMove>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    tags: Vec<(String, String)>,
+    start: ChessBoard,
+    line: Line,
+    result: String,
+    /// The position after the last move of the main line.
+    board: ChessBoard,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self::from_board(ChessBoard::at_start())
+    }
+
+    /// A game from any position. Unless it's the usual start, the `SetUp`
+    /// and `FEN` tags record it.
+    pub fn from_board(board: ChessBoard) -> Self {
+        let mut game = Self {
+            tags: Vec::new(),
+            start: board.clone(),
+            line: Line::default(),
+            result: "*".to_string(),
+            board,
+        };
+        if game.start != ChessBoard::at_start() {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &game.start.to_fen());
+        }
+        game
+    }
+
+    /// The first game in `pgn`.
+    pub fn from_pgn(pgn: &str) -> Result<Self, PgnError> {
+        Parser::new(pgn).game()?.ok_or(PgnError::Empty)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tags.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// `1-0`, `0-1`, `1/2-1/2` or `*` for a game still going.
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+
+    pub fn set_result(&mut self, result: &str) {
+        self.result = result.to_string();
+    }
+
+    pub fn main_line(&self) -> &Line {
+        &self.line
+    }
+
+    /// Plies played in the main line.
+    pub fn len(&self) -> usize {
+        self.line.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.line.moves.is_empty()
+    }
+
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.line.moves.iter().map(|played| played.mv)
+    }
+
+    pub fn start(&self) -> &ChessBoard {
+        &self.start
+    }
+
+    /// The position after the last move.
+    pub fn board(&self) -> &ChessBoard {
+        &self.board
+    }
+
+    /// The position after `ply` moves of the main line, 0 being the start.
+    pub fn board_at(&self, ply: usize) -> Option<ChessBoard> {
+        self.boards().nth(ply)
+    }
+
+    /// Every position of the main line, from the start to the last move.
+    pub fn boards(&self) -> impl Iterator<Item = ChessBoard> + '_ {
+        let mut board = Some(self.start.clone());
+        let mut moves = self.moves();
+        std::iter::from_fn(move || {
+            let current = board.take()?;
+            if let Some(mv) = moves.next() {
+                let mut next = current.clone();
+                next.make_move(mv);
+                board = Some(next);
+            }
+            Some(current)
+        })
+    }
+
+    /// How many times the position after the last move has come up so far.
+    pub fn repetitions(&self) -> usize {
+        let key = self.board.repetition_key();
+        self.boards()
+            .filter(|board| board.repetition_key() == key)
+            .count()
+    }
+
+    /// Whether the game is over by the rules after the last move.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.board
+            .outcome()
+            .or_else(|| (self.repetitions() >= 3).then_some(Outcome::ThreefoldRepetition))
+    }
+
+    /// Adds `mv` to the end of the main line if it's legal.
+    pub fn play(&mut self, mv: Move) -> Result<(), SanError> {
+        if !self.board.is_legal(mv) {
+            return Err(SanError::Illegal(mv.to_string()));
+        }
+        let san = self.board.san(mv);
+        self.board.make_move(mv);
+        self.line.moves.push(PlayedMove::new(mv, san));
+        Ok(())
+    }
+
+    pub fn play_san(&mut self, san: &str) -> Result<Move, SanError> {
+        let mv = self.board.parse_san(san)?;
+        self.play(mv)?;
+        Ok(mv)
+    }
+
+    /// Takes back the last move of the main line.
+    pub fn pop(&mut self) -> Option<PlayedMove> {
+        let played = self.line.moves.pop()?;
+        self.board = self.board_at(self.len()).expect("replays to the new end");
+        Some(played)
+    }
+
+    /// Export format: the Seven Tag Roster first, then any other tags, then
+    /// movetext wrapped to 80 columns.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => &self.result,
+                _ => self.tag(name).unwrap_or(default),
+            };
+            pgn.push_str(&tag_pair(name, value));
+        }
+        for (name, value) in self.tags() {
+            if !SEVEN_TAG_ROSTER.iter().any(|&(n, _)| n == name) {
+                pgn.push_str(&tag_pair(name, value));
+            }
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        movetext(&self.line, &self.start, &mut tokens);
+        tokens.push(self.result.clone());
+        pgn.push_str(&wrap(&tokens));
+        pgn.push('\n');
+        pgn
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_pgn())
+    }
+}
+
+impl FromStr for Game {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Game::from_pgn(s)
+    }
+}
+
+impl PlayedMove {
+    fn new(mv: Move, san: String) -> Self {
+        Self {
+            mv,
+            san,
+            nags: Vec::new(),
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+/// Every game in `pgn`, as in a `.pgn` file holding several.
+pub fn read_games(pgn: &str) -> Result<Vec<Game>, PgnError> {
+    let mut parser = Parser::new(pgn);
+    let mut games = Vec::new();
+    while let Some(game) = parser.game()? {
+        games.push(game);
+    }
+    Ok(games)
+}
+
+fn tag_pair(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+fn movetext(line: &Line, start: &ChessBoard, tokens: &mut Vec<String>) {
+    if let Some(comment) = &line.comment {
+        push_comment(comment, tokens);
+    }
+    let mut board = start.clone();
+    // black's moves need their number after anything that interrupts
+    let mut numbered = false;
+    for played in &line.moves {
+        // kept in the same token so a number never ends a line alone
+        let number = board.fullmove_number();
+        tokens.push(match board.side_to_move() {
+            Color::White => format!("{}. {}", number, played.san),
+            Color::Black if !numbered => format!("{}... {}", number, played.san),
+            Color::Black => played.san.clone(),
+        });
+        tokens.extend(played.nags.iter().map(|nag| format!("${}", nag)));
+        numbered = true;
+        if let Some(comment) = &played.comment {
+            push_comment(comment, tokens);
+            numbered = false;
+        }
+        for variation in &played.variations {
+            tokens.push("(".to_string());
+            movetext(variation, &board, tokens);
+            tokens.push(")".to_string());
+            numbered = false;
+        }
+        board.make_move(played.mv);
+    }
+}
+
+fn push_comment(comment: &str, tokens: &mut Vec<String>) {
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    match words[..] {
+        [] => tokens.push("{}".to_string()),
+        [word] => tokens.push(format!("{{{}}}", word)),
+        [first, ref middle @ .., last] => {
+            tokens.push(format!("{{{}", first));
+            tokens.extend(middle.iter().map(|w| w.to_string()));
+            tokens.push(format!("{}}}", last));
+        }
+    }
+}
+
+fn wrap(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut width = 0;
+    let mut previous = "";
+    for token in tokens {
+        let joined = previous == "(" || token == ")";
+        let space = usize::from(width > 0 && !joined);
+        if width > 0 && width + space + token.len() > LINE_WIDTH {
+            text.push('\n');
+            width = 0;
+        } else if space > 0 {
+            text.push(' ');
+            width += 1;
+        }
+        text.push_str(token);
+        width += token.len();
+        previous = token;
+    }
+    text
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    MoveNumber,
+    San(String),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    /// A token read too far, as when a tag starts the next game.
+    peeked: Option<(Token, usize)>,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+            peeked: None,
+        }
+    }
+
+    fn syntax<T>(&self, line: usize, message: impl Into<String>) -> Result<T, PgnError> {
+        Err(PgnError::Syntax {
+            line,
+            message: message.into(),
+        })
+    }
+
+    /// The next game, or `None` at the end of the text.
+    fn game(&mut self) -> Result<Option<Game>, PgnError> {
+        let mut tags = Vec::new();
+        loop {
+            match self.next()? {
+                Some((Token::Tag(name, value), _)) => tags.push((name, value)),
+                Some(other) => {
+                    self.peeked = Some(other);
+                    break;
+                }
+                None if tags.is_empty() => return Ok(None),
+                None => break,
+            }
+        }
+
+        let start = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => ChessBoard::from_fen(fen)?,
+            None => ChessBoard::at_start(),
+        };
+        let mut game = Game {
+            tags,
+            start: start.clone(),
+            line: Line::default(),
+            result: "*".to_string(),
+            board: start.clone(),
+        };
+        if let Some(result) = game.tag("Result").filter(|r| RESULTS.contains(r)) {
+            game.result = result.to_string();
+        }
+
+        let (line, end) = self.line(&start, 1, false)?;
+        game.line = line;
+        game.board = end;
+        if let Some((Token::Result(result), _)) = &self.peeked {
+            game.result = result.clone();
+            self.peeked = None;
+        }
+        Ok(Some(game))
+    }
+
+    /// Moves up to the end of the game, or up to the `)` closing a
+    /// variation. Returns the line and the position it ends in.
+    fn line(
+        &mut self,
+        start: &ChessBoard,
+        first_ply: usize,
+        nested: bool,
+    ) -> Result<(Line, ChessBoard), PgnError> {
+        let mut line = Line::default();
+        let mut board = start.clone();
+        let mut before = start.clone();
+        while let Some((token, at)) = self.next()? {
+            match token {
+                Token::San(san) => {
+                    let ply = first_ply + line.moves.len();
+                    let mv = board.parse_san(&san).map_err(|source| PgnError::Move {
+                        line: at,
+                        ply,
+                        source,
+                    })?;
+                    before = board.clone();
+                    let san = board.san(mv);
+                    board.make_move(mv);
+                    line.moves.push(PlayedMove::new(mv, san));
+                }
+                Token::Nag(nag) => match line.moves.last_mut() {
+                    Some(played) => played.nags.push(nag),
+                    None => return self.syntax(at, format!("${} before any move", nag)),
+                },
+                Token::Comment(comment) => {
+                    let slot = match line.moves.last_mut() {
+                        Some(played) => &mut played.comment,
+                        None => &mut line.comment,
+                    };
+                    match slot {
+                        Some(existing) => {
+                            existing.push(' ');
+                            existing.push_str(&comment);
+                        }
+                        None => *slot = Some(comment),
+                    }
+                }
+                Token::Open => {
+                    let ply = first_ply + line.moves.len() - 1;
+                    let Some(played) = line.moves.last_mut() else {
+                        return self.syntax(at, "variation before any move");
+                    };
+                    let (variation, _) = self.line(&before, ply, true)?;
+                    played.variations.push(variation);
+                }
+                Token::Close if nested => return Ok((line, board)),
+                Token::Close => return self.syntax(at, "`)` without a variation to close"),
+                Token::MoveNumber => {}
+                Token::Tag(..) | Token::Result(_) if nested => {
+                    return self.syntax(at, "variation not closed")
+                }
+                Token::Tag(..) | Token::Result(_) => {
+                    self.peeked = Some((token, at));
+                    return Ok((line, board));
+                }
+            }
+        }
+        if nested {
+            return self.syntax(self.line, "variation not closed");
+        }
+        Ok((line, board))
+    }
+
+    fn next(&mut self) -> Result<Option<(Token, usize)>, PgnError> {
+        if let Some(peeked) = self.peeked.take() {
+            return Ok(Some(peeked));
+        }
+        loop {
+            let Some(c) = self.peek() else {
+                return Ok(None);
+            };
+            let at = self.line;
+            let token = match c {
+                '%' if self.at_line_start() => {
+                    self.skip_line();
+                    continue;
+                }
+                ';' => {
+                    self.skip_line();
+                    continue;
+                }
+                c if c.is_whitespace() || c == '.' => {
+                    self.bump();
+                    continue;
+                }
+                '[' => self.tag()?,
+                '{' => {
+                    self.bump();
+                    let text = self.until('}', "comment not closed with `}`")?;
+                    Token::Comment(text.split_whitespace().collect::<Vec<_>>().join(" "))
+                }
+                '(' => {
+                    self.bump();
+                    Token::Open
+                }
+                ')' => {
+                    self.bump();
+                    Token::Close
+                }
+                '$' => {
+                    self.bump();
+                    let digits = self.take_while(|c| c.is_ascii_digit());
+                    match digits.parse() {
+                        Ok(nag) => Token::Nag(nag),
+                        Err(_) => return self.syntax(at, format!("`${}` is not a NAG", digits)),
+                    }
+                }
+                '!' | '?' => {
+                    let suffix = self.take_while(|c| c == '!' || c == '?');
+                    match suffix_nag(&suffix) {
+                        Some(nag) => Token::Nag(nag),
+                        None => {
+                            return self.syntax(at, format!("`{}` is not an annotation", suffix))
+                        }
+                    }
+                }
+                '*' => {
+                    self.bump();
+                    Token::Result("*".to_string())
+                }
+                c if c.is_ascii_alphanumeric() => {
+                    let symbol =
+                        self.take_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(c));
+                    if RESULTS.contains(&symbol.as_str()) {
+                        Token::Result(symbol)
+                    } else if symbol.chars().all(|c| c.is_ascii_digit()) {
+                        Token::MoveNumber
+                    } else {
+                        Token::San(symbol)
+                    }
+                }
+                other => return self.syntax(at, format!("unexpected `{}`", other)),
+            };
+            return Ok(Some((token, at)));
+        }
+    }
+
+    fn tag(&mut self) -> Result<Token, PgnError> {
+        let at = self.line;
+        self.bump();
+        self.take_while(char::is_whitespace);
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        if name.is_empty() {
+            return self.syntax(at, "tag without a name");
+        }
+        self.take_while(char::is_whitespace);
+        if self.bump() != Some('"') {
+            return self.syntax(at, format!("tag `{}` needs a quoted value", name));
+        }
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => break,
+                Some('\\') => match self.bump() {
+                    Some(c) => value.push(c),
+                    None => break,
+                },
+                Some('\n') | None => {
+                    return self.syntax(at, format!("tag `{}` value not closed", name))
+                }
+                Some(c) => value.push(c),
+            }
+        }
+        self.take_while(char::is_whitespace);
+        if self.bump() != Some(']') {
+            return self.syntax(at, format!("tag `{}` not closed with `]`", name));
+        }
+        Ok(Token::Tag(name, value))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.pos == 0 || self.chars[self.pos - 1] == '\n'
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn take_while(&mut self, keep: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.peek().filter(|&c| keep(c)) {
+            taken.push(c);
+            self.bump();
+        }
+        taken
+    }
+
+    fn until(&mut self, end: char, unclosed: &str) -> Result<String, PgnError> {
+        let at = self.line;
+        let text = self.take_while(|c| c != end);
+        if self.bump().is_none() {
+            return self.syntax(at, unclosed);
+        }
+        Ok(text)
+    }
+}
+
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANNOTATED: &str = r#"[Event "Casual game"]
+[White "Anderssen, A."]
+[Black "Kieseritzky, L."]
+[Result "1-0"]
+[Annotator "Nobody \"in particular\""]
+
+{A quiet start.} 1. e4 e5 2. Nf3 $1 {Develops with tempo.}
+(2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 3. Bb5!? a6?! 1-0
+"#;
+
+    fn sans(line: &Line) -> Vec<&str> {
+        line.moves.iter().map(|m| m.san.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_tags_comments_variations_and_nags() {
+        let game = Game::from_pgn(ANNOTATED).unwrap();
+        assert_eq!(game.tag("White"), Some("Anderssen, A."));
+        assert_eq!(game.tag("Annotator"), Some("Nobody \"in particular\""));
+        assert_eq!(game.tag("Site"), None);
+        assert_eq!(game.result(), "1-0");
+
+        let line = game.main_line();
+        assert_eq!(line.comment.as_deref(), Some("A quiet start."));
+        assert_eq!(sans(line), ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert_eq!(line.moves[2].nags, [1]);
+        assert_eq!(
+            line.moves[2].comment.as_deref(),
+            Some("Develops with tempo.")
+        );
+        assert_eq!(line.moves[4].nags, [5]);
+        assert_eq!(line.moves[5].nags, [6]);
+
+        let [gambit] = &line.moves[2].variations[..] else {
+            panic!("expected one variation");
+        };
+        assert_eq!(sans(gambit), ["f4", "exf4", "Nf3"]);
+        let [counter] = &gambit.moves[1].variations[..] else {
+            panic!("expected one nested variation");
+        };
+        assert_eq!(sans(counter), ["d5"]);
+        assert_eq!(game.board(), &game.board_at(6).unwrap());
+    }
+
+    #[test]
+    fn export_then_import_keeps_the_game() {
+        let game = Game::from_pgn(ANNOTATED).unwrap();
+        let exported = game.to_pgn();
+        assert_eq!(
+            exported,
+            "[Event \"Casual game\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+             [White \"Anderssen, A.\"]\n[Black \"Kieseritzky, L.\"]\n[Result \"1-0\"]\n\
+             [Annotator \"Nobody \\\"in particular\\\"\"]\n\n\
+             {A quiet start.} 1. e4 e5 2. Nf3 $1 {Develops with tempo.} (2. f4 exf4 (2... d5)\n\
+             3. Nf3) 2... Nc6 3. Bb5 $5 a6 $6 1-0\n"
+        );
+        assert!(exported.lines().all(|line| line.len() <= LINE_WIDTH));
+
+        let again = Game::from_pgn(&exported).unwrap();
+        assert_eq!(again.main_line(), game.main_line());
+        assert_eq!(again.result(), game.result());
+        assert_eq!(again.tag("Black"), game.tag("Black"));
+        assert_eq!(again.to_pgn(), exported);
+    }
+
+    #[test]
+    fn games_from_a_position_carry_their_fen() {
+        let board = ChessBoard::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 30").unwrap();
+        let mut game = Game::from_board(board.clone());
+        assert_eq!(game.tag("SetUp"), Some("1"));
+        for san in ["Kd7", "e4", "Ke6"] {
+            game.play_san(san).unwrap();
+        }
+        let exported = game.to_pgn();
+        assert!(exported.ends_with("30... Kd7 31. e4 Ke6 *\n"));
+        let again = Game::from_pgn(&exported).unwrap();
+        assert_eq!(again.start(), &board);
+        assert_eq!(again.board(), game.board());
+    }
+
+    #[test]
+    fn bad_moves_give_their_line_and_ply() {
+        let pgn = "[Event \"?\"]\n\n1. e4 e5\n2. Nf3 Nc6\n3. Bb5 Nf6 4. Ke3 *\n";
+        assert_eq!(
+            Game::from_pgn(pgn),
+            Err(PgnError::Move {
+                line: 5,
+                ply: 7,
+                source: SanError::Illegal("Ke3".to_string()),
+            })
+        );
+        assert_eq!(
+            Game::from_pgn("1. d4 e5 2. e5 *").unwrap_err().to_string(),
+            "line 1, ply 3: `e5` is not a legal move here"
+        );
+        // inside a variation, plies count from the move it replaces
+        assert_eq!(
+            Game::from_pgn("1. e4 (1. d4\nKd5) e5 *"),
+            Err(PgnError::Move {
+                line: 2,
+                ply: 2,
+                source: SanError::Illegal("Kd5".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_movetext_is_a_syntax_error() {
+        let syntax = |pgn: &str| match Game::from_pgn(pgn) {
+            Err(PgnError::Syntax { line, message }) => (line, message),
+            other => panic!("expected a syntax error from {pgn:?}, got {other:?}"),
+        };
+        assert_eq!(
+            syntax("1. e4 ) e5"),
+            (1, "`)` without a variation to close".into())
+        );
+        assert_eq!(syntax("1. e4 (1. d4\n"), (2, "variation not closed".into()));
+        assert_eq!(syntax("$1 1. e4"), (1, "$1 before any move".into()));
+        assert_eq!(
+            syntax("1. e4 {never closed"),
+            (1, "comment not closed with `}`".into())
+        );
+        assert_eq!(
+            syntax("[Event \"x\"\n1. e4"),
+            (1, "tag `Event` not closed with `]`".into())
+        );
+        assert_eq!(
+            syntax("1. e4 ?!?"),
+            (1, "`?!?` is not an annotation".into())
+        );
+        assert!(matches!(
+            Game::from_pgn("[FEN \"8/8/8 w - - 0 1\"]\n\n*"),
+            Err(PgnError::Fen(FenError::RankCount(3)))
+        ));
+        assert_eq!(
+            Game::from_pgn("  \n; just a comment\n"),
+            Err(PgnError::Empty)
+        );
+    }
+
+    #[test]
+    fn reads_every_game_in_a_file() {
+        let games =
+            read_games("1. e4 e5 1/2-1/2\n\n[Event \"Second\"]\n\n1. d4 d5 2. c4 0-1\n").unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!((games[0].len(), games[0].result()), (2, "1/2-1/2"));
+        assert_eq!(games[1].tag("Event"), Some("Second"));
+        assert_eq!((games[1].len(), games[1].result()), (3, "0-1"));
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt;
+use std::ops::Not;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    pub const ALL: [Color; 2] = [Color::White, Color::Black];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The rank pieces of this color start on, 0 being rank 1.
+    pub fn back_rank(self) -> u8 {
+        match self {
+            Color::White => 0,
+            Color::Black => 7,
+        }
+    }
+
+    /// Which way this color's pawns move up the board.
+    pub fn forward(self) -> i8 {
+        match self {
+            Color::White => 1,
+            Color::Black => -1,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Color::White => "white",
+            Color::Black => "black",
+        }
+    }
+}
+
+impl Not for Color {
+    type Output = Color;
+
+    fn not(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+impl PieceKind {
+    pub const ALL: [PieceKind; 6] = [
+        PieceKind::Pawn,
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+        PieceKind::King,
+    ];
+
+    pub const PROMOTIONS: [PieceKind; 4] = [
+        PieceKind::Queen,
+        PieceKind::Rook,
+        PieceKind::Bishop,
+        PieceKind::Knight,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The uppercase letter, as used for white in FEN and in SAN.
+    pub fn letter(self) -> char {
+        match self {
+            PieceKind::Pawn => 'P',
+            PieceKind::Knight => 'N',
+            PieceKind::Bishop => 'B',
+            PieceKind::Rook => 'R',
+            PieceKind::Queen => 'Q',
+            PieceKind::King => 'K',
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PieceKind::Pawn => "pawn",
+            PieceKind::Knight => "knight",
+            PieceKind::Bishop => "bishop",
+            PieceKind::Rook => "rook",
+            PieceKind::Queen => "queen",
+            PieceKind::King => "king",
+        }
+    }
+
+    pub fn from_letter(c: char) -> Option<PieceKind> {
+        match c.to_ascii_uppercase() {
+            'P' => Some(PieceKind::Pawn),
+            'N' => Some(PieceKind::Knight),
+            'B' => Some(PieceKind::Bishop),
+            'R' => Some(PieceKind::Rook),
+            'Q' => Some(PieceKind::Queen),
+            'K' => Some(PieceKind::King),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Piece {
+    pub color: Color,
+    pub kind: PieceKind,
+}
+
+impl Piece {
+    pub const fn new(color: Color, kind: PieceKind) -> Self {
+        Self { color, kind }
+    }
+
+    /// Uppercase for white, lowercase for black.
+    pub fn to_char(self) -> char {
+        match self.color {
+            Color::White => self.kind.letter(),
+            Color::Black => self.kind.letter().to_ascii_lowercase(),
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Piece> {
+        let kind = PieceKind::from_letter(c)?;
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some(Piece::new(color, kind))
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+/// One of the 64 squares, a1 being 0 and h8 63.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    /// `file` and `rank` count from 0, so e4 is `(4, 3)`.
+    pub fn new(file: u8, rank: u8) -> Option<Square> {
+        (file < 8 && rank < 8).then_some(Square(rank * 8 + file))
+    }
+
+    pub fn from_index(index: usize) -> Option<Square> {
+        (index < 64).then_some(Square(index as u8))
+    }
+
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    pub fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    pub fn offset(self, files: i8, ranks: i8) -> Option<Square> {
+        let file = self.file() as i8 + files;
+        let rank = self.rank() as i8 + ranks;
+        ((0..8).contains(&file) && (0..8).contains(&rank)).then(|| Square((rank * 8 + file) as u8))
+    }
+
+    pub fn file_char(self) -> char {
+        (b'a' + self.file()) as char
+    }
+
+    pub fn rank_char(self) -> char {
+        (b'1' + self.rank()) as char
+    }
+
+    pub fn is_light(self) -> bool {
+        (self.file() + self.rank()) % 2 == 1
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file_char(), self.rank_char())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("`{0}` is not a square")]
+pub struct BadSquare(pub String);
+
+impl FromStr for Square {
+    type Err = BadSquare;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Ok(Square((rank - b'1') * 8 + file - b'a'))
+            }
+            _ => Err(BadSquare(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastleSide {
+    /// Towards the h-file; O-O.
+    King,
+    /// Towards the a-file; O-O-O.
+    Queen,
+}
+
+impl CastleSide {
+    pub const ALL: [CastleSide; 2] = [CastleSide::King, CastleSide::Queen];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CastleSide::King => "kingside",
+            CastleSide::Queen => "queenside",
+        }
+    }
+
+    /// Where the king ends up, whatever the variant.
+    pub fn king_file(self) -> u8 {
+        match self {
+            CastleSide::King => 6,
+            CastleSide::Queen => 2,
+        }
+    }
+
+    /// Where the rook ends up, whatever the variant.
+    pub fn rook_file(self) -> u8 {
+        match self {
+            CastleSide::King => 5,
+            CastleSide::Queen => 3,
+        }
+    }
+}
+
+/// Which rooks can still castle, by the file they stand on so the same
+/// rules cover Chess960 starting positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CastlingRights([[Option<u8>; 2]; 2]);
+
+impl CastlingRights {
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Both sides can castle with the rooks in the corners.
+    pub fn standard() -> Self {
+        Self([[Some(7), Some(0)]; 2])
+    }
+
+    /// The file of the rook `color` can castle with on `side`.
+    pub fn rook_file(self, color: Color, side: CastleSide) -> Option<u8> {
+        self.0[color.index()][side.index()]
+    }
+
+    pub fn has(self, color: Color, side: CastleSide) -> bool {
+        self.rook_file(color, side).is_some()
+    }
+
+    pub fn set(&mut self, color: Color, side: CastleSide, rook_file: Option<u8>) {
+        self.0[color.index()][side.index()] = rook_file;
+    }
+
+    pub fn clear_color(&mut self, color: Color) {
+        self.0[color.index()] = [None, None];
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0.iter().flatten().all(Option::is_none)
+    }
+}
@@ -0,0 +1,409 @@
+use std::fmt::{self, Write};
+
+use super::{ChessBoard, Color, Move, Piece, PieceKind, Square};
+
+/// Square colours, as on most wooden boards.
+const LIGHT: (u8, u8, u8) = (240, 217, 181);
+const DARK: (u8, u8, u8) = (181, 136, 99);
+const HIGHLIGHT_LIGHT: (u8, u8, u8) = (247, 236, 116);
+const HIGHLIGHT_DARK: (u8, u8, u8) = (218, 195, 50);
+
+/// Side of a square in SVG user units.
+const SVG_SQUARE: u32 = 45;
+/// Room left around an SVG board for the labels.
+const SVG_MARGIN: u32 = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Glyphs {
+    /// FEN letters: `K` for a white king, `k` for a black one.
+    #[default]
+    Ascii,
+    /// Chess symbols: `♔` for a white king, `♚` for a black one.
+    Unicode,
+}
+
+impl Glyphs {
+    pub fn piece(self, piece: Piece) -> char {
+        match self {
+            Glyphs::Ascii => piece.to_char(),
+            Glyphs::Unicode => unicode(piece.color, piece.kind),
+        }
+    }
+}
+
+fn unicode(color: Color, kind: PieceKind) -> char {
+    let white = match kind {
+        PieceKind::King => '♔',
+        PieceKind::Queen => '♕',
+        PieceKind::Rook => '♖',
+        PieceKind::Bishop => '♗',
+        PieceKind::Knight => '♘',
+        PieceKind::Pawn => '♙',
+    };
+    match color {
+        Color::White => white,
+        // the black symbols follow the white ones, six code points on
+        Color::Black => char::from_u32(white as u32 + 6).unwrap(),
+    }
+}
+
+/// How to draw a board. The default matches `ChessBoard`'s `Display`: ASCII
+/// letters in brackets, white at the bottom, nothing else.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    glyphs: Glyphs,
+    labels: bool,
+    flipped: bool,
+    ansi: bool,
+    highlights: Vec<Square>,
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn glyphs(mut self, glyphs: Glyphs) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// Rank numbers down the side and file letters along the bottom.
+    pub fn labels(mut self, labels: bool) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Black at the bottom, as black's player sees the board.
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    /// From `color`'s side of the board.
+    pub fn orientation(self, color: Color) -> Self {
+        self.flipped(color == Color::Black)
+    }
+
+    /// Light and dark squares in terminal colours instead of brackets.
+    pub fn ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+
+    pub fn highlight(mut self, squares: &[Square]) -> Self {
+        self.highlights.extend_from_slice(squares);
+        self
+    }
+
+    /// Highlights where `mv` came from and went to.
+    pub fn last_move(self, mv: Move) -> Self {
+        self.highlight(&[mv.from, mv.to])
+    }
+
+    pub fn is_flipped(&self) -> bool {
+        self.flipped
+    }
+
+    fn ranks(&self) -> Vec<u8> {
+        if self.flipped {
+            (0..8).collect()
+        } else {
+            (0..8).rev().collect()
+        }
+    }
+
+    fn files(&self) -> Vec<u8> {
+        if self.flipped {
+            (0..8).rev().collect()
+        } else {
+            (0..8).collect()
+        }
+    }
+
+    pub fn format_to<W: Write>(&self, w: &mut W, board: &ChessBoard) -> fmt::Result {
+        for rank in self.ranks() {
+            if self.labels {
+                write!(w, "{} ", rank + 1)?;
+            }
+            for file in self.files() {
+                let square = Square::new(file, rank).unwrap();
+                let cell = board.piece_at(square).map_or(' ', |piece| {
+                    // outlined symbols vanish on light squares, so colour
+                    // alone tells the sides apart
+                    if self.ansi && self.glyphs == Glyphs::Unicode {
+                        unicode(Color::Black, piece.kind)
+                    } else {
+                        self.glyphs.piece(piece)
+                    }
+                });
+                let highlighted = self.highlights.contains(&square);
+                if self.ansi {
+                    let (r, g, b) = background(square, highlighted);
+                    let fg = match board.piece_at(square).map(|p| p.color) {
+                        Some(Color::White) | None => "97",
+                        Some(Color::Black) => "30",
+                    };
+                    write!(w, "\x1b[48;2;{};{};{};1;{}m {} ", r, g, b, fg, cell)?;
+                } else if highlighted {
+                    write!(w, "({})", cell)?;
+                } else {
+                    write!(w, "[{}]", cell)?;
+                }
+            }
+            if self.ansi {
+                w.write_str("\x1b[0m")?;
+            }
+            writeln!(w)?;
+        }
+        if self.labels {
+            w.write_str("  ")?;
+            for file in self.files() {
+                write!(w, " {} ", (b'a' + file) as char)?;
+            }
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+
+    /// A standalone SVG image, for documentation. Pieces are drawn with the
+    /// Unicode chess symbols whatever `glyphs` says.
+    pub fn svg(&self, board: &ChessBoard) -> String {
+        let margin = if self.labels { SVG_MARGIN } else { 0 };
+        let size = 8 * SVG_SQUARE + 2 * margin;
+        let mut svg = String::new();
+        // writing to a String can't fail
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+            size
+        );
+        for (row, rank) in self.ranks().into_iter().enumerate() {
+            for (column, file) in self.files().into_iter().enumerate() {
+                let square = Square::new(file, rank).unwrap();
+                let x = margin + column as u32 * SVG_SQUARE;
+                let y = margin + row as u32 * SVG_SQUARE;
+                let (r, g, b) = background(square, self.highlights.contains(&square));
+                let _ = writeln!(
+                    svg,
+                    r##"  <rect x="{x}" y="{y}" width="{side}" height="{side}" fill="#{r:02x}{g:02x}{b:02x}"/>"##,
+                    side = SVG_SQUARE,
+                );
+                if let Some(piece) = board.piece_at(square) {
+                    // the solid black symbols, filled white for white
+                    let (fill, stroke) = match piece.color {
+                        Color::White => ("#fff", "#000"),
+                        Color::Black => ("#000", "#000"),
+                    };
+                    let _ = writeln!(
+                        svg,
+                        r#"  <text x="{}" y="{}" font-size="{}" text-anchor="middle" dominant-baseline="central" fill="{}" stroke="{}" stroke-width="1">{}</text>"#,
+                        x + SVG_SQUARE / 2,
+                        y + SVG_SQUARE / 2,
+                        SVG_SQUARE * 4 / 5,
+                        fill,
+                        stroke,
+                        unicode(Color::Black, piece.kind)
+                    );
+                }
+            }
+        }
+        if self.labels {
+            let label = |svg: &mut String, x: u32, y: u32, text: char| {
+                let _ = writeln!(
+                    svg,
+                    r#"  <text x="{}" y="{}" font-size="14" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                    x, y, text
+                );
+            };
+            for (i, rank) in self.ranks().into_iter().enumerate() {
+                let y = margin + i as u32 * SVG_SQUARE + SVG_SQUARE / 2;
+                label(&mut svg, margin / 2, y, (b'1' + rank) as char);
+            }
+            for (i, file) in self.files().into_iter().enumerate() {
+                let x = margin + i as u32 * SVG_SQUARE + SVG_SQUARE / 2;
+                label(&mut svg, x, size - margin / 2, (b'a' + file) as char);
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn background(square: Square, highlighted: bool) -> (u8, u8, u8) {
+    match (square.is_light(), highlighted) {
+        (true, false) => LIGHT,
+        (false, false) => DARK,
+        (true, true) => HIGHLIGHT_LIGHT,
+        (false, true) => HIGHLIGHT_DARK,
+    }
+}
+
+/// A board drawn with some `RenderOptions`, from `ChessBoard::render`.
+pub struct Rendered<'a> {
+    board: &'a ChessBoard,
+    options: &'a RenderOptions,
+}
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.options.format_to(f, self.board)
+    }
+}
+
+impl ChessBoard {
+    pub fn render<'a>(&'a self, options: &'a RenderOptions) -> Rendered<'a> {
+        Rendered {
+            board: self,
+            options,
+        }
+    }
+
+    pub fn to_svg(&self, options: &RenderOptions) -> String {
+        options.svg(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn after(moves: &[&str]) -> (ChessBoard, Move) {
+        let mut board = ChessBoard::at_start();
+        let mut last = None;
+        for &san in moves {
+            let mv = board
+                .legal_moves()
+                .into_iter()
+                .find(|&mv| board.san(mv) == san)
+                .unwrap();
+            board.make_move(mv);
+            last = Some(mv);
+        }
+        (board, last.unwrap())
+    }
+
+    /// Checks every tag is closed in order, and counts them by name.
+    fn tag_counts(svg: &str) -> Vec<(String, usize)> {
+        let mut open: Vec<String> = Vec::new();
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        let mut rest = svg;
+        while let Some(start) = rest.find('<') {
+            let end = rest[start..].find('>').expect("unterminated tag") + start;
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop().as_deref(), Some(name), "stray </{}>", name);
+                continue;
+            }
+            let name = tag.split([' ', '/']).next().unwrap().to_string();
+            match counts.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((name.clone(), 1)),
+            }
+            if !tag.ends_with('/') {
+                open.push(name);
+            }
+        }
+        assert!(open.is_empty(), "unclosed {:?}", open);
+        assert!(!rest.contains('>'), "stray `>`");
+        counts
+    }
+
+    fn count(counts: &[(String, usize)], name: &str) -> usize {
+        counts
+            .iter()
+            .find(|(n, _)| n == name)
+            .map_or(0, |&(_, c)| c)
+    }
+
+    #[test]
+    fn default_render_matches_display() {
+        let options = RenderOptions::new();
+        for board in [ChessBoard::at_start(), after(&["e4", "c5", "Nf3"]).0] {
+            assert_eq!(board.render(&options).to_string(), board.to_string());
+        }
+    }
+
+    #[test]
+    fn flipped_puts_h1_top_left() {
+        let board = ChessBoard::at_start();
+        let text = board
+            .render(&RenderOptions::new().orientation(Color::Black))
+            .to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], "[R][N][B][K][Q][B][N][R]");
+        assert_eq!(lines[1], "[P][P][P][P][P][P][P][P]");
+        assert_eq!(lines[7], "[r][n][b][k][q][b][n][r]");
+
+        let labelled = board
+            .render(&RenderOptions::new().flipped(true).labels(true))
+            .to_string();
+        assert!(labelled.starts_with("1 [R]"));
+        assert_eq!(labelled.lines().last(), Some("   h  g  f  e  d  c  b  a "));
+    }
+
+    #[test]
+    fn labels_line_up_with_cells() {
+        let board = ChessBoard::at_start();
+        for flipped in [false, true] {
+            let options = RenderOptions::new().labels(true).flipped(flipped);
+            let text = board.render(&options).to_string();
+            let lines: Vec<Vec<char>> = text.lines().map(|l| l.chars().collect()).collect();
+            let (footer, ranks) = lines.split_last().unwrap();
+            assert_eq!(ranks.len(), 8);
+            for (row, line) in ranks.iter().enumerate() {
+                assert_eq!(line.len(), footer.len());
+                let rank = if flipped { row + 1 } else { 8 - row };
+                assert_eq!(line[0], char::from(b'0' + rank as u8));
+                for column in 0..8 {
+                    // each cell is three wide, its piece in the middle
+                    let middle = 2 + 3 * column + 1;
+                    assert_eq!((line[middle - 1], line[middle + 1]), ('[', ']'));
+                    let file = if flipped { 7 - column } else { column };
+                    assert_eq!(footer[middle], char::from(b'a' + file as u8));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn last_move_marks_two_squares() {
+        let (board, mv) = after(&["e4", "e5", "Nf3"]);
+        let text = board
+            .render(&RenderOptions::new().last_move(mv))
+            .to_string();
+        assert_eq!(text.matches('(').count(), 2);
+        assert_eq!(text.matches(')').count(), 2);
+        let lines: Vec<&str> = text.lines().collect();
+        // g1 is on the bottom line, f3 three lines up
+        assert_eq!(&lines[7][18..21], "( )");
+        assert_eq!(&lines[5][15..18], "(N)");
+    }
+
+    #[test]
+    fn svg_is_well_formed() {
+        let (board, mv) = after(&["d4", "d5"]);
+        for labels in [false, true] {
+            let options = RenderOptions::new().labels(labels).last_move(mv);
+            let svg = board.to_svg(&options);
+            let size = 8 * SVG_SQUARE + if labels { 2 * SVG_MARGIN } else { 0 };
+            assert!(svg.starts_with(&format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}""#,
+                size
+            )));
+            assert!(svg.ends_with("</svg>\n"));
+            let counts = tag_counts(&svg);
+            assert_eq!(count(&counts, "svg"), 1);
+            assert_eq!(count(&counts, "rect"), 64);
+            let labels = if labels { 16 } else { 0 };
+            assert_eq!(count(&counts, "text"), 32 + labels);
+            let fill =
+                |(r, g, b): (u8, u8, u8)| format!(r##"fill="#{:02x}{:02x}{:02x}""##, r, g, b);
+            let highlights = svg.matches(&fill(HIGHLIGHT_LIGHT)).count()
+                + svg.matches(&fill(HIGHLIGHT_DARK)).count();
+            assert_eq!(highlights, 2);
+        }
+    }
+}
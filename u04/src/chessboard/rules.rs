@@ -0,0 +1,544 @@
+use std::fmt;
+
+use super::movegen::files_between;
+use super::san::SanError;
+use super::{CastleSide, ChessBoard, Color, Move, MoveKind, PieceKind, Square};
+
+/// How a game ends by the rules, without anyone resigning or agreeing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Checkmate { winner: Color },
+    Stalemate,
+    InsufficientMaterial,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+}
+
+impl Outcome {
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate { winner } => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// The PGN result: `1-0`, `0-1` or `1/2-1/2`.
+    pub fn result(self) -> &'static str {
+        match self.winner() {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Checkmate { winner } => write!(f, "checkmate, {} wins", winner),
+            Outcome::Stalemate => f.write_str("stalemate, a draw"),
+            Outcome::InsufficientMaterial => {
+                f.write_str("neither side can mate with what's left, a draw")
+            }
+            Outcome::FiftyMoveRule => {
+                f.write_str("fifty moves without a capture or pawn move, a draw")
+            }
+            Outcome::ThreefoldRepetition => f.write_str("the same position three times, a draw"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MoveError {
+    #[error(transparent)]
+    San(#[from] SanError),
+    #[error("there's no piece on {0}")]
+    NoPiece(Square),
+    #[error("the {} on {square} is {color}'s", kind.name())]
+    NotYourPiece {
+        square: Square,
+        color: Color,
+        kind: PieceKind,
+    },
+    #[error("a {} can't move from {from} to {to}", kind.name())]
+    Unreachable {
+        kind: PieceKind,
+        from: Square,
+        to: Square,
+    },
+    #[error("{square} is taken by {color}'s own {}", kind.name())]
+    Occupied {
+        square: Square,
+        color: Color,
+        kind: PieceKind,
+    },
+    #[error("`{0}` would leave the king in check")]
+    ExposesKing(String),
+    #[error("`{0}` needs a piece to promote to, as in `{0}q`")]
+    MissingPromotion(String),
+    #[error("`{0}` doesn't reach the last rank, so there's nothing to promote")]
+    NotAPromotion(String),
+    #[error("can't castle: {0}")]
+    Castling(String),
+}
+
+impl ChessBoard {
+    pub fn is_checkmate(&self) -> bool {
+        self.in_check() && self.legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.in_check() && self.legal_moves().is_empty()
+    }
+
+    /// Nothing but kings, one knight or bishop besides, or bishops that
+    /// all stand on the same colour: no series of moves can mate.
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut minors = self
+            .pieces()
+            .filter(|(_, p)| p.kind != PieceKind::King)
+            .peekable();
+        let Some(&(first, piece)) = minors.peek() else {
+            return true;
+        };
+        let others: Vec<_> = minors.collect();
+        match piece.kind {
+            PieceKind::Knight => others.len() == 1,
+            PieceKind::Bishop => others
+                .iter()
+                .all(|(sq, p)| p.kind == PieceKind::Bishop && sq.is_light() == first.is_light()),
+            _ => false,
+        }
+    }
+
+    /// Whether the game is over by this position alone. Repetition needs
+    /// the game's history, so `Game::outcome` covers that as well.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if self.legal_moves().is_empty() {
+            return Some(if self.in_check() {
+                Outcome::Checkmate {
+                    winner: !self.side_to_move,
+                }
+            } else {
+                Outcome::Stalemate
+            });
+        }
+        if self.has_insufficient_material() {
+            Some(Outcome::InsufficientMaterial)
+        } else if self.halfmove_clock >= 100 {
+            Some(Outcome::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Positions with equal keys are the same for repetition: the same
+    /// pieces, side to move and castling rights, and en passant only
+    /// when a capture is really possible.
+    pub fn repetition_key(&self) -> ChessBoard {
+        let mut key = self.clone();
+        key.set_clocks(0, 1);
+        if !self
+            .legal_moves()
+            .iter()
+            .any(|m| m.kind == MoveKind::EnPassant)
+        {
+            key.en_passant = None;
+        }
+        key
+    }
+
+    /// A move typed by a player, in SAN (`Nf3`, `exd5`, `O-O`) or
+    /// coordinates (`g1f3`, `e7e8q`, `e1g1`). When the move can't be
+    /// played, the error says why.
+    pub fn parse_move(&self, text: &str) -> Result<Move, MoveError> {
+        let text = text.trim();
+        if let Some((from, to, promotion)) = parse_coordinates(text) {
+            return self.coordinate_move(text, from, to, promotion);
+        }
+        self.parse_san(text).map_err(|e| {
+            let side = match text.trim_end_matches(['+', '#', '!', '?']) {
+                "O-O" | "0-0" => CastleSide::King,
+                "O-O-O" | "0-0-0" => CastleSide::Queen,
+                _ => return e.into(),
+            };
+            match e {
+                SanError::Illegal(_) => MoveError::Castling(self.castle_problem(side)),
+                other => other.into(),
+            }
+        })
+    }
+
+    fn coordinate_move(
+        &self,
+        text: &str,
+        from: Square,
+        to: Square,
+        promotion: Option<PieceKind>,
+    ) -> Result<Move, MoveError> {
+        let us = self.side_to_move;
+        let piece = self.piece_at(from).ok_or(MoveError::NoPiece(from))?;
+        if piece.color != us {
+            return Err(MoveError::NotYourPiece {
+                square: from,
+                color: piece.color,
+                kind: piece.kind,
+            });
+        }
+
+        let legal = self.legal_moves();
+        let candidates: Vec<Move> = legal
+            .iter()
+            .copied()
+            .filter(|m| m.from == from && m.to == to && !matches!(m.kind, MoveKind::Castle(_)))
+            .collect();
+        if let Some(first) = candidates.first() {
+            return match (first.promotion, promotion) {
+                (None, None) => Ok(*first),
+                (None, Some(_)) => Err(MoveError::NotAPromotion(text.to_string())),
+                (Some(_), None) => Err(MoveError::MissingPromotion(text.to_string())),
+                (Some(_), wanted) => Ok(*candidates
+                    .iter()
+                    .find(|m| m.promotion == wanted)
+                    .expect("every promotion piece is generated")),
+            };
+        }
+
+        // castling is the king's move, to its destination or onto its
+        // own rook
+        if piece.kind == PieceKind::King
+            && from.rank() == us.back_rank()
+            && to.rank() == from.rank()
+        {
+            let side = if to.file() > from.file() {
+                CastleSide::King
+            } else {
+                CastleSide::Queen
+            };
+            let onto_rook = self.castling_rook(us, side) == Some(to);
+            if let Some(castle) = legal
+                .iter()
+                .find(|m| m.kind == MoveKind::Castle(side) && (m.to == to || onto_rook))
+            {
+                return Ok(*castle);
+            }
+            if onto_rook || from.file().abs_diff(to.file()) == 2 {
+                return Err(MoveError::Castling(self.castle_problem(side)));
+            }
+        }
+
+        if let Some(own) = self.piece_at(to).filter(|p| p.color == us) {
+            return Err(MoveError::Occupied {
+                square: to,
+                color: us,
+                kind: own.kind,
+            });
+        }
+        if self
+            .pseudo_legal_moves()
+            .iter()
+            .any(|m| m.from == from && m.to == to)
+        {
+            return Err(MoveError::ExposesKing(text.to_string()));
+        }
+        Err(MoveError::Unreachable {
+            kind: piece.kind,
+            from,
+            to,
+        })
+    }
+
+    /// Why the side to move can't castle on `side` right now.
+    fn castle_problem(&self, side: CastleSide) -> String {
+        let us = self.side_to_move;
+        let name = side.name();
+        let (Some(king), Some(rook)) = (self.king_square(us), self.castling_rook(us, side)) else {
+            return format!("{} no longer has the right to castle {}", us, name);
+        };
+        if self.in_check() {
+            return "the king is in check".to_string();
+        }
+        let rank = us.back_rank();
+        let king_to = Square::new(side.king_file(), rank).unwrap();
+        let rook_to = Square::new(side.rook_file(), rank).unwrap();
+        let blocked = files_between(king, king_to)
+            .chain(files_between(rook, rook_to))
+            .map(|file| Square::new(file, rank).unwrap())
+            .any(|sq| sq != king && sq != rook && self.piece_at(sq).is_some());
+        if blocked {
+            format!("there are pieces between the king and rook {}", name)
+        } else {
+            "the king would cross or land on an attacked square".to_string()
+        }
+    }
+}
+
+/// `e2e4`, `e2-e4` or `e7e8q`.
+fn parse_coordinates(text: &str) -> Option<(Square, Square, Option<PieceKind>)> {
+    let text = text.replacen('-', "", 1);
+    if !(4..=5).contains(&text.len()) || !text.is_ascii() {
+        return None;
+    }
+    let from = text[0..2].parse().ok()?;
+    let to = text[2..4].parse().ok()?;
+    let promotion = match text[4..].chars().next() {
+        None => None,
+        Some(c) => Some(
+            PieceKind::from_letter(c).filter(|&k| k != PieceKind::Pawn && k != PieceKind::King)?,
+        ),
+    };
+    Some((from, to, promotion))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::pgn::Game;
+    use super::*;
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::from_fen(fen).unwrap()
+    }
+
+    fn sq(name: &str) -> Square {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn checkmate_names_the_winner() {
+        let fools = board("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert!(fools.is_checkmate());
+        assert!(!fools.is_stalemate());
+        let outcome = fools.outcome().unwrap();
+        assert_eq!(
+            outcome,
+            Outcome::Checkmate {
+                winner: Color::Black
+            }
+        );
+        assert_eq!(outcome.result(), "0-1");
+        assert_eq!(outcome.to_string(), "checkmate, black wins");
+
+        // mate ends the game even when the fifty moves are up as well
+        let late = board("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80");
+        let mut after = late.clone();
+        after.make_move(late.parse_move("Ra8").unwrap());
+        assert_eq!(
+            after.outcome(),
+            Some(Outcome::Checkmate {
+                winner: Color::White
+            })
+        );
+    }
+
+    #[test]
+    fn stalemate_is_a_draw() {
+        let board = board("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert!(board.is_stalemate());
+        assert!(!board.is_checkmate());
+        assert_eq!(board.outcome(), Some(Outcome::Stalemate));
+        assert_eq!(Outcome::Stalemate.result(), "1/2-1/2");
+        assert_eq!(Outcome::Stalemate.winner(), None);
+    }
+
+    #[test]
+    fn fifty_moves_without_progress_is_a_draw() {
+        let rooks = "4k3/r7/8/8/8/8/R7/4K3 w - - {} 80";
+        let at = |halfmoves: u32| board(&rooks.replace("{}", &halfmoves.to_string())).outcome();
+        assert_eq!(at(99), None);
+        assert_eq!(at(100), Some(Outcome::FiftyMoveRule));
+        assert_eq!(at(150), Some(Outcome::FiftyMoveRule));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let drawn = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+            // both bishops on dark squares
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/B3K3 w - - 0 1",
+        ];
+        for fen in drawn {
+            assert!(board(fen).has_insufficient_material(), "{fen}");
+            assert_eq!(board(fen).outcome(), Some(Outcome::InsufficientMaterial));
+        }
+        let playable = [
+            // bishops on opposite colours
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+        ];
+        for fen in playable {
+            assert!(!board(fen).has_insufficient_material(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn repetition_ignores_clocks_and_dead_en_passant() {
+        let mut game = Game::new();
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"] {
+            game.play_san(san).unwrap();
+            assert_eq!(game.outcome(), None);
+        }
+        game.play_san("Ng8").unwrap();
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.outcome(), Some(Outcome::ThreefoldRepetition));
+        assert_ne!(game.board(), &ChessBoard::at_start());
+        assert_eq!(
+            game.board().repetition_key(),
+            ChessBoard::at_start().repetition_key()
+        );
+
+        // after 1. e4 no black pawn can take en passant, so e3 doesn't count
+        let after_e4 = board("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        let without = board("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+        assert_eq!(after_e4.repetition_key(), without.repetition_key());
+        let live = board("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3");
+        let dead = board("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3");
+        assert_ne!(live.repetition_key(), dead.repetition_key());
+    }
+
+    #[test]
+    fn moves_parse_as_san_or_coordinates() {
+        let start = ChessBoard::at_start();
+        let e4 = start.parse_move("e4").unwrap();
+        assert_eq!(start.parse_move("e2e4"), Ok(e4));
+        assert_eq!(start.parse_move(" e2-e4 "), Ok(e4));
+        assert_eq!(start.parse_move("Nf3"), start.parse_move("g1f3"));
+    }
+
+    #[test]
+    fn san_errors_pass_through() {
+        assert_eq!(
+            ChessBoard::at_start().parse_move("Nf4"),
+            Err(MoveError::San(SanError::Illegal("Nf4".to_string())))
+        );
+    }
+
+    #[test]
+    fn no_piece_to_move() {
+        let err = ChessBoard::at_start().parse_move("e3e4").unwrap_err();
+        assert_eq!(err, MoveError::NoPiece(sq("e3")));
+        assert_eq!(err.to_string(), "there's no piece on e3");
+    }
+
+    #[test]
+    fn not_your_piece() {
+        let err = ChessBoard::at_start().parse_move("e7e5").unwrap_err();
+        assert_eq!(
+            err,
+            MoveError::NotYourPiece {
+                square: sq("e7"),
+                color: Color::Black,
+                kind: PieceKind::Pawn,
+            }
+        );
+        assert_eq!(err.to_string(), "the pawn on e7 is black's");
+    }
+
+    #[test]
+    fn unreachable_squares() {
+        let err = ChessBoard::at_start().parse_move("e2e5").unwrap_err();
+        assert_eq!(
+            err,
+            MoveError::Unreachable {
+                kind: PieceKind::Pawn,
+                from: sq("e2"),
+                to: sq("e5"),
+            }
+        );
+        assert_eq!(err.to_string(), "a pawn can't move from e2 to e5");
+    }
+
+    #[test]
+    fn occupied_by_own_piece() {
+        let err = ChessBoard::at_start().parse_move("d1d2").unwrap_err();
+        assert_eq!(
+            err,
+            MoveError::Occupied {
+                square: sq("d2"),
+                color: Color::White,
+                kind: PieceKind::Pawn,
+            }
+        );
+        assert_eq!(err.to_string(), "d2 is taken by white's own pawn");
+    }
+
+    #[test]
+    fn pinned_pieces_expose_the_king() {
+        let pinned = board("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1");
+        assert_eq!(
+            pinned.parse_move("e2c3"),
+            Err(MoveError::ExposesKing("e2c3".to_string()))
+        );
+        assert_eq!(
+            pinned.parse_move("Nc3"),
+            Err(MoveError::San(SanError::ExposesKing("Nc3".to_string())))
+        );
+    }
+
+    #[test]
+    fn promotions_must_be_named_and_real() {
+        let board = board("8/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+        assert_eq!(
+            board.parse_move("e7e8"),
+            Err(MoveError::MissingPromotion("e7e8".to_string()))
+        );
+        assert_eq!(
+            board.parse_move("e7e8").unwrap_err().to_string(),
+            "`e7e8` needs a piece to promote to, as in `e7e8q`"
+        );
+        assert_eq!(
+            board.parse_move("e7e8n").map(|m| m.promotion),
+            Ok(Some(PieceKind::Knight))
+        );
+        assert_eq!(
+            board.parse_move("e1e2q"),
+            Err(MoveError::NotAPromotion("e1e2q".to_string()))
+        );
+    }
+
+    #[test]
+    fn castling_problems_are_explained() {
+        let castling = |fen: &str, mv: &str| match board(fen).parse_move(mv) {
+            Err(MoveError::Castling(why)) => why,
+            other => panic!("expected a castling error for {mv} in {fen}, got {other:?}"),
+        };
+        assert_eq!(
+            castling("r3k2r/8/8/8/8/8/8/R2QK2R w KQkq - 0 1", "O-O-O"),
+            "there are pieces between the king and rook queenside"
+        );
+        assert_eq!(
+            castling("r3k2r/8/8/8/8/8/8/R2QK2R w KQkq - 0 1", "e1c1"),
+            "there are pieces between the king and rook queenside"
+        );
+        assert_eq!(
+            castling("4k3/8/8/8/8/8/8/R3K2R w K - 0 1", "O-O-O"),
+            "white no longer has the right to castle queenside"
+        );
+        assert_eq!(
+            castling("4k3/4r3/8/8/8/8/8/R3K2R w KQ - 0 1", "0-0"),
+            "the king is in check"
+        );
+        assert_eq!(
+            castling("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1g1"),
+            "the king would cross or land on an attacked square"
+        );
+        assert_eq!(
+            board("4k3/8/8/8/8/8/8/R3K2R w K - 0 1")
+                .parse_move("O-O-O")
+                .unwrap_err()
+                .to_string(),
+            "can't castle: white no longer has the right to castle queenside"
+        );
+        let open = board("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        assert_eq!(
+            open.parse_move("e1h1").map(|m| m.kind),
+            Ok(MoveKind::Castle(CastleSide::King))
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use super::{CastleSide, ChessBoard, Move, MoveKind, PieceKind, Square};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SanError {
+    #[error("`{0}` is not a move in algebraic notation")]
+    Syntax(String),
+    #[error("`{0}` is not a legal move here")]
+    Illegal(String),
+    #[error("`{san}` could be any of {}", candidates.join(", "))]
+    Ambiguous {
+        san: String,
+        candidates: Vec<String>,
+    },
+    #[error("`{0}` needs a piece to promote to")]
+    MissingPromotion(String),
+    #[error("`{0}` would leave the king in check")]
+    ExposesKing(String),
+}
+
+/// What a SAN string asks for, before it's matched against the moves
+/// available.
+#[derive(Debug)]
+struct Parsed {
+    piece: PieceKind,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+    capture: bool,
+    to: Square,
+    promotion: Option<PieceKind>,
+}
+
+impl ChessBoard {
+    /// Standard Algebraic Notation for `mv`, one of `legal_moves`: `Nbd2`,
+    /// `exd5`, `e8=Q+`, `O-O-O#`.
+    pub fn san(&self, mv: Move) -> String {
+        let mut san = match mv.kind {
+            MoveKind::Castle(CastleSide::King) => "O-O".to_string(),
+            MoveKind::Castle(CastleSide::Queen) => "O-O-O".to_string(),
+            _ => self.san_body(mv),
+        };
+        let mut after = self.clone();
+        after.make_move(mv);
+        if after.in_check() {
+            san.push(if after.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    fn san_body(&self, mv: Move) -> String {
+        let mut san = String::new();
+        if mv.piece == PieceKind::Pawn {
+            if mv.is_capture() {
+                san.push(mv.from.file_char());
+            }
+        } else {
+            san.push(mv.piece.letter());
+            let rivals: Vec<Move> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|m| m.piece == mv.piece && m.to == mv.to && m.from != mv.from)
+                .filter(|m| !matches!(m.kind, MoveKind::Castle(_)))
+                .collect();
+            if !rivals.is_empty() {
+                if rivals.iter().all(|m| m.from.file() != mv.from.file()) {
+                    san.push(mv.from.file_char());
+                } else if rivals.iter().all(|m| m.from.rank() != mv.from.rank()) {
+                    san.push(mv.from.rank_char());
+                } else {
+                    san.push_str(&mv.from.to_string());
+                }
+            }
+        }
+        if mv.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+        if let Some(kind) = mv.promotion {
+            san.push('=');
+            san.push(kind.letter());
+        }
+        san
+    }
+
+    /// The legal move `san` describes. Check and mate marks and trailing
+    /// `!`/`?` annotations are ignored, `0-0` is taken for `O-O`, and a
+    /// promotion may leave out the `=`. Pawn captures need their file and
+    /// `x`, as in `dxe5`, so `e5` is only ever a pawn push.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let text = san.trim_end_matches(['+', '#', '!', '?']);
+        let moves = self.legal_moves();
+
+        let castle = match text {
+            "O-O" | "0-0" => Some(CastleSide::King),
+            "O-O-O" | "0-0-0" => Some(CastleSide::Queen),
+            _ => None,
+        };
+        if let Some(side) = castle {
+            return moves
+                .into_iter()
+                .find(|m| m.kind == MoveKind::Castle(side))
+                .ok_or_else(|| SanError::Illegal(san.to_string()));
+        }
+
+        let parsed = parse(text).ok_or_else(|| SanError::Syntax(san.to_string()))?;
+        let matches = |m: &Move| {
+            m.piece == parsed.piece
+                && m.to == parsed.to
+                && !matches!(m.kind, MoveKind::Castle(_))
+                && parsed.from_file.is_none_or(|f| m.from.file() == f)
+                && parsed.from_rank.is_none_or(|r| m.from.rank() == r)
+                && (parsed.piece != PieceKind::Pawn || m.is_capture() == parsed.capture)
+                && (parsed.promotion.is_none() || m.promotion == parsed.promotion)
+        };
+        let candidates: Vec<Move> = moves.into_iter().filter(matches).collect();
+        match candidates[..] {
+            [] if self.pseudo_legal_moves().iter().any(matches) => {
+                Err(SanError::ExposesKing(san.to_string()))
+            }
+            [] => Err(SanError::Illegal(san.to_string())),
+            [mv] if mv.promotion.is_some() && parsed.promotion.is_none() => {
+                Err(SanError::MissingPromotion(san.to_string()))
+            }
+            [mv] => Ok(mv),
+            _ if candidates.iter().all(|m| m.from == candidates[0].from) => {
+                Err(SanError::MissingPromotion(san.to_string()))
+            }
+            _ => Err(SanError::Ambiguous {
+                san: san.to_string(),
+                candidates: candidates.iter().map(|&m| self.san(m)).collect(),
+            }),
+        }
+    }
+}
+
+fn parse(text: &str) -> Option<Parsed> {
+    let mut chars: Vec<char> = text.chars().collect();
+
+    let mut promotion = None;
+    if let Some(&last) = chars.last() {
+        if let Some(kind) = PieceKind::from_letter(last).filter(|_| last.is_ascii_uppercase()) {
+            if kind == PieceKind::Pawn || kind == PieceKind::King {
+                return None;
+            }
+            promotion = Some(kind);
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+    }
+
+    let piece = match chars.first() {
+        Some(&c) if c.is_ascii_uppercase() => {
+            chars.remove(0);
+            PieceKind::from_letter(c).filter(|&k| k != PieceKind::Pawn)?
+        }
+        _ => PieceKind::Pawn,
+    };
+    if promotion.is_some() && piece != PieceKind::Pawn {
+        return None;
+    }
+
+    if chars.len() < 2 {
+        return None;
+    }
+    let to: Square = chars[chars.len() - 2..]
+        .iter()
+        .collect::<String>()
+        .parse()
+        .ok()?;
+    chars.truncate(chars.len() - 2);
+    let capture = chars.last() == Some(&'x');
+    if capture {
+        chars.pop();
+    }
+
+    let (mut from_file, mut from_rank) = (None, None);
+    for c in chars {
+        match c {
+            'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                from_file = Some(c as u8 - b'a')
+            }
+            '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+            _ => return None,
+        }
+    }
+    // a pawn names its file when, and only when, it captures
+    if piece == PieceKind::Pawn && (from_rank.is_some() || from_file.is_some() != capture) {
+        return None;
+    }
+    Some(Parsed {
+        piece,
+        from_file,
+        from_rank,
+        capture,
+        to,
+        promotion,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::from_fen(fen).unwrap()
+    }
+
+    fn sq(name: &str) -> Square {
+        name.parse().unwrap()
+    }
+
+    /// The SAN of the move from `from` to `to`, checking it reads back.
+    fn san_of(board: &ChessBoard, from: &str, to: &str) -> String {
+        let mv = board
+            .legal_moves()
+            .into_iter()
+            .find(|m| m.from == sq(from) && m.to == sq(to))
+            .unwrap_or_else(|| panic!("no move {from}{to}"));
+        let san = board.san(mv);
+        assert_eq!(board.parse_san(&san), Ok(mv), "{san}");
+        san
+    }
+
+    #[test]
+    fn every_legal_move_reads_back_from_its_san() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            let board = board(fen);
+            for mv in board.legal_moves() {
+                let san = board.san(mv);
+                assert_eq!(board.parse_san(&san), Ok(mv), "{san} in {fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn rivals_are_told_apart_by_file_then_rank_then_square() {
+        let knights = board("rnbqkbnr/pppppppp/8/8/8/5N2/PPP1PPPP/RNBQKB1R w KQkq - 0 1");
+        assert_eq!(san_of(&knights, "b1", "d2"), "Nbd2");
+        assert_eq!(san_of(&knights, "f3", "d2"), "Nfd2");
+        assert_eq!(
+            knights.parse_san("Nd2"),
+            Err(SanError::Ambiguous {
+                san: "Nd2".to_string(),
+                candidates: vec!["Nbd2".to_string(), "Nfd2".to_string()],
+            })
+        );
+
+        let rooks = board("k7/8/8/8/8/4R3/8/4R1K1 w - - 0 1");
+        assert_eq!(san_of(&rooks, "e1", "e2"), "R1e2");
+        assert_eq!(san_of(&rooks, "e3", "e2"), "R3e2");
+        assert!(matches!(
+            rooks.parse_san("Re2"),
+            Err(SanError::Ambiguous { .. })
+        ));
+
+        let queens = board("8/7k/8/8/8/Q7/8/Q1Q3K1 w - - 0 1");
+        assert_eq!(san_of(&queens, "a1", "b2"), "Qa1b2");
+        assert_eq!(san_of(&queens, "a3", "b2"), "Q3b2");
+        assert_eq!(san_of(&queens, "c1", "b2"), "Qcb2");
+    }
+
+    #[test]
+    fn pawn_captures_need_their_file_and_x() {
+        let board = board("rnbqkbnr/pppp1ppp/8/4p3/3P4/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 2");
+        assert_eq!(san_of(&board, "d4", "e5"), "dxe5");
+        assert_eq!(san_of(&board, "d4", "d5"), "d5");
+        assert_eq!(
+            board.parse_san("e5"),
+            Err(SanError::Illegal("e5".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("xe5"),
+            Err(SanError::Syntax("xe5".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("de5"),
+            Err(SanError::Syntax("de5".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("dxd5"),
+            Err(SanError::Illegal("dxd5".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("d4xe5"),
+            Err(SanError::Syntax("d4xe5".to_string()))
+        );
+
+        let en_passant =
+            ChessBoard::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+        assert_eq!(san_of(&en_passant, "e5", "f6"), "exf6");
+        assert_eq!(
+            en_passant.parse_san("exf6").map(|m| m.kind),
+            Ok(MoveKind::EnPassant)
+        );
+        assert_eq!(
+            en_passant.parse_san("f6"),
+            Err(SanError::Illegal("f6".to_string()))
+        );
+    }
+
+    #[test]
+    fn promotions_name_their_piece() {
+        let board = board("3r4/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+        assert_eq!(san_of(&board, "e7", "e8").len(), "e8=Q".len());
+        let queen = board.parse_san("e8=Q").unwrap();
+        assert_eq!(queen.promotion, Some(PieceKind::Queen));
+        assert_eq!(board.parse_san("e8Q"), Ok(queen));
+        assert_eq!(board.san(queen), "e8=Q");
+        assert_eq!(
+            board.parse_san("dxe8=N").map(|m| m.promotion),
+            Err(SanError::Illegal("dxe8=N".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("exd8=N").map(|m| m.promotion),
+            Ok(Some(PieceKind::Knight))
+        );
+        assert_eq!(
+            board.parse_san("e8"),
+            Err(SanError::MissingPromotion("e8".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("e8=K"),
+            Err(SanError::Syntax("e8=K".to_string()))
+        );
+    }
+
+    #[test]
+    fn illegal_moves_say_why() {
+        let start = ChessBoard::at_start();
+        assert_eq!(
+            start.parse_san("e5"),
+            Err(SanError::Illegal("e5".to_string()))
+        );
+        assert_eq!(
+            start.parse_san("Nf4"),
+            Err(SanError::Illegal("Nf4".to_string()))
+        );
+        assert_eq!(
+            start.parse_san("O-O"),
+            Err(SanError::Illegal("O-O".to_string()))
+        );
+        for nonsense in ["", "Zf3", "Nz9", "N", "e", "e2e4e"] {
+            assert_eq!(
+                start.parse_san(nonsense),
+                Err(SanError::Syntax(nonsense.to_string()))
+            );
+        }
+
+        let pinned = board("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1");
+        assert_eq!(
+            pinned.parse_san("Nc3"),
+            Err(SanError::ExposesKing("Nc3".to_string()))
+        );
+        assert_eq!(
+            pinned.parse_san("Nc3").unwrap_err().to_string(),
+            "`Nc3` would leave the king in check"
+        );
+    }
+
+    #[test]
+    fn castling_check_and_mate_marks() {
+        let board = board("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let short = board.parse_san("O-O").unwrap();
+        assert_eq!(short.kind, MoveKind::Castle(CastleSide::King));
+        assert_eq!(board.parse_san("0-0"), Ok(short));
+        assert_eq!(board.san(short), "O-O");
+        assert_eq!(board.san(board.parse_san("0-0-0").unwrap()), "O-O-O");
+
+        let mut fools = ChessBoard::at_start();
+        for san in ["f3", "e5", "g4"] {
+            let mv = fools.parse_san(san).unwrap();
+            fools.make_move(mv);
+        }
+        let mate = fools.parse_san("Qh4").unwrap();
+        assert_eq!(fools.san(mate), "Qh4#");
+        assert_eq!(fools.parse_san("Qh4#!?"), Ok(mate));
+        assert_eq!(san_of(&board, "a1", "a8"), "Rxa8+");
+    }
+}
@@ -0,0 +1,444 @@
+use rand::Rng;
+
+use super::{CastleSide, CastlingRights, ChessBoard, Color, Piece, PieceKind, Square};
+
+/// Where the two knights go among the five files left once the bishops and
+/// queen are placed, in Scharnagl's order.
+const KNIGHT_PLACES: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/// The number of Chess960 start positions; index 518 is the classical one.
+pub const CHESS960_POSITIONS: u16 = 960;
+pub const CLASSICAL_INDEX: u16 = 518;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PositionError {
+    #[error("{color} needs one king but has {count}")]
+    KingCount { color: Color, count: usize },
+    #[error("there's a pawn on {0}, a back rank")]
+    PawnOnBackRank(Square),
+    #[error("{0} is in check but it's not their move")]
+    WaitingInCheck(Color),
+    #[error("{color} can't castle {}: the king and rook aren't in place", side.name())]
+    Castling { color: Color, side: CastleSide },
+    #[error("en passant on {0} doesn't follow a pawn's double step")]
+    EnPassant(Square),
+    #[error("Chess960 start positions go from 0 to 959, not {0}")]
+    Chess960Index(u16),
+}
+
+impl ChessBoard {
+    /// Chess960 start position `index`, numbered as Scharnagl does: the
+    /// light-squared bishop, the dark-squared bishop, the queen, then the
+    /// knights, with the king between the rooks on what's left. Both sides
+    /// may castle with either rook.
+    pub fn chess960(index: u16) -> Result<Self, PositionError> {
+        if index >= CHESS960_POSITIONS {
+            return Err(PositionError::Chess960Index(index));
+        }
+        let mut n = index as usize;
+        let mut rank: [Option<PieceKind>; 8] = [None; 8];
+        rank[n % 4 * 2 + 1] = Some(PieceKind::Bishop);
+        n /= 4;
+        rank[n % 4 * 2] = Some(PieceKind::Bishop);
+        n /= 4;
+        place_on_empty(&mut rank, n % 6, PieceKind::Queen);
+        n /= 6;
+        let (first, second) = KNIGHT_PLACES[n];
+        // the second knight counts empty files after the first is placed
+        place_on_empty(&mut rank, second, PieceKind::Knight);
+        place_on_empty(&mut rank, first, PieceKind::Knight);
+        for kind in [PieceKind::Rook, PieceKind::King, PieceKind::Rook] {
+            place_on_empty(&mut rank, 0, kind);
+        }
+
+        let mut board = Self::blank();
+        let mut castling = CastlingRights::none();
+        for color in Color::ALL {
+            let pawn_rank = (color.back_rank() as i8 + color.forward()) as u8;
+            for (file, kind) in rank.iter().enumerate() {
+                let file = file as u8;
+                let kind = kind.expect("all eight files are filled");
+                board.set_piece(
+                    Square::new(file, color.back_rank()).unwrap(),
+                    Some(Piece::new(color, kind)),
+                );
+                board.set_piece(
+                    Square::new(file, pawn_rank).unwrap(),
+                    Some(Piece::new(color, PieceKind::Pawn)),
+                );
+            }
+            let rooks: Vec<u8> = (0..8)
+                .filter(|&f| rank[f as usize] == Some(PieceKind::Rook))
+                .collect();
+            castling.set(color, CastleSide::Queen, Some(rooks[0]));
+            castling.set(color, CastleSide::King, Some(rooks[1]));
+        }
+        board.castling = castling;
+        Ok(board)
+    }
+
+    pub fn random_chess960() -> Self {
+        let index = rand::thread_rng().gen_range(0..CHESS960_POSITIONS);
+        Self::chess960(index).expect("index is in range")
+    }
+
+    pub fn builder() -> PositionBuilder {
+        PositionBuilder::new()
+    }
+
+    /// Checks the position could come up in a game: one king each, no
+    /// pawns on the first or last rank, the side that just moved not left
+    /// in check, and castling and en passant rights that fit the pieces.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        for color in Color::ALL {
+            let count = self
+                .pieces()
+                .filter(|&(_, p)| p == Piece::new(color, PieceKind::King))
+                .count();
+            if count != 1 {
+                return Err(PositionError::KingCount { color, count });
+            }
+        }
+        if let Some((square, _)) = self
+            .pieces()
+            .find(|(sq, p)| p.kind == PieceKind::Pawn && (sq.rank() == 0 || sq.rank() == 7))
+        {
+            return Err(PositionError::PawnOnBackRank(square));
+        }
+
+        let waiting = !self.side_to_move;
+        let king = self.king_square(waiting).expect("both kings are present");
+        if self.is_attacked(king, self.side_to_move) {
+            return Err(PositionError::WaitingInCheck(waiting));
+        }
+
+        for color in Color::ALL {
+            for side in CastleSide::ALL {
+                if self.castling.has(color, side) && !self.can_castle_with(color, side) {
+                    return Err(PositionError::Castling { color, side });
+                }
+            }
+        }
+
+        if let Some(square) = self.en_passant {
+            let us = self.side_to_move;
+            let pawn = square.offset(0, -us.forward());
+            let origin = square.offset(0, us.forward());
+            let expected = (!us).back_rank() as i8 - 2 * us.forward();
+            let fits = square.rank() as i8 == expected
+                && self.piece_at(square).is_none()
+                && origin.is_some_and(|sq| self.piece_at(sq).is_none())
+                && pawn.and_then(|sq| self.piece_at(sq)) == Some(Piece::new(!us, PieceKind::Pawn));
+            if !fits {
+                return Err(PositionError::EnPassant(square));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `color`'s castling right on `side` has a king on the back
+    /// rank and a rook on the right side of it.
+    fn can_castle_with(&self, color: Color, side: CastleSide) -> bool {
+        let (Some(king), Some(rook)) = (self.king_square(color), self.castling_rook(color, side))
+        else {
+            return false;
+        };
+        king.rank() == color.back_rank()
+            && match side {
+                CastleSide::King => rook.file() > king.file(),
+                CastleSide::Queen => rook.file() < king.file(),
+            }
+    }
+}
+
+/// Puts `kind` on the `nth` file still empty.
+fn place_on_empty(rank: &mut [Option<PieceKind>; 8], nth: usize, kind: PieceKind) {
+    let file = (0..8)
+        .filter(|&f| rank[f].is_none())
+        .nth(nth)
+        .expect("there are enough empty files");
+    rank[file] = Some(kind);
+}
+
+/// Sets up any position piece by piece, checking it's legal at the end.
+#[derive(Debug, Clone)]
+pub struct PositionBuilder {
+    board: ChessBoard,
+    /// Castling rights to grant once the pieces are in place, with the
+    /// rook's file if it was given.
+    castling: Vec<(Color, CastleSide, Option<u8>)>,
+}
+
+impl PositionBuilder {
+    /// An empty board with white to move.
+    pub fn new() -> Self {
+        Self {
+            board: ChessBoard::blank(),
+            castling: Vec::new(),
+        }
+    }
+
+    /// Starts from `board`, keeping its pieces and rights.
+    pub fn from_board(board: ChessBoard) -> Self {
+        Self {
+            board,
+            castling: Vec::new(),
+        }
+    }
+
+    pub fn piece(mut self, square: Square, piece: Piece) -> Self {
+        self.board.set_piece(square, Some(piece));
+        self
+    }
+
+    pub fn clear(mut self, square: Square) -> Self {
+        self.board.set_piece(square, None);
+        self
+    }
+
+    pub fn side_to_move(mut self, color: Color) -> Self {
+        self.board.side_to_move = color;
+        self
+    }
+
+    /// Lets `color` castle on `side` with the rook furthest from the king.
+    pub fn castling(mut self, color: Color, side: CastleSide) -> Self {
+        self.castling.push((color, side, None));
+        self
+    }
+
+    /// Lets `color` castle with the rook on `rook_file`, as Chess960
+    /// positions with two rooks on one side need.
+    pub fn castling_with(mut self, color: Color, side: CastleSide, rook_file: u8) -> Self {
+        self.castling.push((color, side, Some(rook_file)));
+        self
+    }
+
+    pub fn en_passant(mut self, square: Square) -> Self {
+        self.board.en_passant = Some(square);
+        self
+    }
+
+    pub fn clocks(mut self, halfmove_clock: u32, fullmove_number: u32) -> Self {
+        self.board.set_clocks(halfmove_clock, fullmove_number);
+        self
+    }
+
+    pub fn build(self) -> Result<ChessBoard, PositionError> {
+        let mut board = self.board;
+        for (color, side, file) in self.castling {
+            let rook = Piece::new(color, PieceKind::Rook);
+            let file = file.or_else(|| {
+                let king = board.king_square(color)?;
+                let on_side = |f: &u8| match side {
+                    CastleSide::King => *f > king.file(),
+                    CastleSide::Queen => *f < king.file(),
+                };
+                let mut rooks = (0..8).filter(on_side).filter(|&f| {
+                    board.piece_at(Square::new(f, color.back_rank()).unwrap()) == Some(rook)
+                });
+                match side {
+                    CastleSide::King => rooks.next_back(),
+                    CastleSide::Queen => rooks.next(),
+                }
+            });
+            let Some(file) = file else {
+                return Err(PositionError::Castling { color, side });
+            };
+            board.castling.set(color, side, Some(file));
+        }
+        board.validate()?;
+        Ok(board)
+    }
+}
+
+impl Default for PositionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fen::START_FEN;
+    use super::super::perft::perft;
+    use super::*;
+    use std::collections::HashSet;
+
+    fn sq(name: &str) -> Square {
+        name.parse().unwrap()
+    }
+
+    fn kings() -> PositionBuilder {
+        ChessBoard::builder()
+            .piece(sq("e1"), Piece::new(Color::White, PieceKind::King))
+            .piece(sq("e8"), Piece::new(Color::Black, PieceKind::King))
+    }
+
+    #[test]
+    fn index_518_is_the_classical_start() {
+        let board = ChessBoard::chess960(CLASSICAL_INDEX).unwrap();
+        assert_eq!(board.to_fen(), START_FEN);
+    }
+
+    #[test]
+    fn every_index_gives_a_distinct_valid_position() {
+        let mut seen = HashSet::new();
+        for index in 0..CHESS960_POSITIONS {
+            let board = ChessBoard::chess960(index).unwrap();
+            assert_eq!(board.validate(), Ok(()), "index {}", index);
+            assert!(seen.insert(board.to_fen()), "index {} repeats", index);
+        }
+        assert_eq!(
+            ChessBoard::chess960(0).unwrap().to_fen(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn rejects_indices_past_959() {
+        assert_eq!(
+            ChessBoard::chess960(960).unwrap_err(),
+            PositionError::Chess960Index(960)
+        );
+        assert!(ChessBoard::chess960(959).is_ok());
+    }
+
+    /// From the Chess Programming Wiki's Chess960 perft results.
+    const CHESS960_PERFT: [(&str, [u64; 4]); 2] = [
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            [21, 528, 12_189, 326_672],
+        ),
+        (
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            [21, 807, 18_002, 667_366],
+        ),
+    ];
+
+    fn check_chess960_perft(max_depth: usize) {
+        for (fen, nodes) in CHESS960_PERFT {
+            let board = ChessBoard::from_fen(fen).unwrap();
+            for (depth, &expected) in nodes.iter().enumerate().take(max_depth) {
+                let depth = depth as u32 + 1;
+                assert_eq!(perft(&board, depth), expected, "{} at depth {}", fen, depth);
+            }
+        }
+    }
+
+    #[test]
+    fn chess960_perft_to_depth_3() {
+        check_chess960_perft(3);
+    }
+
+    #[test]
+    #[ignore = "slow without --release"]
+    fn chess960_perft_to_depth_4() {
+        check_chess960_perft(4);
+    }
+
+    #[test]
+    fn builds_a_legal_position() {
+        let board = kings()
+            .piece(sq("h1"), Piece::new(Color::White, PieceKind::Rook))
+            .castling(Color::White, CastleSide::King)
+            .side_to_move(Color::Black)
+            .clocks(3, 40)
+            .build()
+            .unwrap();
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/4K2R b K - 3 40");
+    }
+
+    #[test]
+    fn rejects_a_second_king() {
+        let result = kings()
+            .piece(sq("d1"), Piece::new(Color::White, PieceKind::King))
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            PositionError::KingCount {
+                color: Color::White,
+                count: 2
+            }
+        );
+        let result = ChessBoard::builder()
+            .piece(sq("e1"), Piece::new(Color::White, PieceKind::King))
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            PositionError::KingCount {
+                color: Color::Black,
+                count: 0
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_a_pawn_on_a_back_rank() {
+        let result = kings()
+            .piece(sq("a8"), Piece::new(Color::White, PieceKind::Pawn))
+            .build();
+        assert_eq!(result.unwrap_err(), PositionError::PawnOnBackRank(sq("a8")));
+    }
+
+    #[test]
+    fn rejects_check_on_the_side_not_to_move() {
+        let result = kings()
+            .piece(sq("e4"), Piece::new(Color::White, PieceKind::Rook))
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            PositionError::WaitingInCheck(Color::Black)
+        );
+    }
+
+    #[test]
+    fn rejects_castling_without_a_rook() {
+        let result = kings().castling(Color::Black, CastleSide::Queen).build();
+        assert_eq!(
+            result.unwrap_err(),
+            PositionError::Castling {
+                color: Color::Black,
+                side: CastleSide::Queen
+            }
+        );
+        // rights left over after the king has stepped off its rank
+        let mut board = ChessBoard::at_start();
+        board.set_piece(sq("e1"), None);
+        board.set_piece(sq("e2"), Some(Piece::new(Color::White, PieceKind::King)));
+        assert_eq!(
+            board.validate(),
+            Err(PositionError::Castling {
+                color: Color::White,
+                side: CastleSide::King
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_en_passant_without_a_double_step() {
+        let result = kings()
+            .piece(sq("e4"), Piece::new(Color::White, PieceKind::Pawn))
+            .side_to_move(Color::Black)
+            .en_passant(sq("e3"))
+            .build();
+        assert!(result.is_ok());
+        let result = kings()
+            .piece(sq("e4"), Piece::new(Color::White, PieceKind::Pawn))
+            .side_to_move(Color::Black)
+            .en_passant(sq("d3"))
+            .build();
+        assert_eq!(result.unwrap_err(), PositionError::EnPassant(sq("d3")));
+    }
+}
@@ -0,0 +1,54 @@
+use super::{CastleSide, ChessBoard, Color};
+
+/// One key per piece on each square, then castling rights by colour, side
+/// and rook file, then en passant file, then black to move.
+const PIECE_KEYS: usize = 12 * 64;
+const CASTLING_KEYS: usize = 2 * 2 * 8;
+const EN_PASSANT_KEYS: usize = 8;
+const KEY_COUNT: usize = PIECE_KEYS + CASTLING_KEYS + EN_PASSANT_KEYS + 1;
+
+static KEYS: [u64; KEY_COUNT] = keys();
+
+/// Fixed pseudo-random keys from splitmix64, so hashes are the same on
+/// every run.
+const fn keys() -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+impl ChessBoard {
+    /// A 64-bit hash of the position for transposition tables. The clocks
+    /// are left out, so positions that differ only in them collide.
+    pub fn zobrist(&self) -> u64 {
+        let mut hash = 0;
+        for (square, piece) in self.pieces() {
+            let index = (piece.color.index() * 6 + piece.kind.index()) * 64 + square.index();
+            hash ^= KEYS[index];
+        }
+        for color in Color::ALL {
+            for side in CastleSide::ALL {
+                if let Some(file) = self.castling.rook_file(color, side) {
+                    let index = (color.index() * 2 + side.index()) * 8 + file as usize;
+                    hash ^= KEYS[PIECE_KEYS + index];
+                }
+            }
+        }
+        if let Some(square) = self.en_passant {
+            hash ^= KEYS[PIECE_KEYS + CASTLING_KEYS + square.file() as usize];
+        }
+        if self.side_to_move == Color::Black {
+            hash ^= KEYS[KEY_COUNT - 1];
+        }
+        hash
+    }
+}
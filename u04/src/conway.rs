@@ -1,24 +1,139 @@
 use rand::{distributions::Bernoulli, prelude::Distribution, Rng};
+use std::collections::VecDeque;
 use std::fmt;
 
+mod packed;
+mod parallel;
+mod pattern;
+mod rule;
+
+use packed::BitGrid;
+
+pub use packed::first_mismatch;
+pub use parallel::ParallelError;
+pub use pattern::{Pattern, PatternError, MAX_CELLS};
+pub use rule::{NamedRule, Rule, RuleError, CATALOGUE};
+
 pub struct ConwaySim {
+    rule: Rule,
     cur: Universe,
     next: Universe,
+    generation: u64,
+    threads: usize,
+    /// Earlier generations, oldest first, for `step_back`.
+    history: VecDeque<Universe>,
+    history_len: usize,
 }
 
 impl ConwaySim {
     pub fn new(start: Universe) -> Self {
         let next = start.clone();
-        Self { cur: start, next }
+        Self {
+            rule: Rule::LIFE,
+            cur: start,
+            next,
+            generation: 0,
+            threads: 1,
+            history: VecDeque::new(),
+            history_len: 0,
+        }
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    /// Steps with `threads` threads, each working out a band of rows.
+    pub fn with_threads(mut self, threads: usize) -> Result<Self, ParallelError> {
+        self.set_threads(threads)?;
+        Ok(self)
+    }
+
+    pub fn set_threads(&mut self, threads: usize) -> Result<(), ParallelError> {
+        let (width, height) = self.cur.dimensions();
+        parallel::check_threads(width, height, threads)?;
+        self.threads = threads;
+        Ok(())
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Keeps up to `len` earlier generations so `step_back` can return to
+    /// them. Zero, the default, keeps none.
+    pub fn with_history(mut self, len: usize) -> Self {
+        self.set_history_len(len);
+        self
+    }
+
+    pub fn set_history_len(&mut self, len: usize) {
+        self.history_len = len;
+        while self.history.len() > len {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history_len
     }
 
     pub fn next(&mut self) {
-        self.cur.next_into(&mut self.next);
+        if self.threads > 1 {
+            self.cur
+                .next_into_parallel(&mut self.next, self.rule, self.threads)
+                .expect("the thread count was checked against the universe");
+        } else {
+            self.cur.next_into_with(&mut self.next, self.rule);
+        }
         std::mem::swap(&mut self.cur, &mut self.next);
+        self.generation += 1;
+        if self.history_len > 0 {
+            // the oldest kept generation becomes the spare buffer once the
+            // history is full
+            let spare = if self.history.len() == self.history_len {
+                self.history.pop_front().unwrap()
+            } else {
+                self.cur.clone()
+            };
+            self.history
+                .push_back(std::mem::replace(&mut self.next, spare));
+        }
+    }
+
+    /// Goes back one generation, if it's still in the history.
+    pub fn step_back(&mut self) -> bool {
+        match self.history.pop_back() {
+            Some(previous) => {
+                self.next = std::mem::replace(&mut self.cur, previous);
+                self.generation -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// How many generations `step_back` can undo.
+    pub fn steps_back(&self) -> usize {
+        self.history.len()
     }
 
     pub fn universe(&self) -> &Universe {
-        &self.next
+        &self.cur
+    }
+
+    /// Generations since the start universe, which is generation 0.
+    pub fn generation(&self) -> u64 {
+        self.generation
     }
 }
 
@@ -30,17 +145,42 @@ pub fn from_seed(seed: Universe) -> ConwaySim {
     ConwaySim::new(seed)
 }
 
+/// How a `Universe` stores its cells. Both give the same generations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// A `bool` per cell, neighbours counted one cell at a time.
+    #[default]
+    Rows,
+    /// A bit per cell, neighbours counted 64 cells at a time; much faster
+    /// on large grids.
+    Packed,
+}
+
 #[derive(Debug, Clone)]
 pub struct Universe {
-    cells: Vec<Vec<bool>>,
+    cells: Cells,
     width: usize,
     height: usize,
 }
 
+#[derive(Debug, Clone)]
+enum Cells {
+    Rows(Vec<Vec<bool>>),
+    Packed(BitGrid),
+}
+
 impl Universe {
     pub fn new(width: usize, height: usize) -> Self {
+        Self::with_backend(width, height, Backend::Rows)
+    }
+
+    pub fn with_backend(width: usize, height: usize, backend: Backend) -> Self {
+        let cells = match backend {
+            Backend::Rows => Cells::Rows(vec![vec![false; width]; height]),
+            Backend::Packed => Cells::Packed(BitGrid::new(width, height)),
+        };
         Self {
-            cells: vec![vec![false; width]; height],
+            cells,
             width,
             height,
         }
@@ -56,52 +196,110 @@ impl Universe {
         (self.width, self.height)
     }
 
+    pub fn backend(&self) -> Backend {
+        match self.cells {
+            Cells::Rows(_) => Backend::Rows,
+            Cells::Packed(_) => Backend::Packed,
+        }
+    }
+
+    /// A copy of the universe stored with `backend`.
+    pub fn to_backend(&self, backend: Backend) -> Universe {
+        let mut copy = Self::with_backend(self.width, self.height, backend);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.is_alive(row, col) {
+                    copy.set(row, col, true);
+                }
+            }
+        }
+        copy
+    }
+
+    pub fn is_alive(&self, row: usize, col: usize) -> bool {
+        match &self.cells {
+            Cells::Rows(cells) => cells[row][col],
+            Cells::Packed(grid) => grid.get(row, col),
+        }
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, alive: bool) {
+        match &mut self.cells {
+            Cells::Rows(cells) => cells[row][col] = alive,
+            Cells::Packed(grid) => grid.set(row, col, alive),
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        match &self.cells {
+            Cells::Rows(cells) => cells.iter().flatten().filter(|&&cell| cell).count(),
+            Cells::Packed(grid) => grid.population(),
+        }
+    }
+
     pub fn seed(&mut self, percent: u32) {
         let mut rng = rand::thread_rng();
         let distribution = Bernoulli::from_ratio(percent.clamp(0, 100), 100).unwrap();
-        for row in &mut self.cells {
-            for cell in row {
-                *cell = distribution.sample(&mut rng);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                self.set(row, col, distribution.sample(&mut rng));
             }
         }
     }
 
+    /// Writes the next generation under Conway's rules into `next`.
     pub fn next_into(&self, next: &mut Universe) {
+        self.next_into_with(next, Rule::LIFE);
+    }
+
+    /// Writes the next generation into `next`, which ends up with this
+    /// universe's backend.
+    pub fn next_into_with(&self, next: &mut Universe, rule: Rule) {
         assert!(self.dimensions() == next.dimensions());
-        for (ri, (row, next_row)) in self.cells.iter().zip(next.cells.iter_mut()).enumerate() {
-            for (ci, (cell, next_cell)) in row.iter().zip(next_row.iter_mut()).enumerate() {
-                *next_cell = self.next_state(*cell, ri, ci);
-            }
+        next.match_backend(self.backend());
+        match (&self.cells, &mut next.cells) {
+            (Cells::Rows(cells), Cells::Rows(next_cells)) => next_rows(cells, 0, next_cells, rule),
+            (Cells::Packed(grid), Cells::Packed(next_grid)) => grid.next_into(next_grid, rule),
+            _ => unreachable!("the backends match"),
         }
     }
 
-    fn next_state(&self, alive: bool, row: usize, col: usize) -> bool {
-        match (alive, self.live_neighbors(row, col)) {
-            (true, n) => n == 2 || n == 3,
-            (false, 3) => true,
-            (false, _) => false,
+    /// Swaps in empty cells stored with `backend` if they're stored another
+    /// way.
+    fn match_backend(&mut self, backend: Backend) {
+        if self.backend() != backend {
+            *self = Self::with_backend(self.width, self.height, backend);
         }
     }
+}
 
-    fn live_neighbors(&self, row: usize, col: usize) -> usize {
-        let (width, height) = (self.width, self.height);
-        let prev_row = &self.cells[dec_index_wrapping(row, height)];
-        let cur_row = &self.cells[row];
-        let next_row = &self.cells[inc_index_wrapping(row, height)];
-        let next_col = inc_index_wrapping(col, width);
-        let prev_col = dec_index_wrapping(col, width);
-
-        prev_row[prev_col] as usize
-            + prev_row[col] as usize
-            + prev_row[next_col] as usize
-            + cur_row[prev_col] as usize
-            + cur_row[next_col] as usize
-            + next_row[prev_col] as usize
-            + next_row[col] as usize
-            + next_row[next_col] as usize
+/// Works out the next generation of `out.len()` rows from `first` on.
+fn next_rows(cells: &[Vec<bool>], first: usize, out: &mut [Vec<bool>], rule: Rule) {
+    for (ri, next_row) in (first..).zip(out) {
+        for (ci, (cell, next_cell)) in cells[ri].iter().zip(next_row.iter_mut()).enumerate() {
+            *next_cell = rule.next_state(*cell, live_neighbors(cells, ri, ci));
+        }
     }
 }
 
+fn live_neighbors(cells: &[Vec<bool>], row: usize, col: usize) -> usize {
+    let (width, height) = (cells[row].len(), cells.len());
+    let prev_row = &cells[dec_index_wrapping(row, height)];
+    let cur_row = &cells[row];
+    let next_row = &cells[inc_index_wrapping(row, height)];
+    let next_col = inc_index_wrapping(col, width);
+    let prev_col = dec_index_wrapping(col, width);
+
+    prev_row[prev_col] as usize
+        + prev_row[col] as usize
+        + prev_row[next_col] as usize
+        + cur_row[prev_col] as usize
+        + cur_row[next_col] as usize
+        + next_row[prev_col] as usize
+        + next_row[col] as usize
+        + next_row[next_col] as usize
+}
+
 #[inline]
 fn inc_index_wrapping(index: usize, len: usize) -> usize {
     (index + 1) % len
@@ -112,15 +310,33 @@ fn dec_index_wrapping(index: usize, len: usize) -> usize {
     (index + len - 1) % len
 }
 
+/// Universes are equal when their cells are, whatever the backends.
+impl PartialEq for Universe {
+    fn eq(&self, other: &Self) -> bool {
+        if self.dimensions() != other.dimensions() {
+            return false;
+        }
+        match (&self.cells, &other.cells) {
+            (Cells::Rows(a), Cells::Rows(b)) => a == b,
+            (Cells::Packed(a), Cells::Packed(b)) => a == b,
+            _ => (0..self.height).all(|row| {
+                (0..self.width).all(|col| self.is_alive(row, col) == other.is_alive(row, col))
+            }),
+        }
+    }
+}
+
+impl Eq for Universe {}
+
 impl fmt::Display for Universe {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let width = self.width;
         let hmargin: String = "-".repeat(width);
         writeln!(f, " {}", hmargin)?;
-        for row in &self.cells {
+        for row in 0..self.height {
             write!(f, "|")?;
-            for cell in row.iter().copied() {
-                write!(f, "{}", if cell { '+' } else { ' ' })?;
+            for col in 0..width {
+                write!(f, "{}", if self.is_alive(row, col) { '+' } else { ' ' })?;
             }
             writeln!(f, "|")?;
         }
@@ -128,3 +344,112 @@ impl fmt::Display for Universe {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: [(usize, usize); 5] = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+
+    fn universe(
+        width: usize,
+        height: usize,
+        backend: Backend,
+        cells: &[(usize, usize)],
+    ) -> Universe {
+        let mut universe = Universe::with_backend(width, height, backend);
+        for &(row, col) in cells {
+            universe.set(row, col, true);
+        }
+        universe
+    }
+
+    #[test]
+    fn blinker_returns_after_two_generations() {
+        for backend in [Backend::Rows, Backend::Packed] {
+            let across = universe(5, 5, backend, &[(2, 1), (2, 2), (2, 3)]);
+            let down = universe(5, 5, backend, &[(1, 2), (2, 2), (3, 2)]);
+            let mut sim = from_seed(across.clone());
+            assert_eq!(sim.generation(), 0);
+            sim.next();
+            assert_eq!((sim.generation(), sim.universe()), (1, &down));
+            sim.next();
+            assert_eq!((sim.generation(), sim.universe()), (2, &across));
+        }
+    }
+
+    #[test]
+    fn glider_moves_diagonally_every_four_generations() {
+        for backend in [Backend::Rows, Backend::Packed] {
+            let mut sim = from_seed(universe(8, 8, backend, &GLIDER));
+            for shift in 1..=3 {
+                for _ in 0..4 {
+                    sim.next();
+                }
+                let moved: Vec<_> = GLIDER
+                    .iter()
+                    .map(|&(r, c)| (r + shift, c + shift))
+                    .collect();
+                assert_eq!(
+                    sim.universe(),
+                    &universe(8, 8, backend, &moved),
+                    "{:?}",
+                    backend
+                );
+                assert_eq!(sim.universe().population(), 5);
+            }
+            assert_eq!(sim.generation(), 12);
+        }
+    }
+
+    #[test]
+    fn history_keeps_only_the_latest_generations() {
+        let mut sim = from_seed(universe(8, 8, Backend::Rows, &GLIDER)).with_history(3);
+        let mut seen = vec![sim.universe().clone()];
+        for _ in 0..6 {
+            sim.next();
+            seen.push(sim.universe().clone());
+        }
+        assert_eq!(sim.steps_back(), 3);
+        for generation in (3..6).rev() {
+            assert!(sim.step_back());
+            assert_eq!(sim.generation(), generation);
+            assert_eq!(sim.universe(), &seen[generation as usize]);
+        }
+        // generations 0 to 2 were evicted
+        assert_eq!(sim.steps_back(), 0);
+        assert!(!sim.step_back());
+        assert_eq!(sim.generation(), 3);
+        assert_eq!(sim.universe(), &seen[3]);
+
+        // stepping on from there goes the same way as before
+        sim.next();
+        assert_eq!((sim.generation(), sim.universe()), (4, &seen[4]));
+        assert_eq!(sim.steps_back(), 1);
+    }
+
+    #[test]
+    fn shrinking_the_history_drops_the_oldest() {
+        let mut sim = from_seed(universe(8, 8, Backend::Packed, &GLIDER)).with_history(4);
+        let start = sim.universe().clone();
+        for _ in 0..4 {
+            sim.next();
+        }
+        sim.set_history_len(1);
+        assert_eq!(sim.steps_back(), 1);
+        assert!(sim.step_back());
+        assert!(!sim.step_back());
+        assert_eq!(sim.generation(), 3);
+        assert_ne!(sim.universe(), &start);
+    }
+
+    #[test]
+    fn without_history_there_is_no_stepping_back() {
+        let mut sim = from_seed(universe(5, 5, Backend::Rows, &[(2, 1), (2, 2), (2, 3)]));
+        assert!(!sim.step_back());
+        sim.next();
+        assert_eq!(sim.steps_back(), 0);
+        assert!(!sim.step_back());
+        assert_eq!(sim.generation(), 1);
+    }
+}
@@ -0,0 +1,282 @@
+use super::{Backend, Rule, Universe};
+
+/// Cells one bit each, 64 to a word, with every row starting on a fresh
+/// word. Bits past the end of a row are always zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct BitGrid {
+    words: Vec<u64>,
+    width: usize,
+    height: usize,
+    /// Words per row.
+    stride: usize,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(64);
+        Self {
+            words: vec![0; stride * height],
+            width,
+            height,
+            stride,
+        }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        assert!(col < self.width);
+        self.words[row * self.stride + col / 64] & (1 << (col % 64)) != 0
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, alive: bool) {
+        assert!(col < self.width);
+        let word = &mut self.words[row * self.stride + col / 64];
+        if alive {
+            *word |= 1 << (col % 64);
+        } else {
+            *word &= !(1 << (col % 64));
+        }
+    }
+
+    pub fn population(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn row(&self, row: usize) -> &[u64] {
+        &self.words[row * self.stride..(row + 1) * self.stride]
+    }
+
+    pub fn next_into(&self, next: &mut BitGrid, rule: Rule) {
+        assert!((self.width, self.height) == (next.width, next.height));
+        self.next_rows(0, &mut next.words, rule);
+    }
+
+    /// Works out the next generation of the rows from `first` on into
+    /// `out`, as many as it holds.
+    pub fn next_rows(&self, first: usize, out: &mut [u64], rule: Rule) {
+        if self.stride == 0 {
+            return;
+        }
+        let table = RuleMasks::new(rule);
+        for (row, out) in (first..).zip(out.chunks_mut(self.stride)) {
+            self.next_row(row, out, &table);
+        }
+    }
+
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    pub fn words_mut(&mut self) -> &mut [u64] {
+        &mut self.words
+    }
+
+    /// Works out row `row` of the next generation into `out`, 64 cells at
+    /// a time.
+    fn next_row(&self, row: usize, out: &mut [u64], table: &RuleMasks) {
+        let above = self.row((row + self.height - 1) % self.height);
+        let here = self.row(row);
+        let below = self.row((row + 1) % self.height);
+        let last = self.stride - 1;
+        for (i, out) in out.iter_mut().enumerate() {
+            let mut count = Counter::default();
+            // a cell isn't its own neighbour, but the ones above and below are
+            for (line, straight) in [(above, true), (here, false), (below, true)] {
+                count.add(self.west(line, i));
+                count.add(self.east(line, i));
+                if straight {
+                    count.add(line[i]);
+                }
+            }
+            *out = table.apply(here[i], &count);
+        }
+        // keep the padding past the last cell clear
+        out[last] &= self.last_word_mask();
+    }
+
+    /// Word `i` of `line` with each bit holding the cell to its left,
+    /// wrapping round from the end of the row.
+    fn west(&self, line: &[u64], i: usize) -> u64 {
+        let carry = if i == 0 {
+            (line[self.stride - 1] >> ((self.width - 1) % 64)) & 1
+        } else {
+            line[i - 1] >> 63
+        };
+        (line[i] << 1) | carry
+    }
+
+    /// Word `i` of `line` with each bit holding the cell to its right,
+    /// wrapping round from the start of the row.
+    fn east(&self, line: &[u64], i: usize) -> u64 {
+        let shifted = line[i] >> 1;
+        if i == self.stride - 1 {
+            shifted | ((line[0] & 1) << ((self.width - 1) % 64))
+        } else {
+            shifted | (line[i + 1] << 63)
+        }
+    }
+
+    fn last_word_mask(&self) -> u64 {
+        match self.width % 64 {
+            0 => !0,
+            used => (1 << used) - 1,
+        }
+    }
+}
+
+/// Neighbour counts from 0 to 8 for 64 cells at once, one bit of the count
+/// per word.
+#[derive(Default)]
+struct Counter([u64; 4]);
+
+impl Counter {
+    fn add(&mut self, cells: u64) {
+        let mut carry = cells;
+        for bit in &mut self.0 {
+            let next = *bit & carry;
+            *bit ^= carry;
+            carry = next;
+        }
+    }
+
+    /// The cells whose count is `n`.
+    fn equals(&self, n: usize) -> u64 {
+        self.0.iter().enumerate().fold(!0, |mask, (i, &bit)| {
+            mask & if n & (1 << i) != 0 { bit } else { !bit }
+        })
+    }
+}
+
+/// For each neighbour count, whether dead and live cells with it are alive
+/// next generation.
+struct RuleMasks {
+    counts: Vec<(usize, bool, bool)>,
+}
+
+impl RuleMasks {
+    fn new(rule: Rule) -> Self {
+        let counts = (0..=8)
+            .map(|n| (n, rule.births(n), rule.survives(n)))
+            .filter(|&(_, birth, survival)| birth || survival)
+            .collect();
+        Self { counts }
+    }
+
+    fn apply(&self, alive: u64, count: &Counter) -> u64 {
+        self.counts.iter().fold(0, |next, &(n, birth, survival)| {
+            let cells = match (birth, survival) {
+                (true, true) => !0,
+                (true, false) => !alive,
+                _ => alive,
+            };
+            next | (count.equals(n) & cells)
+        })
+    }
+}
+
+/// Steps `start` on both backends side by side under `rule`, serially and
+/// in bands on a few thread counts, and returns the first generation where
+/// any of them disagree. The rows backend is simple enough to trust, so
+/// this checks the word-at-a-time tricks above against it.
+pub fn first_mismatch(start: &Universe, rule: Rule, generations: usize) -> Option<usize> {
+    let mut rows = start.to_backend(Backend::Rows);
+    let mut packed = start.to_backend(Backend::Packed);
+    let (mut next_rows, mut next_packed) = (rows.clone(), packed.clone());
+    // up to one band a row on the small sizes
+    let height = start.dimensions().1;
+    let mut thread_counts = vec![2, 3, height.min(16)];
+    thread_counts.retain(|&threads| threads <= height);
+    thread_counts.dedup();
+    for generation in 1..=generations {
+        rows.next_into_with(&mut next_rows, rule);
+        packed.next_into_with(&mut next_packed, rule);
+        std::mem::swap(&mut rows, &mut next_rows);
+        std::mem::swap(&mut packed, &mut next_packed);
+        let parallel_agrees = thread_counts.iter().all(|&threads| {
+            [&rows, &packed].into_iter().all(|universe| {
+                let mut stepped = universe.clone();
+                let mut serial = universe.clone();
+                universe.next_into_with(&mut serial, rule);
+                universe
+                    .next_into_parallel(&mut stepped, rule, threads)
+                    .is_ok_and(|()| stepped == serial)
+            })
+        });
+        if rows != packed || !parallel_agrees {
+            return Some(generation);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::CATALOGUE;
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Odd sizes, to catch mistakes at the edges of the packed words.
+    const SIZES: [(usize, usize); 9] = [
+        (1, 1),
+        (1, 7),
+        (7, 1),
+        (63, 5),
+        (64, 4),
+        (65, 9),
+        (127, 3),
+        (128, 6),
+        (130, 17),
+    ];
+
+    fn seeded(width: usize, height: usize, density: f64, rng: &mut StdRng) -> Universe {
+        let mut universe = Universe::new(width, height);
+        for row in 0..height {
+            for col in 0..width {
+                universe.set(row, col, rng.gen_bool(density));
+            }
+        }
+        universe
+    }
+
+    #[test]
+    fn backends_agree_on_every_rule_and_size() {
+        let mut rng = StdRng::seed_from_u64(49);
+        for named in &CATALOGUE {
+            for &(width, height) in &SIZES {
+                let start = seeded(width, height, 0.35, &mut rng);
+                assert_eq!(
+                    first_mismatch(&start, named.rule, 20),
+                    None,
+                    "{} on {}x{}",
+                    named.name,
+                    width,
+                    height
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bits_past_the_row_end_stay_clear() {
+        for width in [1, 63, 64, 65, 130] {
+            let mut grid = BitGrid::new(width, 3);
+            for row in 0..3 {
+                for col in 0..width {
+                    grid.set(row, col, true);
+                }
+            }
+            assert_eq!(grid.population(), 3 * width);
+            assert!(grid.get(1, width - 1));
+            // every cell of a full grid has eight live neighbours, so it
+            // stays full, and a stray bit past the end would be counted
+            let rule = Rule::from_counts(&[8], &[8]);
+            let mut next = BitGrid::new(width, 3);
+            grid.next_into(&mut next, rule);
+            assert_eq!(next, grid);
+            assert_eq!(next.population(), 3 * width);
+            grid.set(2, width - 1, false);
+            assert!(!grid.get(2, width - 1));
+            assert_eq!(grid.population(), 3 * width - 1);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::ops::Range;
+use std::thread;
+
+use super::{next_rows, Cells, Rule, Universe};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParallelError {
+    #[error("stepping needs at least one thread")]
+    NoThreads,
+    #[error("the universe is {width}x{height}, with no cells to share out")]
+    Empty { width: usize, height: usize },
+    #[error("{threads} threads would be more than the {rows} rows to share between them")]
+    TooManyThreads { threads: usize, rows: usize },
+    #[error("can't step from {}x{} cells into {}x{}", from.0, from.1, into.0, into.1)]
+    Dimensions {
+        from: (usize, usize),
+        into: (usize, usize),
+    },
+}
+
+/// Checks `threads` can share out the rows of a `width` by `height`
+/// universe, each getting at least one.
+pub fn check_threads(width: usize, height: usize, threads: usize) -> Result<(), ParallelError> {
+    if threads == 0 {
+        return Err(ParallelError::NoThreads);
+    }
+    if width == 0 || height == 0 {
+        return Err(ParallelError::Empty { width, height });
+    }
+    if threads > height {
+        return Err(ParallelError::TooManyThreads {
+            threads,
+            rows: height,
+        });
+    }
+    Ok(())
+}
+
+/// `rows` split into `count` runs of consecutive rows, the first few one
+/// row longer when they don't divide evenly.
+fn bands(rows: usize, count: usize) -> Vec<Range<usize>> {
+    let (size, longer) = (rows / count, rows % count);
+    let mut start = 0;
+    (0..count)
+        .map(|i| {
+            let end = start + size + usize::from(i < longer);
+            let band = start..end;
+            start = end;
+            band
+        })
+        .collect()
+}
+
+/// Cuts `items`, `per_row` of them to a row, into the rows of each band.
+fn split_bands<'a, T>(
+    mut items: &'a mut [T],
+    per_row: usize,
+    bands: &[Range<usize>],
+) -> Vec<(usize, &'a mut [T])> {
+    bands
+        .iter()
+        .map(|band| {
+            let (head, tail) = std::mem::take(&mut items).split_at_mut(band.len() * per_row);
+            items = tail;
+            (band.start, head)
+        })
+        .collect()
+}
+
+impl Universe {
+    /// Like `next_into_with`, but shares the rows out between `threads`
+    /// threads in bands. Every band reads only this universe and writes
+    /// only its own rows of `next`, so the result is the same as stepping
+    /// on one thread.
+    pub fn next_into_parallel(
+        &self,
+        next: &mut Universe,
+        rule: Rule,
+        threads: usize,
+    ) -> Result<(), ParallelError> {
+        if self.dimensions() != next.dimensions() {
+            return Err(ParallelError::Dimensions {
+                from: self.dimensions(),
+                into: next.dimensions(),
+            });
+        }
+        check_threads(self.width, self.height, threads)?;
+        next.match_backend(self.backend());
+        let bands = bands(self.height, threads);
+        match (&self.cells, &mut next.cells) {
+            (Cells::Rows(cells), Cells::Rows(next_cells)) => thread::scope(|scope| {
+                for (first, out) in split_bands(next_cells, 1, &bands) {
+                    scope.spawn(move || next_rows(cells, first, out, rule));
+                }
+            }),
+            (Cells::Packed(grid), Cells::Packed(next_grid)) => {
+                let stride = grid.stride();
+                thread::scope(|scope| {
+                    for (first, out) in split_bands(next_grid.words_mut(), stride, &bands) {
+                        scope.spawn(move || grid.next_rows(first, out, rule));
+                    }
+                })
+            }
+            _ => unreachable!("the backends match"),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Backend, CATALOGUE};
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn seeded(width: usize, height: usize, backend: Backend, seed: u64) -> Universe {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut universe = Universe::with_backend(width, height, backend);
+        for row in 0..height {
+            for col in 0..width {
+                universe.set(row, col, rng.gen_bool(0.4));
+            }
+        }
+        universe
+    }
+
+    #[test]
+    fn bands_cover_every_row_once() {
+        assert_eq!(bands(17, 3), [0..6, 6..12, 12..17]);
+        // one row each, bar the first
+        let thin = bands(17, 16);
+        assert_eq!(thin.len(), 16);
+        assert_eq!((thin[0].clone(), thin[1].clone()), (0..2, 2..3));
+        assert_eq!(thin.last(), Some(&(16..17)));
+        assert!(thin.windows(2).all(|pair| pair[0].end == pair[1].start));
+    }
+
+    #[test]
+    fn parallel_matches_serial_on_uneven_bands() {
+        for backend in [Backend::Rows, Backend::Packed] {
+            for width in [5, 64, 70] {
+                for (seed, named) in CATALOGUE.iter().enumerate().take(4) {
+                    let mut universe = seeded(width, 17, backend, seed as u64);
+                    for _ in 0..8 {
+                        let mut serial = universe.clone();
+                        universe.next_into_with(&mut serial, named.rule);
+                        for threads in [1, 3, 16, 17] {
+                            // `next` starts on the other backend with other cells
+                            let other = match backend {
+                                Backend::Rows => Backend::Packed,
+                                Backend::Packed => Backend::Rows,
+                            };
+                            let mut stepped = seeded(width, 17, other, 99);
+                            universe
+                                .next_into_parallel(&mut stepped, named.rule, threads)
+                                .unwrap();
+                            assert_eq!(stepped.backend(), backend);
+                            assert_eq!(
+                                stepped, serial,
+                                "{:?} {} wide under {} on {} threads",
+                                backend, width, named.name, threads
+                            );
+                        }
+                        universe = serial;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn checks_the_thread_count() {
+        assert_eq!(check_threads(10, 10, 0), Err(ParallelError::NoThreads));
+        assert_eq!(
+            check_threads(0, 10, 2),
+            Err(ParallelError::Empty {
+                width: 0,
+                height: 10
+            })
+        );
+        assert_eq!(
+            check_threads(10, 0, 2),
+            Err(ParallelError::Empty {
+                width: 10,
+                height: 0
+            })
+        );
+        assert_eq!(
+            check_threads(10, 17, 18),
+            Err(ParallelError::TooManyThreads {
+                threads: 18,
+                rows: 17
+            })
+        );
+        assert_eq!(check_threads(10, 17, 17), Ok(()));
+
+        let universe = Universe::new(10, 4);
+        let mut next = universe.clone();
+        assert_eq!(
+            universe.next_into_parallel(&mut next, Rule::LIFE, 5),
+            Err(ParallelError::TooManyThreads {
+                threads: 5,
+                rows: 4
+            })
+        );
+        assert_eq!(
+            ParallelError::TooManyThreads {
+                threads: 5,
+                rows: 4
+            }
+            .to_string(),
+            "5 threads would be more than the 4 rows to share between them"
+        );
+    }
+
+    #[test]
+    fn refuses_a_next_of_another_size() {
+        let universe = seeded(8, 6, Backend::Packed, 1);
+        let mut next = Universe::new(6, 8);
+        let before = next.clone();
+        assert_eq!(
+            universe.next_into_parallel(&mut next, Rule::LIFE, 2),
+            Err(ParallelError::Dimensions {
+                from: (8, 6),
+                into: (6, 8)
+            })
+        );
+        // nothing was written
+        assert_eq!(next, before);
+        assert_eq!(next.backend(), Backend::Rows);
+    }
+}
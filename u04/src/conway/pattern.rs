@@ -0,0 +1,777 @@
+use std::fmt::Write;
+
+use super::{Rule, RuleError, Universe};
+
+/// Longest line `to_rle` writes, as the format asks.
+const RLE_LINE: usize = 70;
+
+/// Most cells an RLE header may ask for, 4096 by 4096 or the like. Cells
+/// are allocated before the body is read, so a typo'd or hostile header
+/// mustn't be able to ask for more than any real pattern needs.
+pub const MAX_CELLS: usize = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PatternError {
+    #[error("line {line}, column {column}: {message}")]
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A rule in an RLE header that doesn't parse. Any column in `source`
+    /// counts along the whole header line.
+    #[error("line {line}: rule `{rule}`: {source}")]
+    Rule {
+        line: usize,
+        rule: String,
+        source: RuleError,
+    },
+    #[error("a {}x{} pattern doesn't fit in a {}x{} universe", pattern.0, pattern.1, universe.0, universe.1)]
+    TooLarge {
+        pattern: (usize, usize),
+        universe: (usize, usize),
+    },
+}
+
+fn syntax(line: usize, column: usize, message: impl Into<String>) -> PatternError {
+    PatternError::Syntax {
+        line,
+        column,
+        message: message.into(),
+    }
+}
+
+/// A rectangle of cells read from or written to a pattern file, with the
+/// name, comments and rule the file gave.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pattern {
+    name: Option<String>,
+    comments: Vec<String>,
+    rule: Option<Rule>,
+    cells: Vec<Vec<bool>>,
+    width: usize,
+    height: usize,
+}
+
+impl Pattern {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![vec![false; width]; height],
+            width,
+            height,
+            ..Self::default()
+        }
+    }
+
+    /// Reads Run Length Encoded Life: `#` comment lines, an
+    /// `x = 3, y = 3, rule = B3/S23` header, then runs of `b` (dead) and
+    /// `o` (alive) cells, with `$` ending a row and `!` the pattern.
+    pub fn from_rle(text: &str) -> Result<Self, PatternError> {
+        let mut pattern = Pattern::default();
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let (header_line, header) = loop {
+            let Some((n, line)) = lines.next() else {
+                return Err(syntax(
+                    text.lines().count().max(1),
+                    1,
+                    "there's no `x = ..., y = ...` header",
+                ));
+            };
+            match line.trim_start().strip_prefix('#') {
+                Some(comment) => pattern.rle_comment(comment),
+                None if line.trim().is_empty() => {}
+                None => break (n, line),
+            }
+        };
+        let (width, height, rule) = parse_header(header_line, header)?;
+        pattern.rule = rule;
+        pattern.width = width;
+        pattern.height = height;
+        pattern.cells = vec![vec![false; width]; height];
+
+        let (mut row, mut col) = (0, 0);
+        let mut run: Option<usize> = None;
+        let mut last_line = header_line;
+        for (n, line) in lines {
+            last_line = n;
+            for (i, c) in line.chars().enumerate() {
+                let column = i + 1;
+                if let Some(digit) = c.to_digit(10) {
+                    run = run
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|run| run.checked_add(digit as usize));
+                    if run.is_none() {
+                        return Err(syntax(n, column, "the run count is too large"));
+                    }
+                    continue;
+                }
+                let count = run.take().unwrap_or(1);
+                match c {
+                    'b' | '.' => col += count,
+                    'o' => {
+                        if row >= height {
+                            return Err(syntax(
+                                n,
+                                column,
+                                format!("there are more rows than y = {}", height),
+                            ));
+                        }
+                        if col + count > width {
+                            return Err(syntax(
+                                n,
+                                column,
+                                format!("row {} is wider than x = {}", row + 1, width),
+                            ));
+                        }
+                        pattern.cells[row][col..col + count].fill(true);
+                        col += count;
+                    }
+                    '$' => {
+                        row += count;
+                        col = 0;
+                    }
+                    // anything after the end is a comment
+                    '!' => return Ok(pattern),
+                    c if c.is_whitespace() => {}
+                    c if c.is_ascii_alphabetic() => {
+                        return Err(syntax(
+                            n,
+                            column,
+                            format!("`{}` is a multi-state cell, which Life doesn't have", c),
+                        ))
+                    }
+                    c => {
+                        return Err(syntax(
+                            n,
+                            column,
+                            format!("expected `b`, `o`, `$` or `!` but found `{}`", c),
+                        ))
+                    }
+                }
+            }
+        }
+        let column = text
+            .lines()
+            .nth(last_line - 1)
+            .map_or(0, |line| line.chars().count())
+            + 1;
+        Err(syntax(
+            last_line,
+            column,
+            "the pattern doesn't end with `!`",
+        ))
+    }
+
+    /// Handles the text after `#` on an RLE comment line.
+    fn rle_comment(&mut self, comment: &str) {
+        let mut chars = comment.chars();
+        match chars.next() {
+            Some('N') => self.name = Some(chars.as_str().trim().to_string()),
+            Some(_) => self.comments.push(chars.as_str().trim().to_string()),
+            None => {}
+        }
+    }
+
+    /// Reads the plaintext `.cells` format: `!` comment lines, with
+    /// `!Name:` naming the pattern, then rows of `.` (dead) and `O` (alive).
+    pub fn from_cells(text: &str) -> Result<Self, PatternError> {
+        let mut pattern = Pattern::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if let Some(comment) = line.strip_prefix('!') {
+                match comment.strip_prefix("Name:") {
+                    Some(name) => pattern.name = Some(name.trim().to_string()),
+                    None => pattern.comments.push(comment.trim().to_string()),
+                }
+                continue;
+            }
+            let mut row = Vec::with_capacity(line.len());
+            for (j, c) in line.chars().enumerate() {
+                row.push(match c {
+                    '.' => false,
+                    'O' | '*' => true,
+                    c => {
+                        return Err(syntax(
+                            i + 1,
+                            j + 1,
+                            format!("expected `.` or `O` but found `{}`", c),
+                        ))
+                    }
+                });
+            }
+            pattern.width = pattern.width.max(row.len());
+            pattern.cells.push(row);
+        }
+        pattern.height = pattern.cells.len();
+        for row in &mut pattern.cells {
+            row.resize(pattern.width, false);
+        }
+        Ok(pattern)
+    }
+
+    pub fn to_rle(&self) -> String {
+        let mut rle = String::new();
+        if let Some(name) = &self.name {
+            let _ = writeln!(rle, "#N {}", name);
+        }
+        for comment in &self.comments {
+            let _ = writeln!(rle, "{}", format!("#C {}", comment).trim_end());
+        }
+        let _ = writeln!(
+            rle,
+            "x = {}, y = {}, rule = {}",
+            self.width,
+            self.height,
+            self.rule.unwrap_or_default()
+        );
+
+        let mut line = String::new();
+        let mut push = |token: String, rle: &mut String| {
+            if line.len() + token.len() > RLE_LINE {
+                rle.push_str(&line);
+                rle.push('\n');
+                line.clear();
+            }
+            line.push_str(&token);
+        };
+        let token = |count: usize, tag: char| match count {
+            1 => tag.to_string(),
+            n => format!("{}{}", n, tag),
+        };
+        // row ends are held back so that empty rows at the bottom vanish
+        let mut row_ends = 0;
+        for row in &self.cells {
+            let Some(last) = row.iter().rposition(|&cell| cell) else {
+                row_ends += 1;
+                continue;
+            };
+            if row_ends > 0 {
+                push(token(row_ends, '$'), &mut rle);
+            }
+            let mut cells = &row[..=last];
+            while let Some(&alive) = cells.first() {
+                let count = cells.iter().take_while(|&&cell| cell == alive).count();
+                push(token(count, if alive { 'o' } else { 'b' }), &mut rle);
+                cells = &cells[count..];
+            }
+            row_ends = 1;
+        }
+        push("!".to_string(), &mut rle);
+        rle.push_str(&line);
+        rle.push('\n');
+        rle
+    }
+
+    pub fn to_cells(&self) -> String {
+        let mut cells = String::new();
+        if let Some(name) = &self.name {
+            let _ = writeln!(cells, "!Name: {}", name);
+        }
+        for comment in &self.comments {
+            let _ = writeln!(cells, "!{}", comment);
+        }
+        for row in &self.cells {
+            cells.extend(row.iter().map(|&cell| if cell { 'O' } else { '.' }));
+            cells.push('\n');
+        }
+        cells
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comments.push(comment.to_string());
+        self
+    }
+
+    /// The rule the pattern was written for, if the file said.
+    pub fn rule(&self) -> Option<Rule> {
+        self.rule
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = Some(rule);
+        self
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn is_alive(&self, row: usize, col: usize) -> bool {
+        self.cells[row][col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, alive: bool) {
+        self.cells[row][col] = alive;
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().flatten().filter(|&&cell| cell).count()
+    }
+
+    /// Turned a quarter clockwise.
+    pub fn rotate_cw(&self) -> Self {
+        let height = self.height;
+        self.remap(self.height, self.width, |row, col| (col, height - 1 - row))
+    }
+
+    /// Turned a quarter anticlockwise.
+    pub fn rotate_ccw(&self) -> Self {
+        let width = self.width;
+        self.remap(self.height, self.width, |row, col| (width - 1 - col, row))
+    }
+
+    pub fn rotate_180(&self) -> Self {
+        self.flip_horizontal().flip_vertical()
+    }
+
+    /// Mirrored left to right.
+    pub fn flip_horizontal(&self) -> Self {
+        let width = self.width;
+        self.remap(self.width, self.height, |row, col| (row, width - 1 - col))
+    }
+
+    /// Mirrored top to bottom.
+    pub fn flip_vertical(&self) -> Self {
+        let height = self.height;
+        self.remap(self.width, self.height, |row, col| (height - 1 - row, col))
+    }
+
+    /// Mirrored in the diagonal from the top left.
+    pub fn transpose(&self) -> Self {
+        self.remap(self.height, self.width, |row, col| (col, row))
+    }
+
+    /// A `width` by `height` copy with each live cell moved by `to`.
+    fn remap(
+        &self,
+        width: usize,
+        height: usize,
+        to: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Self {
+        let mut remapped = Pattern {
+            cells: vec![vec![false; width]; height],
+            width,
+            height,
+            ..self.clone()
+        };
+        for (row, cells) in self.cells.iter().enumerate() {
+            for (col, _) in cells.iter().enumerate().filter(|(_, &cell)| cell) {
+                let (row, col) = to(row, col);
+                remapped.cells[row][col] = true;
+            }
+        }
+        remapped
+    }
+}
+
+/// Reads `x = 3, y = 3, rule = B3/S23`, the rule being optional.
+fn parse_header(line: usize, header: &str) -> Result<(usize, usize, Option<Rule>), PatternError> {
+    // columns count chars, as they do in the body and in rules
+    let chars = |s: &str| s.chars().count();
+    let (mut width, mut height, mut rule) = (None, None, None);
+    let mut column = 1;
+    let mut rest = header;
+    while !rest.trim().is_empty() {
+        // the rule comes last and may have commas of its own
+        let end = if rest.trim_start().starts_with("rule") {
+            rest.len()
+        } else {
+            rest.find(',').unwrap_or(rest.len())
+        };
+        let field = &rest[..end];
+        let field_start = column;
+        let field_column = field_start + chars(field) - chars(field.trim_start());
+        column += chars(field) + 1;
+        rest = rest.get(end + 1..).unwrap_or("");
+        let Some((key, raw_value)) = field.split_once('=') else {
+            return Err(syntax(
+                line,
+                field_column,
+                format!("expected `key = value` but found `{}`", field.trim()),
+            ));
+        };
+        let value = raw_value.trim();
+        let value_column =
+            field_start + chars(key) + 1 + chars(raw_value) - chars(raw_value.trim_start());
+        match key.trim() {
+            "x" | "y" => {
+                let n = value.parse::<usize>().map_err(|_| {
+                    syntax(
+                        line,
+                        field_column,
+                        format!("{} = `{}` is not a whole number", key.trim(), value),
+                    )
+                })?;
+                if key.trim() == "x" {
+                    width = Some(n);
+                } else {
+                    height = Some(n);
+                }
+            }
+            "rule" => {
+                rule = Some(
+                    value
+                        .parse()
+                        .map_err(|source: RuleError| PatternError::Rule {
+                            line,
+                            rule: value.to_string(),
+                            source: source.shifted(value_column - 1),
+                        })?,
+                )
+            }
+            other => {
+                return Err(syntax(
+                    line,
+                    field_column,
+                    format!("unknown header field `{}`", other),
+                ))
+            }
+        }
+    }
+    match (width, height) {
+        (Some(width), Some(height)) if width.checked_mul(height).is_none_or(|n| n > MAX_CELLS) => {
+            Err(syntax(
+                line,
+                1,
+                format!(
+                    "a {} by {} pattern is larger than the {} cells allowed",
+                    width, height, MAX_CELLS
+                ),
+            ))
+        }
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        (None, _) => Err(syntax(line, 1, "the header has no `x = ...`")),
+        (_, None) => Err(syntax(line, 1, "the header has no `y = ...`")),
+    }
+}
+
+impl Universe {
+    /// Draws the live cells of `pattern` with its top left corner at `row`,
+    /// `col`, wrapping round the edges as the universe does. Dead cells
+    /// leave what's under them, so patterns can be layered.
+    pub fn place(&mut self, pattern: &Pattern, row: usize, col: usize) -> Result<(), PatternError> {
+        let (width, height) = self.dimensions();
+        if pattern.width > width || pattern.height > height {
+            return Err(PatternError::TooLarge {
+                pattern: pattern.dimensions(),
+                universe: (width, height),
+            });
+        }
+        for (r, cells) in pattern.cells.iter().enumerate() {
+            for (c, _) in cells.iter().enumerate().filter(|(_, &cell)| cell) {
+                self.set((row + r) % height, (col + c) % width, true);
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws `pattern` in the middle of the universe.
+    pub fn place_centered(&mut self, pattern: &Pattern) -> Result<(), PatternError> {
+        let (width, height) = self.dimensions();
+        let row = height.saturating_sub(pattern.height) / 2;
+        let col = width.saturating_sub(pattern.width) / 2;
+        self.place(pattern, row, col)
+    }
+
+    /// The `width` by `height` cells from `row`, `col`, wrapping round the
+    /// edges.
+    pub fn region(&self, row: usize, col: usize, width: usize, height: usize) -> Pattern {
+        let (universe_width, universe_height) = self.dimensions();
+        let mut pattern = Pattern::new(width, height);
+        for r in 0..height {
+            for c in 0..width {
+                pattern.cells[r][c] =
+                    self.is_alive((row + r) % universe_height, (col + c) % universe_width);
+            }
+        }
+        pattern
+    }
+
+    pub fn to_pattern(&self) -> Pattern {
+        let (width, height) = self.dimensions();
+        self.region(0, 0, width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER_RLE: &str =
+        "#N Glider\n#C The smallest spaceship.\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+    const GLIDER_CELLS: &str = "!Name: Glider\n!The smallest spaceship.\n.O.\n..O\nOOO\n";
+
+    fn rows(pattern: &Pattern) -> Vec<String> {
+        pattern
+            .to_cells()
+            .lines()
+            .filter(|line| !line.starts_with('!'))
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn syntax_at(result: Result<Pattern, PatternError>) -> (usize, usize, String) {
+        match result {
+            Err(PatternError::Syntax {
+                line,
+                column,
+                message,
+            }) => (line, column, message),
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rle_and_cells_round_trip() {
+        let from_rle = Pattern::from_rle(GLIDER_RLE).unwrap();
+        assert_eq!(from_rle.to_rle(), GLIDER_RLE);
+        // dead cells at the end of a row are optional
+        let padded = GLIDER_RLE.replace("bo$", "bob$");
+        assert_eq!(Pattern::from_rle(&padded).unwrap(), from_rle);
+        assert_eq!(from_rle.name(), Some("Glider"));
+        assert_eq!(from_rle.comments(), ["The smallest spaceship."]);
+        assert_eq!(from_rle.rule(), Some(Rule::LIFE));
+        assert_eq!((from_rle.dimensions(), from_rle.population()), ((3, 3), 5));
+
+        let from_cells = Pattern::from_cells(GLIDER_CELLS).unwrap();
+        assert_eq!(from_cells.to_cells(), GLIDER_CELLS);
+        // .cells files don't say what rule they're for
+        assert_eq!(from_cells.rule(), None);
+        assert_eq!(from_cells.with_rule(Rule::LIFE), from_rle);
+        assert_eq!(from_rle.to_cells(), GLIDER_CELLS);
+    }
+
+    #[test]
+    fn rle_keeps_blank_rows_and_wraps_long_lines() {
+        let mut pattern = Pattern::new(101, 5).with_rule(Rule::named("HighLife").unwrap());
+        for col in (0..101).step_by(2) {
+            pattern.set(1, col, true);
+        }
+        pattern.set(3, 100, true);
+        let rle = pattern.to_rle();
+        assert!(rle.lines().all(|line| line.len() <= RLE_LINE), "{}", rle);
+        assert!(rle.starts_with("x = 101, y = 5, rule = B36/S23\n$obo"));
+        assert!(rle.ends_with("2$100bo!\n"));
+        assert_eq!(Pattern::from_rle(&rle).unwrap(), pattern);
+
+        // rows after the last live one are left to the header
+        let mut sparse = Pattern::new(3, 3);
+        sparse.set(0, 0, true);
+        assert_eq!(sparse.to_rle(), "x = 3, y = 3, rule = B3/S23\no!\n");
+        assert_eq!(
+            Pattern::from_rle(&sparse.to_rle())
+                .unwrap()
+                .with_rule(Rule::LIFE),
+            sparse.with_rule(Rule::LIFE)
+        );
+    }
+
+    #[test]
+    fn reads_lenient_rle() {
+        let pattern = Pattern::from_rle("\n#C\n  x=2,y=2\n o\n b $\n2o ! trailing text").unwrap();
+        assert_eq!(rows(&pattern), ["O.", "OO"]);
+        assert_eq!(pattern.comments(), [""]);
+        assert_eq!(pattern.rule(), None);
+        let cells = Pattern::from_cells("!no name\n.*\nO\n\n").unwrap();
+        assert_eq!(rows(&cells), [".O", "O.", ".."]);
+    }
+
+    #[test]
+    fn transforms_move_every_cell() {
+        let l = Pattern::from_cells("OO.\nO..\n").unwrap();
+        assert_eq!(rows(&l.rotate_cw()), ["OO", ".O", ".."]);
+        assert_eq!(rows(&l.rotate_ccw()), ["..", "O.", "OO"]);
+        assert_eq!(rows(&l.rotate_180()), ["..O", ".OO"]);
+        assert_eq!(rows(&l.flip_horizontal()), [".OO", "..O"]);
+        assert_eq!(rows(&l.flip_vertical()), ["O..", "OO."]);
+        assert_eq!(rows(&l.transpose()), ["OO", "O.", ".."]);
+
+        let glider = Pattern::from_rle(GLIDER_RLE).unwrap();
+        let turned = glider.rotate_cw().rotate_cw().rotate_cw().rotate_cw();
+        assert_eq!(turned, glider);
+        assert_eq!(glider.rotate_cw().rotate_ccw(), glider);
+        assert_eq!(glider.rotate_cw().rotate_cw(), glider.rotate_180());
+        assert_eq!(glider.transpose().transpose(), glider);
+        assert_eq!(
+            glider.flip_horizontal().flip_vertical(),
+            glider.rotate_180()
+        );
+        // the name and rule go along with the cells
+        assert_eq!(turned.name(), Some("Glider"));
+    }
+
+    #[test]
+    fn header_errors_point_at_their_field() {
+        assert_eq!(
+            syntax_at(Pattern::from_rle("#C no header\n")),
+            (1, 1, "there's no `x = ..., y = ...` header".to_string())
+        );
+        assert_eq!(
+            syntax_at(Pattern::from_rle("x = 3, z = 3\n!")),
+            (1, 8, "unknown header field `z`".to_string())
+        );
+        assert_eq!(
+            syntax_at(Pattern::from_rle("#N name\nx = 3,  y = a\n!")),
+            (2, 9, "y = `a` is not a whole number".to_string())
+        );
+        assert_eq!(
+            syntax_at(Pattern::from_rle("x = 3, 3\n!")),
+            (1, 8, "expected `key = value` but found `3`".to_string())
+        );
+        assert_eq!(
+            syntax_at(Pattern::from_rle("x = 3\n!")),
+            (1, 1, "the header has no `y = ...`".to_string())
+        );
+        // columns count chars, so a no-break space is one column, not two
+        assert_eq!(
+            syntax_at(Pattern::from_rle("\u{a0}x = 3, y = a\n!")),
+            (1, 9, "y = `a` is not a whole number".to_string())
+        );
+    }
+
+    #[test]
+    fn oversized_headers_are_refused_before_allocating() {
+        assert_eq!(
+            syntax_at(Pattern::from_rle("x = 100000000, y = 100000000\n!")),
+            (
+                1,
+                1,
+                format!(
+                    "a 100000000 by 100000000 pattern is larger than the {} cells allowed",
+                    MAX_CELLS
+                )
+            )
+        );
+        let overflowing = format!("x = {}, y = 2\n!", usize::MAX);
+        assert!(syntax_at(Pattern::from_rle(&overflowing))
+            .2
+            .contains("is larger than"));
+        let widest = format!("x = {}, y = 1\n!", MAX_CELLS);
+        assert_eq!(
+            Pattern::from_rle(&widest).map(|p| p.dimensions()),
+            Ok((MAX_CELLS, 1))
+        );
+    }
+
+    #[test]
+    fn rule_errors_count_columns_along_the_line() {
+        let error = Pattern::from_rle("x = 2, y = 2, rule = B3/S9\n!").unwrap_err();
+        assert_eq!(
+            error,
+            PatternError::Rule {
+                line: 1,
+                rule: "B3/S9".to_string(),
+                source: RuleError::Count {
+                    column: 26,
+                    found: '9'
+                },
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 1: rule `B3/S9`: column 26: `9` is not a neighbour count from 0 to 8"
+        );
+        let error = Pattern::from_rle("#C\nx=2,y=2,rule=  B3S23\n!").unwrap_err();
+        assert!(matches!(
+            error,
+            PatternError::Rule {
+                line: 2,
+                source: RuleError::Separator { column: 18, .. },
+                ..
+            }
+        ));
+        let error = Pattern::from_rle("x = 2,\u{a0}y = 2, rule = B3/S9\n!").unwrap_err();
+        assert!(matches!(
+            error,
+            PatternError::Rule {
+                source: RuleError::Count { column: 26, .. },
+                ..
+            }
+        ));
+        let error = Pattern::from_rle("x = 2, y = 2, rule = B3/\n!").unwrap_err();
+        assert!(matches!(
+            error,
+            PatternError::Rule {
+                source: RuleError::MissingPart('S'),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn body_errors_point_at_their_cell() {
+        let cases = [
+            ("x = 2, y = 1\n3o!", (2, 2, "row 1 is wider than x = 2")),
+            (
+                "x = 2, y = 1\no$o!",
+                (2, 3, "there are more rows than y = 1"),
+            ),
+            (
+                "x = 2, y = 1\n2A!",
+                (2, 2, "`A` is a multi-state cell, which Life doesn't have"),
+            ),
+            (
+                "x = 2, y = 1\no?!",
+                (2, 2, "expected `b`, `o`, `$` or `!` but found `?`"),
+            ),
+            (
+                "x = 2, y = 1\noo",
+                (2, 3, "the pattern doesn't end with `!`"),
+            ),
+            ("x = 2, y = 1", (1, 13, "the pattern doesn't end with `!`")),
+            (
+                "x = 2, y = 1\n\n99999999999999999999999o!",
+                (3, 20, "the run count is too large"),
+            ),
+        ];
+        for (text, (line, column, message)) in cases {
+            assert_eq!(
+                syntax_at(Pattern::from_rle(text)),
+                (line, column, message.to_string()),
+                "{:?}",
+                text
+            );
+        }
+        assert_eq!(
+            syntax_at(Pattern::from_cells("!x\n.O\n.X\n")),
+            (3, 2, "expected `.` or `O` but found `X`".to_string())
+        );
+    }
+
+    #[test]
+    fn places_and_reads_back_with_wrapping() {
+        let glider = Pattern::from_rle(GLIDER_RLE).unwrap();
+        let mut universe = Universe::new(5, 4);
+        universe.place(&glider, 3, 4).unwrap();
+        assert_eq!(universe.population(), 5);
+        assert!(universe.is_alive(3, 0) && universe.is_alive(0, 1) && universe.is_alive(1, 4));
+        assert_eq!(rows(&universe.region(3, 4, 3, 3)), rows(&glider));
+
+        let mut universe = Universe::new(7, 7);
+        universe.place_centered(&glider).unwrap();
+        assert_eq!(rows(&universe.region(2, 2, 3, 3)), rows(&glider));
+        assert_eq!(universe.to_pattern().population(), 5);
+
+        assert_eq!(
+            Universe::new(2, 8).place(&glider, 0, 0),
+            Err(PatternError::TooLarge {
+                pattern: (3, 3),
+                universe: (2, 8)
+            })
+        );
+    }
+}
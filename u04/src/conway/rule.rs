@@ -0,0 +1,445 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A Life-like rule: which live-neighbour counts bring a dead cell to life
+/// and which keep a live one alive. Each is a bit set over 0 to 8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RuleError {
+    #[error("the rule is empty")]
+    Empty,
+    #[error("column {column}: expected `B` or `S` but found `{found}`")]
+    Part { column: usize, found: char },
+    #[error("column {column}: `{part}` appears twice")]
+    RepeatedPart { column: usize, part: char },
+    #[error("there's no `{0}` part")]
+    MissingPart(char),
+    #[error("column {column}: expected `/` between the parts but found `{found}`")]
+    Separator { column: usize, found: char },
+    #[error("column {column}: `{found}` is not a neighbour count from 0 to 8")]
+    Count { column: usize, found: char },
+    #[error("column {column}: {count} is listed twice")]
+    RepeatedCount { column: usize, count: u8 },
+    #[error("unknown rule name `{0}`")]
+    UnknownName(String),
+}
+
+impl RuleError {
+    /// The same error with its column, if it has one, moved `by` columns
+    /// right, for a rule read from partway along a line.
+    pub(super) fn shifted(self, by: usize) -> Self {
+        match self {
+            RuleError::Part { column, found } => RuleError::Part {
+                column: column + by,
+                found,
+            },
+            RuleError::RepeatedPart { column, part } => RuleError::RepeatedPart {
+                column: column + by,
+                part,
+            },
+            RuleError::Separator { column, found } => RuleError::Separator {
+                column: column + by,
+                found,
+            },
+            RuleError::Count { column, found } => RuleError::Count {
+                column: column + by,
+                found,
+            },
+            RuleError::RepeatedCount { column, count } => RuleError::RepeatedCount {
+                column: column + by,
+                count,
+            },
+            other => other,
+        }
+    }
+}
+
+impl Rule {
+    /// Conway's Game of Life, B3/S23.
+    pub const LIFE: Rule = Rule::from_counts(&[3], &[2, 3]);
+
+    /// Builds a rule from neighbour counts; counts above 8 are ignored.
+    pub const fn from_counts(birth: &[u8], survival: &[u8]) -> Self {
+        Self {
+            birth: mask(birth),
+            survival: mask(survival),
+        }
+    }
+
+    /// Looks `name` up in `CATALOGUE`, ignoring case, or else reads it as
+    /// B/S notation.
+    pub fn named(name: &str) -> Result<Self, RuleError> {
+        if let Some(named) = CATALOGUE
+            .iter()
+            .find(|named| named.name.eq_ignore_ascii_case(name.trim()))
+        {
+            return Ok(named.rule);
+        }
+        let name = name.trim();
+        match name.parse() {
+            // a bare word is more likely a misspelt name than a bad rule
+            Err(RuleError::Part { .. }) if name.chars().all(|c| c.is_alphabetic() || c == ' ') => {
+                Err(RuleError::UnknownName(name.to_string()))
+            }
+            result => result,
+        }
+    }
+
+    pub fn births(&self, neighbours: usize) -> bool {
+        neighbours <= 8 && self.birth & (1 << neighbours) != 0
+    }
+
+    pub fn survives(&self, neighbours: usize) -> bool {
+        neighbours <= 8 && self.survival & (1 << neighbours) != 0
+    }
+
+    pub fn next_state(&self, alive: bool, neighbours: usize) -> bool {
+        if alive {
+            self.survives(neighbours)
+        } else {
+            self.births(neighbours)
+        }
+    }
+
+    /// The catalogue name for this rule, if it has one.
+    pub fn name(&self) -> Option<&'static str> {
+        CATALOGUE
+            .iter()
+            .find(|named| named.rule == *self)
+            .map(|named| named.name)
+    }
+}
+
+const fn mask(counts: &[u8]) -> u16 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < counts.len() {
+        if counts[i] <= 8 {
+            mask |= 1 << counts[i];
+        }
+        i += 1;
+    }
+    mask
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::LIFE
+    }
+}
+
+/// Reads B/S notation such as `B3/S23` or `B2/S`. The parts may come in
+/// either order and either case. Plain digits, `23/3`, are read as the
+/// older survival/birth form.
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(RuleError::Empty);
+        }
+        if s.chars().all(|c| c.is_ascii_digit() || c == '/') {
+            let Some((survival, birth)) = s.split_once('/') else {
+                return Err(RuleError::MissingPart('B'));
+            };
+            return Ok(Self {
+                birth: counts(birth, survival.len() + 2)?,
+                survival: counts(survival, 1)?,
+            });
+        }
+        let mut birth = None;
+        let mut survival = None;
+        let mut chars = s.chars().enumerate().map(|(i, c)| (i + 1, c)).peekable();
+        while let Some((column, part)) = chars.next() {
+            let slot = match part.to_ascii_uppercase() {
+                'B' => &mut birth,
+                'S' => &mut survival,
+                found => return Err(RuleError::Part { column, found }),
+            };
+            if slot.is_some() {
+                return Err(RuleError::RepeatedPart {
+                    column,
+                    part: part.to_ascii_uppercase(),
+                });
+            }
+            let mut counts = 0u16;
+            while let Some(&(column, c)) = chars.peek() {
+                if c == '/' {
+                    chars.next();
+                    break;
+                }
+                let count = match c.to_digit(10) {
+                    Some(n) if n <= 8 => n as u8,
+                    _ if matches!(c.to_ascii_uppercase(), 'B' | 'S') => {
+                        return Err(RuleError::Separator { column, found: c })
+                    }
+                    _ => return Err(RuleError::Count { column, found: c }),
+                };
+                if counts & (1 << count) != 0 {
+                    return Err(RuleError::RepeatedCount { column, count });
+                }
+                counts |= 1 << count;
+                chars.next();
+            }
+            *slot = Some(counts);
+        }
+        Ok(Self {
+            birth: birth.ok_or(RuleError::MissingPart('B'))?,
+            survival: survival.ok_or(RuleError::MissingPart('S'))?,
+        })
+    }
+}
+
+/// Neighbour counts written as digits, starting at `first_column`.
+fn counts(digits: &str, first_column: usize) -> Result<u16, RuleError> {
+    let mut counts = 0u16;
+    for (i, c) in digits.chars().enumerate() {
+        let column = first_column + i;
+        let count = match c.to_digit(10) {
+            Some(n) if n <= 8 => n as u8,
+            _ => return Err(RuleError::Count { column, found: c }),
+        };
+        if counts & (1 << count) != 0 {
+            return Err(RuleError::RepeatedCount { column, count });
+        }
+        counts |= 1 << count;
+    }
+    Ok(counts)
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = |mask: u16| -> String {
+            (0..=8u8)
+                .filter(|n| mask & (1 << n) != 0)
+                .map(|n| char::from(b'0' + n))
+                .collect()
+        };
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamedRule {
+    pub name: &'static str,
+    pub rule: Rule,
+}
+
+const fn named(name: &'static str, birth: &[u8], survival: &[u8]) -> NamedRule {
+    NamedRule {
+        name,
+        rule: Rule::from_counts(birth, survival),
+    }
+}
+
+/// Well-known Life-like rules, by the names LifeWiki gives them.
+pub const CATALOGUE: [NamedRule; 16] = [
+    named("Life", &[3], &[2, 3]),
+    named("HighLife", &[3, 6], &[2, 3]),
+    named("Seeds", &[2], &[]),
+    named("Day & Night", &[3, 6, 7, 8], &[3, 4, 6, 7, 8]),
+    named("Life without Death", &[3], &[0, 1, 2, 3, 4, 5, 6, 7, 8]),
+    named("Replicator", &[1, 3, 5, 7], &[1, 3, 5, 7]),
+    named("Fredkin", &[1, 3, 5, 7], &[0, 2, 4, 6, 8]),
+    named("Maze", &[3], &[1, 2, 3, 4, 5]),
+    named("Mazectric", &[3], &[1, 2, 3, 4]),
+    named("2x2", &[3, 6], &[1, 2, 5]),
+    named("Diamoeba", &[3, 5, 6, 7, 8], &[5, 6, 7, 8]),
+    named("Morley", &[3, 6, 8], &[2, 4, 5]),
+    named("Anneal", &[4, 6, 7, 8], &[3, 5, 6, 7, 8]),
+    named("Coral", &[3], &[4, 5, 6, 7, 8]),
+    named("Serviettes", &[2, 3, 4], &[]),
+    named("DryLife", &[3, 7], &[2, 3]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::super::{from_seed, Universe};
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn universe(width: usize, height: usize, cells: &[(usize, usize)]) -> Universe {
+        let mut universe = Universe::new(width, height);
+        for &(row, col) in cells {
+            universe.set(row, col, true);
+        }
+        universe
+    }
+
+    fn live_cells(universe: &Universe) -> Vec<(usize, usize)> {
+        let (width, height) = universe.dimensions();
+        (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .filter(|&(row, col)| universe.is_alive(row, col))
+            .collect()
+    }
+
+    #[test]
+    fn catalogue_round_trips_through_text() {
+        for named in &CATALOGUE {
+            let text = named.rule.to_string();
+            assert_eq!(text.parse::<Rule>(), Ok(named.rule), "{}", named.name);
+            assert_eq!(Rule::named(&text), Ok(named.rule), "{}", named.name);
+            assert_eq!(Rule::named(named.name), Ok(named.rule));
+            assert_eq!(Rule::named(&named.name.to_uppercase()), Ok(named.rule));
+            assert_eq!(named.rule.name(), Some(named.name));
+        }
+    }
+
+    #[test]
+    fn reads_every_spelling_of_life() {
+        for text in ["B3/S23", "b3/s23", "S23/B3", " B3/S23 ", "23/3", "Life"] {
+            assert_eq!(Rule::named(text), Ok(Rule::LIFE), "{}", text);
+        }
+        assert_eq!(Rule::default(), Rule::LIFE);
+        assert_eq!("B2/S".parse::<Rule>().map(|r| r.name()), Ok(Some("Seeds")));
+        assert_eq!(
+            "B36/S23".parse::<Rule>().map(|r| r.to_string()),
+            Ok("B36/S23".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_where_a_rule_goes_wrong() {
+        let cases = [
+            (
+                "B9/S23",
+                RuleError::Count {
+                    column: 2,
+                    found: '9',
+                },
+            ),
+            (
+                "B33/S23",
+                RuleError::RepeatedCount {
+                    column: 3,
+                    count: 3,
+                },
+            ),
+            (
+                "B3S23",
+                RuleError::Separator {
+                    column: 3,
+                    found: 'S',
+                },
+            ),
+            ("B3/", RuleError::MissingPart('S')),
+            (
+                "X3/S23",
+                RuleError::Part {
+                    column: 1,
+                    found: 'X',
+                },
+            ),
+            (
+                "B3/B3",
+                RuleError::RepeatedPart {
+                    column: 4,
+                    part: 'B',
+                },
+            ),
+            (
+                "23/39",
+                RuleError::Count {
+                    column: 5,
+                    found: '9',
+                },
+            ),
+            ("", RuleError::Empty),
+        ];
+        for (text, error) in cases {
+            assert_eq!(text.parse::<Rule>(), Err(error), "{}", text);
+        }
+        assert_eq!(
+            Rule::named("HighLyfe"),
+            Err(RuleError::UnknownName("HighLyfe".to_string()))
+        );
+        assert_eq!(
+            RuleError::Count {
+                column: 2,
+                found: '9'
+            }
+            .to_string(),
+            "column 2: `9` is not a neighbour count from 0 to 8"
+        );
+    }
+
+    #[test]
+    fn highlife_replicator_copies_itself() {
+        let replicator = [
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 1),
+            (1, 4),
+            (2, 0),
+            (2, 4),
+            (3, 0),
+            (3, 3),
+            (4, 0),
+            (4, 1),
+            (4, 2),
+        ];
+        let placed = |dr: usize, dc: usize| -> Vec<(usize, usize)> {
+            let mut cells: Vec<_> = replicator.iter().map(|&(r, c)| (r + dr, c + dc)).collect();
+            cells.sort();
+            cells
+        };
+        let highlife = Rule::named("HighLife").unwrap();
+        let mut sim = from_seed(universe(64, 64, &placed(30, 30))).with_rule(highlife);
+        assert_eq!(sim.universe().population(), 12);
+        for _ in 0..12 {
+            sim.next();
+        }
+        assert_eq!(sim.universe().population(), 24);
+        let mut copies = placed(28, 28);
+        copies.extend(placed(32, 32));
+        copies.sort();
+        assert_eq!(live_cells(sim.universe()), copies);
+    }
+
+    #[test]
+    fn seeds_cells_live_for_one_generation() {
+        let seeds = Rule::named("Seeds").unwrap();
+        let mut sim = from_seed(universe(8, 8, &[(2, 2), (3, 2)])).with_rule(seeds);
+        sim.next();
+        assert_eq!(live_cells(sim.universe()), [(2, 1), (2, 3), (3, 1), (3, 3)]);
+        // a lone cell has no one to pair with
+        let mut sim = from_seed(universe(8, 8, &[(4, 4)])).with_rule(seeds);
+        sim.next();
+        assert_eq!(sim.universe().population(), 0);
+    }
+
+    #[test]
+    fn day_and_night_treats_live_and_dead_alike() {
+        let rule = Rule::named("Day & Night").unwrap();
+        let mut rng = StdRng::seed_from_u64(47);
+        let (width, height) = (24, 16);
+        let mut cells = Universe::new(width, height);
+        let mut inverse = Universe::new(width, height);
+        for row in 0..height {
+            for col in 0..width {
+                let alive = rng.gen_bool(0.4);
+                cells.set(row, col, alive);
+                inverse.set(row, col, !alive);
+            }
+        }
+        let mut sim = from_seed(cells).with_rule(rule);
+        let mut inverse_sim = from_seed(inverse).with_rule(rule);
+        for _ in 0..10 {
+            sim.next();
+            inverse_sim.next();
+            let (a, b) = (sim.universe(), inverse_sim.universe());
+            assert!((0..height)
+                .all(|row| (0..width).all(|col| a.is_alive(row, col) != b.is_alive(row, col))));
+        }
+        assert!(sim.universe().population() > 0);
+    }
+}
@@ -38,27 +38,27 @@ impl std::hash::Hash for Hf64 {
 
 impl std::cmp::PartialOrd for Hf64 {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::cmp::Ord for Hf64 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         use std::cmp::Ordering::*;
         if self.0.is_nan() {
             if other.0.is_nan() {
-                Some(Equal)
+                Equal
             } else {
-                Some(Less)
+                Less
             }
         } else if other.0.is_nan() {
-            Some(Greater)
+            Greater
         } else {
-            self.0.partial_cmp(&other.0)
+            self.0.partial_cmp(&other.0).unwrap_or(Less)
         }
     }
 }
 
-impl std::cmp::Ord for Hf64 {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap_or(std::cmp::Ordering::Less)
-    }
-}
-
 impl std::fmt::Display for Hf64 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -165,6 +165,7 @@ pub fn explore_slicing() {
 pub fn explore_chessboard() {
     let board = ChessBoard::at_start();
     println!("{}", board);
+    println!("{} legal moves", board.legal_moves().len());
 }
 
 pub fn explore_multidimensional_arrays() {
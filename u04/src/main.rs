@@ -1,13 +1,39 @@
-use std::io;
-use u04::conway;
+use std::{env, fs, io, process};
+use u04::conway::{self, ConwaySim, Pattern, Rule, Universe};
+
+/// A random universe under a rule, or a pattern read from a file.
+fn setup(argument: Option<String>) -> Result<ConwaySim, String> {
+    let Some(argument) = argument else {
+        return Ok(conway::random(80, 20));
+    };
+    let read = if argument.ends_with(".rle") {
+        Pattern::from_rle
+    } else if argument.ends_with(".cells") {
+        Pattern::from_cells
+    } else {
+        let rule = Rule::named(&argument).map_err(|e| e.to_string())?;
+        return Ok(conway::random(80, 20).with_rule(rule));
+    };
+    let text = fs::read_to_string(&argument).map_err(|e| format!("{}: {}", argument, e))?;
+    let pattern = read(&text).map_err(|e| format!("{}: {}", argument, e))?;
+    let mut universe = Universe::new(80, 20);
+    universe
+        .place_centered(&pattern)
+        .map_err(|e| format!("{}: {}", argument, e))?;
+    Ok(conway::from_seed(universe).with_rule(pattern.rule().unwrap_or_default()))
+}
 
 fn main() -> io::Result<()> {
-    let mut sim = conway::random(80, 20);
+    let mut sim = setup(env::args().nth(1)).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(2);
+    });
 
+    println!("generation {}\n{}", sim.generation(), sim.universe());
     for _ in 0..100 {
         std::thread::sleep(std::time::Duration::from_millis(300));
         sim.next();
-        println!("{}", sim.universe());
+        println!("generation {}\n{}", sim.generation(), sim.universe());
     }
     Ok(())
 }
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use quantities::{Length, LengthUnit};
 use serde::{ser::SerializeStruct, Serialize, Serializer};
 
 #[derive(Debug)]
@@ -34,35 +35,41 @@ impl Gps {
         }
     }
 
-    pub fn distance(&self) -> f64 {
+    pub fn distance(&self) -> Length {
         self.world.distance(&self.current, &self.destination)
     }
 
     pub fn message(&self) -> String {
         format!(
-            "{:.1} km to {}",
-            self.distance(),
+            "{:.1} to {}",
+            self.distance().in_unit(LengthUnit::Kilometer),
             self.destination.description()
         )
     }
 }
 
 pub fn earth() -> World {
-    World::new("Earth".to_string(), 6_371.0)
+    World::new(
+        "Earth".to_string(),
+        Length::new(6_371.0, LengthUnit::Kilometer),
+    )
 }
 
 pub fn mars() -> World {
-    World::new("Mars".to_string(), 3_389.5)
+    World::new(
+        "Mars".to_string(),
+        Length::new(3_389.5, LengthUnit::Kilometer),
+    )
 }
 
 #[derive(Debug, Clone)]
 pub struct World {
     name: String,
-    radius: f64,
+    radius: Length,
 }
 
 impl World {
-    pub fn new(name: String, radius: f64) -> Self {
+    pub fn new(name: String, radius: Length) -> Self {
         Self { name, radius }
     }
 
@@ -70,7 +77,11 @@ impl World {
         &self.name
     }
 
-    pub fn distance(&self, a: &Location, b: &Location) -> f64 {
+    pub fn radius(&self) -> Length {
+        self.radius
+    }
+
+    pub fn distance(&self, a: &Location, b: &Location) -> Length {
         // println!(
         //     "a: {} ({}, {})",
         //     a,
@@ -1,3 +1,4 @@
+use quantities::LengthUnit;
 use serde::Serialize;
 use std::io::{self, Write};
 use u05::world::{self, Coordinate, Gps, Hemisphere, Location, Rover};
@@ -58,7 +59,10 @@ pub fn explore_struct_methods() {
         Location::from_decimal_degrees(-1.9462, 354.4734, "Challenger Memorial Station".to_owned());
 
     let dist = mars.distance(&spirit, &opportunity);
-    println!("Spirit and Opportunity are {:.2} km apart.", dist);
+    println!(
+        "Spirit and Opportunity are {:.2} apart.",
+        dist.in_unit(LengthUnit::Kilometer)
+    );
 
     let curiosity = Location::new(
         Coordinate::new(4, 35, 22.2, Hemisphere::SOUTH),
@@ -86,7 +90,12 @@ pub fn explore_struct_methods() {
     }
     distances.sort_by(|(_, _, d1), (_, _, d2)| d1.partial_cmp(d2).unwrap());
     for (x, y, dist) in distances {
-        println!("{:15} {:15} {:6.1} km ", x, y, dist);
+        println!(
+            "{:15} {:15} {:6.1} ",
+            x,
+            y,
+            dist.in_unit(LengthUnit::Kilometer)
+        );
     }
 
     let earth = world::earth();
@@ -101,9 +110,11 @@ pub fn explore_struct_methods() {
         "Paris".to_owned(),
     );
     println!(
-        "{} is {:.1} km from {}.",
+        "{} is {:.1} from {}.",
         london.name(),
-        earth.distance(&london, &paris),
+        earth
+            .distance(&london, &paris)
+            .in_unit(LengthUnit::Kilometer),
         paris.name()
     );
 }